@@ -10,7 +10,9 @@ use tokio_stream::Stream;
 use super::spec;
 use crate::primitives::{
     registry::{
-        DeregistrationBatch, Lookahead, Operator, Registration, RegistrationBatch, RegistryEntry,
+        DeregistrationBatch, DiscoveryFilter, Lookahead, Operator, OperatorDeregistrationBatch,
+        OperatorSettingsUpdate, Registration, RegistrationBatch, RegistryEntry, RestakingProtocol,
+        RpcEndpointUpdate, SourceConflict, StakeSnapshot, WhitelistedCollateral,
     },
     BlsPublicKey,
 };
@@ -26,10 +28,19 @@ pub(crate) enum Action {
         deregistration: DeregistrationBatch,
         response: oneshot::Sender<Result<(), spec::RegistryError>>,
     },
+    OperatorDeregister {
+        deregistration: OperatorDeregistrationBatch,
+        response: oneshot::Sender<Result<(), spec::RegistryError>>,
+    },
+    UpdateOperatorSettings {
+        update: OperatorSettingsUpdate,
+        response: oneshot::Sender<Result<(), spec::RegistryError>>,
+    },
     GetRegistrations {
         response: oneshot::Sender<Result<Vec<Registration>, spec::RegistryError>>,
     },
     GetValidators {
+        filter: DiscoveryFilter,
         response: oneshot::Sender<Result<Vec<RegistryEntry>, spec::RegistryError>>,
     },
     GetValidatorsByPubkeys {
@@ -45,6 +56,7 @@ pub(crate) enum Action {
         response: oneshot::Sender<Result<RegistryEntry, spec::RegistryError>>,
     },
     GetOperators {
+        filter: DiscoveryFilter,
         response: oneshot::Sender<Result<Vec<Operator>, spec::RegistryError>>,
     },
     GetLookahead {
@@ -55,6 +67,26 @@ pub(crate) enum Action {
         signer: Address,
         response: oneshot::Sender<Result<Operator, spec::RegistryError>>,
     },
+    GetOperatorRpcHistory {
+        signer: Address,
+        response: oneshot::Sender<Result<Vec<RpcEndpointUpdate>, spec::RegistryError>>,
+    },
+    GetOperatorStakeHistory {
+        signer: Address,
+        response: oneshot::Sender<Result<Vec<StakeSnapshot>, spec::RegistryError>>,
+    },
+    GetOperatorStakeAt {
+        signer: Address,
+        epoch: u64,
+        response: oneshot::Sender<Result<StakeSnapshot, spec::RegistryError>>,
+    },
+    GetWhitelist {
+        protocol: Option<RestakingProtocol>,
+        response: oneshot::Sender<Result<Vec<WhitelistedCollateral>, spec::RegistryError>>,
+    },
+    GetSourceConflicts {
+        response: oneshot::Sender<Result<Vec<SourceConflict>, spec::RegistryError>>,
+    },
 }
 
 /// A stream of API actions ([`Action`]).
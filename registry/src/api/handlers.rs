@@ -6,23 +6,34 @@ use utoipa::OpenApi;
 
 use crate::primitives::{
     registry::{
-        Deregistration, DeregistrationBatch, Lookahead, Operator,
-        Registration, RegistrationBatch, RegistryEntry
+        DataSource, Deregistration, DeregistrationBatch, DiscoveryFilter, Lookahead, Operator,
+        OperatorDeregistrationBatch, OperatorMetadata, OperatorSettingsUpdate, Registration,
+        RegistrationBatch, RegistryEntry, RestakingProtocol, RpcEndpointUpdate, SourceClaim,
+        SourceConflict, StakeSnapshot, WhitelistedCollateral
     },
     BlsPublicKey,
 };
 
 use super::{
     DiscoverySpec,
+    OperatorSpec,
     RegistryApi,
     ValidatorFilter,
     ValidatorSpec,
+    DISCOVERY_CONFLICTS_PATH,
     DISCOVERY_LOOKAHEAD_PATH,
     DISCOVERY_OPERATORS_PATH,
+    DISCOVERY_OPERATOR_EPOCH_STAKE_PATH,
     DISCOVERY_OPERATOR_PATH,
+    DISCOVERY_OPERATOR_RPC_HISTORY_PATH,
+    DISCOVERY_OPERATOR_STAKE_PATH,
+    DISCOVERY_PROTOCOL_WHITELIST_PATH,
     DISCOVERY_VALIDATORS_PATH,
     DISCOVERY_VALIDATOR_PATH,
+    DISCOVERY_WHITELIST_PATH,
+    OPERATORS_SETTINGS_PATH,
     VALIDATORS_DEREGISTER_PATH,
+    VALIDATORS_OPERATOR_DEREGISTER_PATH,
     VALIDATORS_REGISTER_PATH,
     VALIDATORS_REGISTRATIONS_PATH,
 };
@@ -38,24 +49,45 @@ use super::{
         Deregistration,
         RegistryEntry,
         Operator,
+        OperatorMetadata,
         Lookahead,
         RegistrationBatch,
         DeregistrationBatch,
+        OperatorDeregistrationBatch,
+        OperatorSettingsUpdate,
+        RestakingProtocol,
+        DataSource,
+        RpcEndpointUpdate,
+        StakeSnapshot,
+        WhitelistedCollateral,
+        SourceConflict,
+        SourceClaim,
     )),
     paths(
         register,
         deregister,
+        operator_deregister,
         get_registrations,
+        update_operator_settings,
         get_validators,
         get_validator_by_pubkey,
         get_operators,
         get_operator_by_signer,
+        get_operator_rpc_history,
+        get_operator_stake_history,
+        get_operator_stake_at,
         get_lookahead,
+        get_whitelist,
+        get_whitelist_by_protocol,
+        get_source_conflicts,
     )
 )]
 pub(crate) struct ApiDoc;
 
 /// Registers a new validator.
+///
+/// Validators sign the registration either individually (`signatures`) or with a single
+/// `aggregate_signature`, which is cheaper to verify for large batches.
 #[utoipa::path(post, path = VALIDATORS_REGISTER_PATH, request_body = RegistrationBatch, responses(
     (status = 200, description = "Success")
 ))]
@@ -77,6 +109,20 @@ pub(crate) async fn deregister(
     api.deregister(deregistration).await
 }
 
+/// Deregisters validators on behalf of their operator.
+///
+/// The deregistration must be signed by the operator signer with EIP-712, and all validators must
+/// currently be registered to that operator.
+#[utoipa::path(post, path = VALIDATORS_OPERATOR_DEREGISTER_PATH, request_body = OperatorDeregistrationBatch, responses(
+    (status = 200, description = "Success")
+))]
+pub(crate) async fn operator_deregister(
+    State(api): State<Arc<RegistryApi>>,
+    Json(deregistration): Json<OperatorDeregistrationBatch>,
+) -> impl IntoResponse {
+    api.operator_deregister(deregistration).await
+}
+
 /// Gets all validator registrations.
 #[utoipa::path(get, path = VALIDATORS_REGISTRATIONS_PATH, responses(
     (status = 200, description = "Success", body = Vec<Registration>)
@@ -85,11 +131,27 @@ pub(crate) async fn get_registrations(State(api): State<Arc<RegistryApi>>) -> im
     api.get_registrations().await.map(Json)
 }
 
+/// Updates the settings of an operator.
+///
+/// The update must be signed by the operator signer with EIP-712. Operators can use this to
+/// require their acceptance signature on every registration that delegates to them.
+#[utoipa::path(post, path = OPERATORS_SETTINGS_PATH, request_body = OperatorSettingsUpdate, responses(
+    (status = 200, description = "Success")
+))]
+pub(crate) async fn update_operator_settings(
+    State(api): State<Arc<RegistryApi>>,
+    Json(update): Json<OperatorSettingsUpdate>,
+) -> impl IntoResponse {
+    api.update_operator_settings(update).await
+}
+
 /// Gets all validators.
 #[utoipa::path(get, path = DISCOVERY_VALIDATORS_PATH,
     params(
         ("pubkeys" = Option<Vec<BlsPublicKey>>, Query, description = "The public keys of the validators to get."),
         ("indices" = Option<Vec<u64>>, Query, description = "The indices of the validators to get."),
+        ("protocol" = Option<RestakingProtocol>, Query, description = "Only list validators of operators restaking through this protocol."),
+        ("source" = Option<DataSource>, Query, description = "Only list validators registered through this source."),
     ),
     responses(
         (status = 200, description = "Success", body = Vec<RegistryEntry>),
@@ -99,10 +161,11 @@ pub(crate) async fn get_validators(
     State(api): State<Arc<RegistryApi>>,
     Query(filter): Query<ValidatorFilter>,
 ) -> impl IntoResponse {
+    let discovery = filter.discovery();
     match (filter.pubkeys, filter.indices) {
         (Some(pubkeys), None) => api.get_validators_by_pubkeys(pubkeys).await.map(Json),
         (None, Some(indices)) => api.get_validators_by_indices(indices).await.map(Json),
-        _ => api.get_validators().await.map(Json),
+        _ => api.get_validators(discovery).await.map(Json),
     }
 }
 
@@ -124,11 +187,20 @@ pub(crate) async fn get_validator_by_pubkey(
 }
 
 /// Gets all operators.
-#[utoipa::path(get, path = DISCOVERY_OPERATORS_PATH, responses(
-    (status = 200, description = "Success", body = Vec<Operator>)
-))]
-pub(crate) async fn get_operators(State(api): State<Arc<RegistryApi>>) -> impl IntoResponse {
-    api.get_operators().await.map(Json)
+#[utoipa::path(get, path = DISCOVERY_OPERATORS_PATH,
+    params(
+        ("protocol" = Option<RestakingProtocol>, Query, description = "Only list operators restaking through this protocol."),
+        ("source" = Option<DataSource>, Query, description = "Only list operators from this source."),
+    ),
+    responses(
+        (status = 200, description = "Success", body = Vec<Operator>),
+    )
+)]
+pub(crate) async fn get_operators(
+    State(api): State<Arc<RegistryApi>>,
+    Query(filter): Query<DiscoveryFilter>,
+) -> impl IntoResponse {
+    api.get_operators(filter).await.map(Json)
 }
 
 /// Gets an operator by its signer.
@@ -148,6 +220,65 @@ pub(crate) async fn get_operator_by_signer(
     api.get_operator_by_signer(signer).await.map(Json)
 }
 
+/// Gets the RPC endpoint history of an operator.
+///
+/// Every entry carries the beacon chain epoch from which the registry served the endpoint, so
+/// the endpoint served for a slot is the last entry with an epoch at or before the slot epoch.
+#[utoipa::path(
+    get,
+    path = DISCOVERY_OPERATOR_RPC_HISTORY_PATH,
+    params(("signer" = String, description = "The address of the operator.")),
+    responses(
+        (status = 200, description = "Success", body = Vec<RpcEndpointUpdate>),
+    )
+)]
+pub(crate) async fn get_operator_rpc_history(
+    State(api): State<Arc<RegistryApi>>,
+    Path(signer): Path<Address>,
+) -> impl IntoResponse {
+    api.get_operator_rpc_history(signer).await.map(Json)
+}
+
+/// Gets the stake history of an operator.
+///
+/// The stake of every operator is snapshotted once per operators registry epoch, at the epoch
+/// start. EigenLayer stake is measured when the snapshot is taken instead, shortly after the
+/// epoch start. Snapshots are ordered by epoch.
+#[utoipa::path(
+    get,
+    path = DISCOVERY_OPERATOR_STAKE_PATH,
+    params(("signer" = String, description = "The address of the operator.")),
+    responses(
+        (status = 200, description = "Success", body = Vec<StakeSnapshot>),
+    )
+)]
+pub(crate) async fn get_operator_stake_history(
+    State(api): State<Arc<RegistryApi>>,
+    Path(signer): Path<Address>,
+) -> impl IntoResponse {
+    api.get_operator_stake_history(signer).await.map(Json)
+}
+
+/// Gets the stake of an operator at an operators registry epoch.
+#[utoipa::path(
+    get,
+    path = DISCOVERY_OPERATOR_EPOCH_STAKE_PATH,
+    params(
+        ("signer" = String, description = "The address of the operator."),
+        ("epoch" = u64, description = "The operators registry epoch."),
+    ),
+    responses(
+        (status = 200, description = "Success", body = StakeSnapshot),
+        (status = 404, description = "Not Found", body = String, example = "Not found"),
+    )
+)]
+pub(crate) async fn get_operator_stake_at(
+    State(api): State<Arc<RegistryApi>>,
+    Path((signer, epoch)): Path<(Address, u64)>,
+) -> impl IntoResponse {
+    api.get_operator_stake_at(signer, epoch).await.map(Json)
+}
+
 /// Gets the lookahead for an epoch.
 #[utoipa::path(
     get, 
@@ -162,4 +293,44 @@ pub(crate) async fn get_lookahead(
     Path(epoch): Path<u64>,
 ) -> impl IntoResponse {
     api.get_lookahead(epoch).await.map(Json)
-}
\ No newline at end of file
+}
+
+/// Gets the collateral whitelisted in the restaking middlewares.
+///
+/// Vaults (Symbiotic) and strategies (EigenLayer) are included with their pause status. Removed
+/// collateral is omitted.
+#[utoipa::path(get, path = DISCOVERY_WHITELIST_PATH, responses(
+    (status = 200, description = "Success", body = Vec<WhitelistedCollateral>)
+))]
+pub(crate) async fn get_whitelist(State(api): State<Arc<RegistryApi>>) -> impl IntoResponse {
+    api.get_whitelist().await.map(Json)
+}
+
+/// Gets the collateral whitelisted in the middleware of a restaking protocol.
+#[utoipa::path(
+    get,
+    path = DISCOVERY_PROTOCOL_WHITELIST_PATH,
+    params(("protocol" = RestakingProtocol, description = "The restaking protocol, `symbiotic` or `eigenlayer`.")),
+    responses(
+        (status = 200, description = "Success", body = Vec<WhitelistedCollateral>),
+    )
+)]
+pub(crate) async fn get_whitelist_by_protocol(
+    State(api): State<Arc<RegistryApi>>,
+    Path(protocol): Path<RestakingProtocol>,
+) -> impl IntoResponse {
+    api.get_whitelist_by_protocol(protocol).await.map(Json)
+}
+
+/// Gets the validators that external sources claim for different operators.
+///
+/// The claim of the source with the highest priority is registered. Conflicts are updated
+/// whenever the validators are queried again, i.e. when they are in the lookahead.
+#[utoipa::path(get, path = DISCOVERY_CONFLICTS_PATH, responses(
+    (status = 200, description = "Success", body = Vec<SourceConflict>)
+))]
+pub(crate) async fn get_source_conflicts(
+    State(api): State<Arc<RegistryApi>>,
+) -> impl IntoResponse {
+    api.get_source_conflicts().await.map(Json)
+}
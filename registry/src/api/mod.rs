@@ -13,7 +13,7 @@ use tokio::{
         mpsc::{self, error::SendTimeoutError},
         oneshot,
     },
-    task::JoinHandle,
+    task::{spawn_blocking, JoinHandle},
 };
 use tower_http::{cors::CorsLayer, timeout::TimeoutLayer, trace::TraceLayer};
 use tracing::error;
@@ -23,8 +23,12 @@ use utoipa_swagger_ui::SwaggerUi;
 
 use crate::primitives::{
     registry::{
-        DeregistrationBatch, Lookahead, Operator, Registration, RegistrationBatch, RegistryEntry,
+        DataSource, DeregistrationBatch, DiscoveryFilter, Lookahead, Operator,
+        OperatorDeregistrationBatch, OperatorSettingsUpdate, Registration, RegistrationBatch,
+        RegistryEntry, RestakingProtocol, RpcEndpointUpdate, SourceConflict, StakeSnapshot,
+        WhitelistedCollateral,
     },
+    signing::SigningContext,
     BlsPublicKey,
 };
 
@@ -38,14 +42,20 @@ use actions::{Action, ActionStream};
 /// API specification and traits.
 pub(crate) mod spec;
 use spec::{
-    DiscoverySpec, ValidatorSpec, DISCOVERY_LOOKAHEAD_PATH, DISCOVERY_OPERATORS_PATH,
-    DISCOVERY_OPERATOR_PATH, DISCOVERY_VALIDATORS_PATH, DISCOVERY_VALIDATOR_PATH,
-    VALIDATORS_DEREGISTER_PATH, VALIDATORS_REGISTER_PATH, VALIDATORS_REGISTRATIONS_PATH,
+    DiscoverySpec, OperatorSpec, ValidatorSpec, DISCOVERY_CONFLICTS_PATH, DISCOVERY_LOOKAHEAD_PATH,
+    DISCOVERY_OPERATORS_PATH, DISCOVERY_OPERATOR_EPOCH_STAKE_PATH, DISCOVERY_OPERATOR_PATH,
+    DISCOVERY_OPERATOR_RPC_HISTORY_PATH, DISCOVERY_OPERATOR_STAKE_PATH,
+    DISCOVERY_PROTOCOL_WHITELIST_PATH, DISCOVERY_VALIDATORS_PATH, DISCOVERY_VALIDATOR_PATH,
+    DISCOVERY_WHITELIST_PATH, OPERATORS_SETTINGS_PATH, VALIDATORS_DEREGISTER_PATH,
+    VALIDATORS_OPERATOR_DEREGISTER_PATH, VALIDATORS_REGISTER_PATH, VALIDATORS_REGISTRATIONS_PATH,
 };
 
 /// The registry API server, implementing the [`spec::ApiSpec`] trait.
 pub(crate) struct RegistryApi {
     cfg: ApiConfig,
+    /// The signing context of the network, used to verify validator signatures before requests
+    /// are sent to the registry.
+    signing: SigningContext,
     /// Sender notifying event listeners of API events.
     tx: mpsc::Sender<Action>,
 }
@@ -75,16 +85,25 @@ impl Default for ApiConfig {
 struct ValidatorFilter {
     pubkeys: Option<Vec<BlsPublicKey>>,
     indices: Option<Vec<u64>>,
+    protocol: Option<RestakingProtocol>,
+    source: Option<DataSource>,
+}
+
+impl ValidatorFilter {
+    /// Returns the discovery filter applied when listing all validators.
+    const fn discovery(&self) -> DiscoveryFilter {
+        DiscoveryFilter { protocol: self.protocol, source: self.source }
+    }
 }
 
 impl RegistryApi {
     /// Creates a new API server with the given configuration. Returns the server that can be
     /// spawned with [`RegistryApi::spawn`], and the action stream on which API queries and commands
-    /// are sent.
-    pub(crate) fn new(config: ApiConfig) -> (Self, ActionStream) {
+    /// are sent. Validator signatures are verified against the given network [`SigningContext`].
+    pub(crate) fn new(config: ApiConfig, signing: SigningContext) -> (Self, ActionStream) {
         let (tx, rx) = mpsc::channel(config.action_buffer);
 
-        let api = Self { cfg: config, tx };
+        let api = Self { cfg: config, signing, tx };
         let stream = ActionStream::new(rx);
 
         (api, stream)
@@ -99,12 +118,20 @@ impl RegistryApi {
         let (router, api_docs) = OpenApiRouter::with_openapi(handlers::ApiDoc::openapi())
             .route(VALIDATORS_REGISTER_PATH, post(handlers::register))
             .route(VALIDATORS_DEREGISTER_PATH, post(handlers::deregister))
+            .route(VALIDATORS_OPERATOR_DEREGISTER_PATH, post(handlers::operator_deregister))
             .route(VALIDATORS_REGISTRATIONS_PATH, get(handlers::get_registrations))
+            .route(OPERATORS_SETTINGS_PATH, post(handlers::update_operator_settings))
             .route(DISCOVERY_VALIDATORS_PATH, get(handlers::get_validators))
             .route(DISCOVERY_VALIDATOR_PATH, get(handlers::get_validator_by_pubkey))
             .route(DISCOVERY_OPERATORS_PATH, get(handlers::get_operators))
             .route(DISCOVERY_OPERATOR_PATH, get(handlers::get_operator_by_signer))
+            .route(DISCOVERY_OPERATOR_RPC_HISTORY_PATH, get(handlers::get_operator_rpc_history))
+            .route(DISCOVERY_OPERATOR_STAKE_PATH, get(handlers::get_operator_stake_history))
+            .route(DISCOVERY_OPERATOR_EPOCH_STAKE_PATH, get(handlers::get_operator_stake_at))
             .route(DISCOVERY_LOOKAHEAD_PATH, get(handlers::get_lookahead))
+            .route(DISCOVERY_WHITELIST_PATH, get(handlers::get_whitelist))
+            .route(DISCOVERY_PROTOCOL_WHITELIST_PATH, get(handlers::get_whitelist_by_protocol))
+            .route(DISCOVERY_CONFLICTS_PATH, get(handlers::get_source_conflicts))
             .with_state(state)
             .split_for_parts();
 
@@ -138,6 +165,13 @@ impl RegistryApi {
 impl spec::ValidatorSpec for RegistryApi {
     #[tracing::instrument(skip(self))]
     async fn register(&self, registration: RegistrationBatch) -> Result<(), spec::RegistryError> {
+        // Signature verification is CPU-heavy for large batches, so it runs on the blocking thread
+        // pool for each request instead of in the registry, which handles actions sequentially.
+        let signing = self.signing;
+        let registration =
+            spawn_blocking(move || verify_registration_signatures(registration, &signing))
+                .await??;
+
         let (tx, rx) = oneshot::channel();
 
         let action = Action::Register { registration, response: tx };
@@ -151,6 +185,11 @@ impl spec::ValidatorSpec for RegistryApi {
         &self,
         deregistration: DeregistrationBatch,
     ) -> Result<(), spec::RegistryError> {
+        let signing = self.signing;
+        let deregistration =
+            spawn_blocking(move || verify_deregistration_signatures(deregistration, &signing))
+                .await??;
+
         let (tx, rx) = oneshot::channel();
 
         let action = Action::Deregister { deregistration, response: tx };
@@ -159,6 +198,19 @@ impl spec::ValidatorSpec for RegistryApi {
         rx.await?
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn operator_deregister(
+        &self,
+        deregistration: OperatorDeregistrationBatch,
+    ) -> Result<(), spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::OperatorDeregister { deregistration, response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+
     #[tracing::instrument(skip(self))]
     async fn get_registrations(&self) -> Result<Vec<Registration>, spec::RegistryError> {
         let (tx, rx) = oneshot::channel();
@@ -170,12 +222,30 @@ impl spec::ValidatorSpec for RegistryApi {
     }
 }
 
+impl spec::OperatorSpec for RegistryApi {
+    #[tracing::instrument(skip(self))]
+    async fn update_operator_settings(
+        &self,
+        update: OperatorSettingsUpdate,
+    ) -> Result<(), spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::UpdateOperatorSettings { update, response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+}
+
 impl spec::DiscoverySpec for RegistryApi {
     #[tracing::instrument(skip(self))]
-    async fn get_validators(&self) -> Result<Vec<RegistryEntry>, spec::RegistryError> {
+    async fn get_validators(
+        &self,
+        filter: DiscoveryFilter,
+    ) -> Result<Vec<RegistryEntry>, spec::RegistryError> {
         let (tx, rx) = oneshot::channel();
 
-        let action = Action::GetValidators { response: tx };
+        let action = Action::GetValidators { filter, response: tx };
         self.send_action(action).await?;
 
         rx.await?
@@ -221,10 +291,13 @@ impl spec::DiscoverySpec for RegistryApi {
     }
 
     #[tracing::instrument(skip(self))]
-    async fn get_operators(&self) -> Result<Vec<Operator>, spec::RegistryError> {
+    async fn get_operators(
+        &self,
+        filter: DiscoveryFilter,
+    ) -> Result<Vec<Operator>, spec::RegistryError> {
         let (tx, rx) = oneshot::channel();
 
-        let action = Action::GetOperators { response: tx };
+        let action = Action::GetOperators { filter, response: tx };
         self.send_action(action).await?;
 
         rx.await?
@@ -243,6 +316,46 @@ impl spec::DiscoverySpec for RegistryApi {
         rx.await?
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn get_operator_rpc_history(
+        &self,
+        signer: Address,
+    ) -> Result<Vec<RpcEndpointUpdate>, spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::GetOperatorRpcHistory { signer, response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_operator_stake_history(
+        &self,
+        signer: Address,
+    ) -> Result<Vec<StakeSnapshot>, spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::GetOperatorStakeHistory { signer, response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_operator_stake_at(
+        &self,
+        signer: Address,
+        epoch: u64,
+    ) -> Result<StakeSnapshot, spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::GetOperatorStakeAt { signer, epoch, response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+
     #[tracing::instrument(skip(self))]
     async fn get_lookahead(&self, epoch: u64) -> Result<Lookahead, spec::RegistryError> {
         let (tx, rx) = oneshot::channel();
@@ -252,6 +365,98 @@ impl spec::DiscoverySpec for RegistryApi {
 
         rx.await?
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_whitelist(&self) -> Result<Vec<WhitelistedCollateral>, spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::GetWhitelist { protocol: None, response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_whitelist_by_protocol(
+        &self,
+        protocol: RestakingProtocol,
+    ) -> Result<Vec<WhitelistedCollateral>, spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::GetWhitelist { protocol: Some(protocol), response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_source_conflicts(&self) -> Result<Vec<SourceConflict>, spec::RegistryError> {
+        let (tx, rx) = oneshot::channel();
+
+        let action = Action::GetSourceConflicts { response: tx };
+        self.send_action(action).await?;
+
+        rx.await?
+    }
+}
+
+/// Verifies the signatures of a registration batch, which must be signed either with one
+/// signature per validator or with a single aggregate signature. Returns the batch if valid.
+///
+/// NOTE: this is CPU-heavy for large batches and should not run on the async runtime.
+fn verify_registration_signatures(
+    registration: RegistrationBatch,
+    ctx: &SigningContext,
+) -> Result<RegistrationBatch, spec::RegistryError> {
+    // An aggregate signature is verified in one pass, but can't pinpoint invalid signers
+    if registration.aggregate_signature.is_some() {
+        if !registration.signatures.is_empty() {
+            return Err(spec::RegistryError::BadRequest(
+                "Only one of signatures or aggregate signature can be set",
+            ));
+        }
+
+        if !registration.verify_aggregate_signature(ctx) {
+            return Err(spec::RegistryError::BadRequest("Invalid aggregate signature"));
+        }
+
+        return Ok(registration);
+    }
+
+    if registration.signatures.len() != registration.validator_pubkeys.len() {
+        return Err(spec::RegistryError::BadRequest(
+            "Number of signatures does not match number of validators",
+        ));
+    }
+
+    let invalid_signers = registration.invalid_signers(ctx);
+    if !invalid_signers.is_empty() {
+        return Err(spec::RegistryError::InvalidSignatures(invalid_signers));
+    }
+
+    Ok(registration)
+}
+
+/// Verifies the signatures of a deregistration batch, which must be signed with one signature per
+/// validator. Returns the batch if valid.
+///
+/// NOTE: this is CPU-heavy for large batches and should not run on the async runtime.
+fn verify_deregistration_signatures(
+    deregistration: DeregistrationBatch,
+    ctx: &SigningContext,
+) -> Result<DeregistrationBatch, spec::RegistryError> {
+    if deregistration.signatures.len() != deregistration.validator_pubkeys.len() {
+        return Err(spec::RegistryError::BadRequest(
+            "Number of signatures does not match number of validators",
+        ));
+    }
+
+    let invalid_signers = deregistration.invalid_signers(ctx);
+    if !invalid_signers.is_empty() {
+        return Err(spec::RegistryError::InvalidSignatures(invalid_signers));
+    }
+
+    Ok(deregistration)
 }
 
 #[cfg(test)]
@@ -259,24 +464,30 @@ mod tests {
     use tokio_stream::StreamExt;
 
     use super::*;
+    use crate::primitives::signing::tests::MAINNET;
 
     #[tokio::test]
     async fn test_register() {
         let _ = tracing_subscriber::fmt().try_init();
 
-        let (api, mut stream) = RegistryApi::new(Default::default());
+        let (api, mut stream) = RegistryApi::new(Default::default(), MAINNET);
 
         let operator = Address::random();
         let gas_limit = 10_000u64;
         let expiry = 0u64;
+        let keypair = bls::Keypair::random();
 
-        let registration = RegistrationBatch {
-            validator_pubkeys: vec![BlsPublicKey::random()],
+        let mut registration = RegistrationBatch {
+            validator_pubkeys: vec![BlsPublicKey::from(keypair.pk.clone())],
             operator,
             gas_limit,
             expiry,
+            nonce: 0,
             signatures: vec![],
+            aggregate_signature: None,
+            operator_signature: None,
         };
+        registration.signatures = vec![keypair.sk.sign(registration.signing_root(&MAINNET))];
 
         let reg_clone = registration.clone();
         tokio::spawn(async move {
@@ -1,5 +1,5 @@
-//! The API specification for the registry, and its errors. Contains 2 sub-specs: [`ValidatorSpec`]
-//! and [`DiscoverySpec`]. The [`ApiSpec`] trait combines both of these.
+//! The API specification for the registry, and its errors. Contains 3 sub-specs: [`ValidatorSpec`],
+//! [`OperatorSpec`] and [`DiscoverySpec`].
 
 use std::time::Duration;
 
@@ -7,7 +7,10 @@ use alloy::primitives::Address;
 use axum::{http::StatusCode, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::sync::{mpsc::error::SendTimeoutError, oneshot::error::RecvError};
+use tokio::{
+    sync::{mpsc::error::SendTimeoutError, oneshot::error::RecvError},
+    task::JoinError,
+};
 
 use super::actions::Action;
 use crate::{
@@ -15,8 +18,10 @@ use crate::{
     db::DbError,
     primitives::{
         registry::{
-            DeregistrationBatch, Lookahead, Operator, Registration, RegistrationBatch,
-            RegistryEntry,
+            DeregistrationBatch, DiscoveryFilter, Lookahead, Operator, OperatorDeregistrationBatch,
+            OperatorSettingsUpdate, OperatorStatus, Registration, RegistrationBatch, RegistryEntry,
+            RestakingProtocol, RpcEndpointUpdate, SourceConflict, StakeSnapshot,
+            WhitelistedCollateral,
         },
         BlsPublicKey,
     },
@@ -28,14 +33,29 @@ pub(super) const MAX_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 // validator endpoints
 pub(super) const VALIDATORS_REGISTER_PATH: &str = "/registry/v1/validators/register";
 pub(super) const VALIDATORS_DEREGISTER_PATH: &str = "/registry/v1/validators/deregister";
+pub(super) const VALIDATORS_OPERATOR_DEREGISTER_PATH: &str =
+    "/registry/v1/validators/deregister/operator";
 pub(super) const VALIDATORS_REGISTRATIONS_PATH: &str = "/registry/v1/validators/registrations";
 
+// operator endpoints
+pub(super) const OPERATORS_SETTINGS_PATH: &str = "/registry/v1/operators/settings";
+
 // discovery endpoints
 pub(super) const DISCOVERY_VALIDATORS_PATH: &str = "/registry/v1/discovery/validators";
 pub(super) const DISCOVERY_VALIDATOR_PATH: &str = "/registry/v1/discovery/validators/{pubkey}";
 pub(super) const DISCOVERY_OPERATORS_PATH: &str = "/registry/v1/discovery/operators";
 pub(super) const DISCOVERY_OPERATOR_PATH: &str = "/registry/v1/discovery/operators/{signer}";
+pub(super) const DISCOVERY_OPERATOR_RPC_HISTORY_PATH: &str =
+    "/registry/v1/discovery/operators/{signer}/rpc";
+pub(super) const DISCOVERY_OPERATOR_STAKE_PATH: &str =
+    "/registry/v1/discovery/operators/{signer}/stake";
+pub(super) const DISCOVERY_OPERATOR_EPOCH_STAKE_PATH: &str =
+    "/registry/v1/discovery/operators/{signer}/stake/{epoch}";
 pub(super) const DISCOVERY_LOOKAHEAD_PATH: &str = "/registry/v1/discovery/lookahead/{epoch}";
+pub(super) const DISCOVERY_WHITELIST_PATH: &str = "/registry/v1/discovery/whitelist";
+pub(super) const DISCOVERY_PROTOCOL_WHITELIST_PATH: &str =
+    "/registry/v1/discovery/whitelist/{protocol}";
+pub(super) const DISCOVERY_CONFLICTS_PATH: &str = "/registry/v1/discovery/conflicts";
 
 /// The registry API spec for validators.
 pub(super) trait ValidatorSpec {
@@ -45,14 +65,32 @@ pub(super) trait ValidatorSpec {
     /// /registry/v1/validators/deregister
     async fn deregister(&self, deregistration: DeregistrationBatch) -> Result<(), RegistryError>;
 
+    /// /registry/v1/validators/deregister/operator
+    async fn operator_deregister(
+        &self,
+        deregistration: OperatorDeregistrationBatch,
+    ) -> Result<(), RegistryError>;
+
     /// /registry/v1/validators/registrations
     async fn get_registrations(&self) -> Result<Vec<Registration>, RegistryError>;
 }
 
+/// The registry API spec for operators.
+pub(super) trait OperatorSpec {
+    /// /registry/v1/operators/settings
+    async fn update_operator_settings(
+        &self,
+        update: OperatorSettingsUpdate,
+    ) -> Result<(), RegistryError>;
+}
+
 /// The registry API spec for discovery.
 pub(super) trait DiscoverySpec {
-    /// /registry/v1/discovery/validators
-    async fn get_validators(&self) -> Result<Vec<RegistryEntry>, RegistryError>;
+    /// /registry/v1/discovery/validators?protocol=...&source=...
+    async fn get_validators(
+        &self,
+        filter: DiscoveryFilter,
+    ) -> Result<Vec<RegistryEntry>, RegistryError>;
 
     /// /registry/v1/discovery/validators?pubkeys=...
     async fn get_validators_by_pubkeys(
@@ -72,15 +110,46 @@ pub(super) trait DiscoverySpec {
         pubkey: BlsPublicKey,
     ) -> Result<RegistryEntry, RegistryError>;
 
-    /// /registry/v1/discovery/operators
-    async fn get_operators(&self) -> Result<Vec<Operator>, RegistryError>;
+    /// /registry/v1/discovery/operators?protocol=...&source=...
+    async fn get_operators(&self, filter: DiscoveryFilter) -> Result<Vec<Operator>, RegistryError>;
 
     /// /registry/v1/discovery/operators/{signer}
     async fn get_operator_by_signer(&self, signer: Address) -> Result<Operator, RegistryError>;
 
+    /// /registry/v1/discovery/operators/{signer}/rpc
+    async fn get_operator_rpc_history(
+        &self,
+        signer: Address,
+    ) -> Result<Vec<RpcEndpointUpdate>, RegistryError>;
+
+    /// /registry/v1/discovery/operators/{signer}/stake
+    async fn get_operator_stake_history(
+        &self,
+        signer: Address,
+    ) -> Result<Vec<StakeSnapshot>, RegistryError>;
+
+    /// /registry/v1/discovery/operators/{signer}/stake/{epoch}
+    async fn get_operator_stake_at(
+        &self,
+        signer: Address,
+        epoch: u64,
+    ) -> Result<StakeSnapshot, RegistryError>;
+
     /// /registry/v1/discovery/lookahead/{epoch}
     /// This will return `TooEarly` if the epoch is too far in the future.
     async fn get_lookahead(&self, epoch: u64) -> Result<Lookahead, RegistryError>;
+
+    /// /registry/v1/discovery/whitelist
+    async fn get_whitelist(&self) -> Result<Vec<WhitelistedCollateral>, RegistryError>;
+
+    /// /registry/v1/discovery/whitelist/{protocol}
+    async fn get_whitelist_by_protocol(
+        &self,
+        protocol: RestakingProtocol,
+    ) -> Result<Vec<WhitelistedCollateral>, RegistryError>;
+
+    /// /registry/v1/discovery/conflicts
+    async fn get_source_conflicts(&self) -> Result<Vec<SourceConflict>, RegistryError>;
 }
 
 #[derive(Debug, Error)]
@@ -95,8 +164,20 @@ pub(crate) enum RegistryError {
     Database(#[from] DbError),
     #[error("Internal Server Error")]
     Beacon(#[from] BeaconClientError),
+    #[error("Internal Server Error")]
+    Verification(#[from] JoinError),
     #[error("Bad Request: {0}")]
     BadRequest(&'static str),
+    #[error("Bad Request: invalid signatures for validators [{}]", fmt_pubkeys(.0))]
+    InvalidSignatures(Vec<BlsPublicKey>),
+    #[error("Bad Request: validators [{}] are not registered to this operator", fmt_pubkeys(.0))]
+    OperatorMismatch(Vec<BlsPublicKey>),
+    #[error("Bad Request: stale or replayed nonce for validators [{}]", fmt_pubkeys(.0))]
+    StaleNonce(Vec<BlsPublicKey>),
+    #[error("Bad Request: unknown operator {0}")]
+    UnknownOperator(Address),
+    #[error("Bad Request: operator {0} is not active (status: {1})")]
+    InactiveOperator(Address, OperatorStatus),
 }
 
 impl IntoResponse for RegistryError {
@@ -105,20 +186,31 @@ impl IntoResponse for RegistryError {
             Self::BufferFull(_) |
             Self::ReponseChannelDropped(_) |
             Self::Database(_) |
-            Self::Beacon(_) => {
+            Self::Beacon(_) |
+            Self::Verification(_) => {
                 json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
                     .into_response()
             }
             Self::NotFound => {
                 json_error_response(StatusCode::NOT_FOUND, "Not Found").into_response()
             }
-            Self::BadRequest(_) => {
+            Self::BadRequest(_) |
+            Self::InvalidSignatures(_) |
+            Self::OperatorMismatch(_) |
+            Self::StaleNonce(_) |
+            Self::UnknownOperator(_) |
+            Self::InactiveOperator(..) => {
                 json_error_response(StatusCode::BAD_REQUEST, &self.to_string()).into_response()
             }
         }
     }
 }
 
+/// Formats a list of public keys as a comma-separated list of hex strings.
+fn fmt_pubkeys(pubkeys: &[BlsPublicKey]) -> String {
+    pubkeys.iter().map(|pk| pk.as_hex_string()).collect::<Vec<_>>().join(", ")
+}
+
 fn json_error_response(status: StatusCode, message: &str) -> impl IntoResponse {
     (status, Json(ErrorBody { code: status.as_u16(), message })).into_response()
 }
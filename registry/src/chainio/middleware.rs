@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+
+use alloy::{
+    primitives::{aliases::U48, Address, U256},
+    providers::{Provider, ReqwestProvider},
+    rpc::types::{Filter, Log},
+    sol_types::{SolEvent, SolEventInterface},
+};
+use tokio::sync::OnceCell;
+use url::Url;
+
+use crate::{
+    cli::Deployment,
+    primitives::registry::{
+        OperatorSetChange, OperatorSetEvent, RestakingProtocol, WhitelistAction, WhitelistEvent,
+    },
+};
+
+use super::{
+    abi::{
+        AllocationManager::{
+            AllocationManagerEvents, OperatorAddedToOperatorSet, OperatorRemovedFromOperatorSet,
+            OperatorSetCreated, StrategyAddedToOperatorSet, StrategyRemovedFromOperatorSet,
+        },
+        EigenLayerMiddleware::{
+            self, EigenLayerMiddlewareEvents, StrategyPaused, StrategyRemoved, StrategyUnpaused,
+            StrategyWhitelisted,
+        },
+        SymbioticMiddleware::{
+            self, SymbioticMiddlewareEvents, VaultPaused, VaultRemoved, VaultUnpaused,
+            VaultWhitelisted,
+        },
+    },
+    ChainIoError,
+};
+
+/// Read-only client for the Symbiotic and EigenLayer restaking middleware contracts.
+#[derive(Debug, Clone)]
+pub(crate) struct RestakingMiddlewaresClient {
+    provider: ReqwestProvider,
+    symbiotic: Deployment,
+    eigenlayer: Deployment,
+    /// The EigenLayer allocation manager, read from the EigenLayer middleware on first use.
+    allocation_manager: OnceCell<Address>,
+}
+
+impl RestakingMiddlewaresClient {
+    /// Creates a new client for the given middleware deployments, using the given execution RPC
+    /// URL.
+    pub(crate) fn new(execution_url: Url, symbiotic: Deployment, eigenlayer: Deployment) -> Self {
+        Self {
+            provider: ReqwestProvider::new_http(execution_url),
+            symbiotic,
+            eigenlayer,
+            allocation_manager: OnceCell::new(),
+        }
+    }
+
+    /// Returns the block in which the first of the middlewares was deployed.
+    pub(crate) fn deployment_block(&self) -> u64 {
+        self.symbiotic.deployment_block.min(self.eigenlayer.deployment_block)
+    }
+
+    /// Returns the restaking protocol of the middleware at the given address, if it is one of
+    /// the configured middlewares.
+    pub(crate) fn protocol_of(&self, middleware: Address) -> Option<RestakingProtocol> {
+        if middleware == self.symbiotic.address {
+            Some(RestakingProtocol::Symbiotic)
+        } else if middleware == self.eigenlayer.address {
+            Some(RestakingProtocol::EigenLayer)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the current collateral of an operator across both middlewares, as parallel
+    /// vectors of collateral tokens and amounts. Amounts of the same token are summed.
+    pub(crate) async fn get_operator_collaterals(
+        &self,
+        operator: Address,
+    ) -> Result<(Vec<Address>, Vec<U256>), ChainIoError> {
+        let symbiotic = SymbioticMiddleware::new(self.symbiotic.address, &self.provider);
+        let eigenlayer = EigenLayerMiddleware::new(self.eigenlayer.address, &self.provider);
+
+        let symbiotic_call = symbiotic.getOperatorCollaterals(operator);
+        let eigenlayer_call = eigenlayer.getOperatorCollaterals(operator);
+        let (symbiotic, eigenlayer) =
+            tokio::try_join!(symbiotic_call.call(), eigenlayer_call.call())?;
+
+        let tokens = symbiotic._0.into_iter().chain(eigenlayer._0);
+        let amounts = symbiotic._1.into_iter().chain(eigenlayer._1);
+
+        Ok(merge_collaterals(tokens.zip(amounts)))
+    }
+
+    /// Returns the stake of an operator at the given UNIX timestamp across both middlewares, as
+    /// parallel vectors of collateral tokens and amounts. Amounts of the same token are summed.
+    ///
+    /// NOTE: Symbiotic stake is read at the timestamp, but the EigenLayer middleware has no
+    /// historical stake view, so its current stake is used. Callers should therefore only ask
+    /// for recent timestamps, and label the result accordingly.
+    pub(crate) async fn get_operator_stake_at(
+        &self,
+        operator: Address,
+        timestamp: u64,
+    ) -> Result<(Vec<Address>, Vec<U256>), ChainIoError> {
+        let symbiotic = SymbioticMiddleware::new(self.symbiotic.address, &self.provider);
+        let eigenlayer = EigenLayerMiddleware::new(self.eigenlayer.address, &self.provider);
+
+        let symbiotic_call = symbiotic.getOperatorCollaterals(operator);
+        let eigenlayer_call = eigenlayer.getOperatorCollaterals(operator);
+        let (symbiotic_collaterals, eigenlayer) =
+            tokio::try_join!(symbiotic_call.call(), eigenlayer_call.call())?;
+
+        // NOTE: the Symbiotic middleware reports one token per whitelisted vault, but its stake
+        // view reads the first enabled vault of a token, so every token must only be read once.
+        let mut symbiotic_tokens = symbiotic_collaterals._0;
+        let mut seen = HashSet::new();
+        symbiotic_tokens.retain(|token| seen.insert(*token));
+
+        let mut symbiotic_amounts = Vec::with_capacity(symbiotic_tokens.len());
+        for token in &symbiotic_tokens {
+            let call = symbiotic.getOperatorStakeAt(operator, *token, U48::from(timestamp));
+            symbiotic_amounts.push(call.call().await?._0);
+        }
+
+        let tokens = symbiotic_tokens.into_iter().chain(eigenlayer._0);
+        let amounts = symbiotic_amounts.into_iter().chain(eigenlayer._1);
+
+        Ok(merge_collaterals(tokens.zip(amounts)))
+    }
+
+    /// Fetches the vault and strategy whitelist events (whitelisted, removed, paused, unpaused)
+    /// of both middlewares emitted in the inclusive block range `from..=to`, in the order they
+    /// were emitted.
+    pub(crate) async fn get_whitelist_events(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<WhitelistEvent>, ChainIoError> {
+        let filter = Filter::new()
+            .address(vec![self.symbiotic.address, self.eigenlayer.address])
+            .from_block(from)
+            .to_block(to)
+            .event_signature(vec![
+                VaultWhitelisted::SIGNATURE_HASH,
+                VaultRemoved::SIGNATURE_HASH,
+                VaultPaused::SIGNATURE_HASH,
+                VaultUnpaused::SIGNATURE_HASH,
+                StrategyWhitelisted::SIGNATURE_HASH,
+                StrategyRemoved::SIGNATURE_HASH,
+                StrategyPaused::SIGNATURE_HASH,
+                StrategyUnpaused::SIGNATURE_HASH,
+            ]);
+
+        let logs = self.provider.get_logs(&filter).await?;
+
+        logs.into_iter().filter_map(|log| self.decode_whitelist_event(&log).transpose()).collect()
+    }
+
+    /// Decodes a whitelist event log of either middleware. Logs of other contracts are skipped.
+    fn decode_whitelist_event(&self, log: &Log) -> Result<Option<WhitelistEvent>, ChainIoError> {
+        let block_number = log.block_number.ok_or(ChainIoError::MissingField("block number"))?;
+        let log_index = log.log_index.ok_or(ChainIoError::MissingField("log index"))?;
+
+        let (protocol, address, action) = if log.address() == self.symbiotic.address {
+            let (vault, action) = match SymbioticMiddlewareEvents::decode_log(&log.inner, true)?
+                .data
+            {
+                SymbioticMiddlewareEvents::VaultWhitelisted(e) => {
+                    (e.vault, WhitelistAction::Whitelisted)
+                }
+                SymbioticMiddlewareEvents::VaultRemoved(e) => (e.vault, WhitelistAction::Removed),
+                SymbioticMiddlewareEvents::VaultPaused(e) => (e.vault, WhitelistAction::Paused),
+                SymbioticMiddlewareEvents::VaultUnpaused(e) => (e.vault, WhitelistAction::Unpaused),
+                _ => return Ok(None),
+            };
+            (RestakingProtocol::Symbiotic, vault, action)
+        } else if log.address() == self.eigenlayer.address {
+            let (strategy, action) =
+                match EigenLayerMiddlewareEvents::decode_log(&log.inner, true)?.data {
+                    EigenLayerMiddlewareEvents::StrategyWhitelisted(e) => {
+                        (e.strategy, WhitelistAction::Whitelisted)
+                    }
+                    EigenLayerMiddlewareEvents::StrategyRemoved(e) => {
+                        (e.strategy, WhitelistAction::Removed)
+                    }
+                    EigenLayerMiddlewareEvents::StrategyPaused(e) => {
+                        (e.strategy, WhitelistAction::Paused)
+                    }
+                    EigenLayerMiddlewareEvents::StrategyUnpaused(e) => {
+                        (e.strategy, WhitelistAction::Unpaused)
+                    }
+                    _ => return Ok(None),
+                };
+            (RestakingProtocol::EigenLayer, strategy, action)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(WhitelistEvent { block_number, log_index, protocol, address, action }))
+    }
+
+    /// Fetches the operator set events (operator set created, operator added or removed,
+    /// strategy added or removed) of the bolt AVS emitted by the EigenLayer allocation manager in
+    /// the inclusive block range `from..=to`, in the order they were emitted. Events of other
+    /// AVSs are skipped.
+    pub(crate) async fn get_operator_set_events(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<OperatorSetEvent>, ChainIoError> {
+        let allocation_manager = self
+            .allocation_manager
+            .get_or_try_init(|| async {
+                let middleware = EigenLayerMiddleware::new(self.eigenlayer.address, &self.provider);
+                Ok::<_, ChainIoError>(middleware.ALLOCATION_MANAGER().call().await?._0)
+            })
+            .await?;
+
+        // Operator sets are not indexed, so events are filtered by AVS after decoding
+        let filter = Filter::new()
+            .address(*allocation_manager)
+            .from_block(from)
+            .to_block(to)
+            .event_signature(vec![
+                OperatorSetCreated::SIGNATURE_HASH,
+                OperatorAddedToOperatorSet::SIGNATURE_HASH,
+                OperatorRemovedFromOperatorSet::SIGNATURE_HASH,
+                StrategyAddedToOperatorSet::SIGNATURE_HASH,
+                StrategyRemovedFromOperatorSet::SIGNATURE_HASH,
+            ]);
+
+        let logs = self.provider.get_logs(&filter).await?;
+
+        logs.into_iter()
+            .filter_map(|log| self.decode_operator_set_event(&log).transpose())
+            .collect()
+    }
+
+    /// Decodes an operator set event log of the allocation manager. Events of other AVSs are
+    /// skipped.
+    fn decode_operator_set_event(
+        &self,
+        log: &Log,
+    ) -> Result<Option<OperatorSetEvent>, ChainIoError> {
+        let block_number = log.block_number.ok_or(ChainIoError::MissingField("block number"))?;
+        let log_index = log.log_index.ok_or(ChainIoError::MissingField("log index"))?;
+
+        let (operator_set, change) =
+            match AllocationManagerEvents::decode_log(&log.inner, true)?.data {
+                AllocationManagerEvents::OperatorSetCreated(e) => {
+                    (e.operatorSet, OperatorSetChange::Created)
+                }
+                AllocationManagerEvents::OperatorAddedToOperatorSet(e) => {
+                    (e.operatorSet, OperatorSetChange::OperatorAdded(e.operator))
+                }
+                AllocationManagerEvents::OperatorRemovedFromOperatorSet(e) => {
+                    (e.operatorSet, OperatorSetChange::OperatorRemoved(e.operator))
+                }
+                AllocationManagerEvents::StrategyAddedToOperatorSet(e) => {
+                    (e.operatorSet, OperatorSetChange::StrategyAdded(e.strategy))
+                }
+                AllocationManagerEvents::StrategyRemovedFromOperatorSet(e) => {
+                    (e.operatorSet, OperatorSetChange::StrategyRemoved(e.strategy))
+                }
+            };
+
+        if operator_set.avs != self.eigenlayer.address {
+            return Ok(None);
+        }
+
+        Ok(Some(OperatorSetEvent {
+            block_number,
+            log_index,
+            operator_set_id: operator_set.id,
+            change,
+        }))
+    }
+}
+
+/// Merges collateral (token, amount) pairs by token, summing the amounts. Tokens are kept in
+/// order of first appearance.
+fn merge_collaterals(
+    collaterals: impl IntoIterator<Item = (Address, U256)>,
+) -> (Vec<Address>, Vec<U256>) {
+    let mut tokens = Vec::new();
+    let mut amounts: Vec<U256> = Vec::new();
+
+    for (token, amount) in collaterals {
+        if let Some(i) = tokens.iter().position(|t| *t == token) {
+            amounts[i] = amounts[i].saturating_add(amount);
+        } else {
+            tokens.push(token);
+            amounts.push(amount);
+        }
+    }
+
+    (tokens, amounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_collaterals() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+
+        let (tokens, amounts) = merge_collaterals([
+            (a, U256::from(1)),
+            (b, U256::ZERO),
+            (a, U256::from(2)),
+            (b, U256::MAX),
+            (b, U256::from(1)),
+        ]);
+
+        assert_eq!(tokens, vec![a, b]);
+        assert_eq!(amounts, vec![U256::from(3), U256::MAX]);
+    }
+}
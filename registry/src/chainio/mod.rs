@@ -1,19 +1,120 @@
+use alloy::transports::RpcError;
+
 pub(crate) mod abi {
     use alloy::sol;
 
     sol!(
-        #[sol(rpc)]
+        #[sol(rpc, all_derives)]
         SymbioticMiddleware,
         "src/chainio/artifacts/SymbioticMiddlewareV1.json"
     );
     sol!(
-        #[sol(rpc)]
+        #[sol(rpc, all_derives)]
         OperatorsRegistry,
         "src/chainio/artifacts/OperatorsRegistryV1.json"
     );
     sol!(
-        #[sol(rpc)]
+        #[sol(rpc, all_derives)]
         EigenLayerMiddleware,
         "src/chainio/artifacts/EigenLayerMiddlewareV3.json"
     );
+
+    sol! {
+        /// Operator set events of the EigenLayer `AllocationManager` contract. Operator sets are
+        /// identified by their AVS, which is the bolt EigenLayer middleware, and an ID.
+        #[sol(all_derives)]
+        contract AllocationManager {
+            struct OperatorSet {
+                address avs;
+                uint32 id;
+            }
+
+            event OperatorSetCreated(OperatorSet operatorSet);
+            event OperatorAddedToOperatorSet(address indexed operator, OperatorSet operatorSet);
+            event OperatorRemovedFromOperatorSet(address indexed operator, OperatorSet operatorSet);
+            event StrategyAddedToOperatorSet(OperatorSet operatorSet, address strategy);
+            event StrategyRemovedFromOperatorSet(OperatorSet operatorSet, address strategy);
+        }
+    }
+}
+
+/// Client for the `OperatorsRegistryV1` contract.
+mod registry;
+pub(crate) use registry::{OperatorsRegistryClient, RegistryLog};
+
+/// Client for the restaking middleware contracts.
+mod middleware;
+pub(crate) use middleware::RestakingMiddlewaresClient;
+
+/// Lowercase fragments of the error messages of providers rejecting `eth_getLogs` queries over
+/// too many blocks, or with too many results. Fragments are specific enough not to match rate
+/// limiting errors, which must not shrink the block range.
+const RANGE_TOO_LARGE_ERRORS: &[&str] = &[
+    // Geth and derived clients
+    "query returned more than",
+    // Alchemy
+    "response size exceeded",
+    // Most other providers, e.g. "exceed maximum block range" or "block range too large"
+    "block range",
+    // Reth
+    "query exceeds max results",
+    // QuickNode
+    "eth_getlogs is limited to",
+];
+
+/// Errors that can occur while interacting with the execution layer.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub(crate) enum ChainIoError {
+    #[error("Execution RPC error: {0}")]
+    Transport(#[from] alloy::transports::TransportError),
+    #[error("Contract call failed: {0}")]
+    Contract(#[from] alloy::contract::Error),
+    #[error("Failed to decode log: {0}")]
+    Decode(#[from] alloy::sol_types::Error),
+    #[error("Block {0} not found")]
+    BlockNotFound(u64),
+    #[error("Log is missing its {0}")]
+    MissingField(&'static str),
+}
+
+impl ChainIoError {
+    /// Returns whether the error is the provider rejecting a log query because its block range
+    /// is too large, or because it matches too many results.
+    pub(crate) fn is_range_too_large(&self) -> bool {
+        let Self::Transport(RpcError::ErrorResp(payload)) = self else {
+            return false;
+        };
+
+        let message = payload.message.to_lowercase();
+        RANGE_TOO_LARGE_ERRORS.iter().any(|fragment| message.contains(fragment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::json_rpc::ErrorPayload;
+
+    use super::*;
+
+    fn error_response(message: &'static str) -> ChainIoError {
+        ChainIoError::Transport(RpcError::ErrorResp(ErrorPayload {
+            code: -32005,
+            message: message.into(),
+            data: None,
+        }))
+    }
+
+    #[test]
+    fn test_is_range_too_large() {
+        assert!(error_response("query returned more than 10000 results").is_range_too_large());
+        assert!(error_response("Log response size exceeded.").is_range_too_large());
+        assert!(error_response("exceed maximum block range: 50000").is_range_too_large());
+        assert!(error_response("query exceeds max block range 100000").is_range_too_large());
+        assert!(!error_response("execution reverted").is_range_too_large());
+        assert!(!error_response("Too many requests, rate limit exceeded").is_range_too_large());
+        assert!(!error_response("daily request limit exceeded").is_range_too_large());
+        assert!(!error_response("exceeded more than 100 requests per second").is_range_too_large());
+        assert!(!ChainIoError::BlockNotFound(1).is_range_too_large());
+    }
 }
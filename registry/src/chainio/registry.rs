@@ -0,0 +1,182 @@
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    primitives::{Address, B256},
+    providers::{Provider, ReqwestProvider},
+    rpc::types::{BlockTransactionsKind, Filter},
+    sol_types::{SolEvent, SolEventInterface},
+};
+use url::Url;
+
+use crate::cli::Deployment;
+
+use super::{
+    abi::OperatorsRegistry::{
+        self, OperatorDeregistered, OperatorPaused, OperatorRegistered, OperatorUnpaused,
+        OperatorsRegistryEvents,
+    },
+    ChainIoError,
+};
+
+/// A decoded operator lifecycle event of the `OperatorsRegistryV1` contract.
+#[derive(Debug, Clone)]
+pub(crate) struct RegistryLog {
+    /// The block number in which the event was emitted.
+    pub(crate) block_number: u64,
+    /// The index of the log in the block.
+    pub(crate) log_index: u64,
+    /// The decoded event.
+    pub(crate) event: OperatorsRegistryEvents,
+}
+
+/// The epoch schedule of the `OperatorsRegistryV1` contract. Epochs are numbered from 0, starting
+/// at the start timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RegistryEpochs {
+    /// The UNIX timestamp in seconds at which epoch 0 starts.
+    pub(crate) start_timestamp: u64,
+    /// The duration of an epoch in seconds.
+    pub(crate) epoch_duration: u64,
+}
+
+impl RegistryEpochs {
+    /// Returns the epoch at the given UNIX timestamp, or `None` if it is before the first epoch.
+    pub(crate) fn epoch_at(&self, timestamp: u64) -> Option<u64> {
+        let elapsed = timestamp.checked_sub(self.start_timestamp)?;
+        elapsed.checked_div(self.epoch_duration)
+    }
+
+    /// Returns the UNIX timestamp at which the given epoch starts.
+    pub(crate) const fn epoch_start(&self, epoch: u64) -> u64 {
+        self.start_timestamp + epoch * self.epoch_duration
+    }
+}
+
+/// Read-only client for the `OperatorsRegistryV1` contract.
+#[derive(Debug, Clone)]
+pub(crate) struct OperatorsRegistryClient {
+    provider: ReqwestProvider,
+    deployment: Deployment,
+}
+
+impl OperatorsRegistryClient {
+    /// Creates a new client for the given contract deployment, using the given execution RPC URL.
+    pub(crate) fn new(execution_url: Url, deployment: Deployment) -> Self {
+        Self { provider: ReqwestProvider::new_http(execution_url), deployment }
+    }
+
+    /// Returns the address of the contract.
+    pub(crate) const fn address(&self) -> Address {
+        self.deployment.address
+    }
+
+    /// Returns the block in which the contract was deployed.
+    pub(crate) const fn deployment_block(&self) -> u64 {
+        self.deployment.deployment_block
+    }
+
+    /// Returns the latest block number of the execution node.
+    pub(crate) async fn get_block_number(&self) -> Result<u64, ChainIoError> {
+        Ok(self.provider.get_block_number().await?)
+    }
+
+    /// Returns the hash of the canonical block at the given height, if any.
+    pub(crate) async fn get_block_hash(&self, number: u64) -> Result<Option<B256>, ChainIoError> {
+        let block = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Number(number), BlockTransactionsKind::Hashes)
+            .await?;
+
+        Ok(block.map(|block| block.header.hash))
+    }
+
+    /// Returns the timestamp of the canonical block at the given height, if any.
+    pub(crate) async fn get_block_timestamp(
+        &self,
+        number: u64,
+    ) -> Result<Option<u64>, ChainIoError> {
+        let block = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Number(number), BlockTransactionsKind::Hashes)
+            .await?;
+
+        Ok(block.map(|block| block.header.timestamp))
+    }
+
+    /// Returns the epoch schedule of the contract.
+    pub(crate) async fn get_epochs(&self) -> Result<RegistryEpochs, ChainIoError> {
+        let registry = OperatorsRegistry::new(self.address(), &self.provider);
+
+        let start_call = registry.START_TIMESTAMP();
+        let duration_call = registry.EPOCH_DURATION();
+        let (start, duration) = tokio::try_join!(start_call.call(), duration_call.call())?;
+
+        Ok(RegistryEpochs {
+            start_timestamp: start._0.to::<u64>(),
+            epoch_duration: duration._0.to::<u64>(),
+        })
+    }
+
+    /// Returns the signer and RPC endpoint of all operators in the contract at the given block,
+    /// including inactive ones. RPC endpoints are returned unparsed.
+    pub(crate) async fn get_operator_rpc_endpoints(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<(Address, String)>, ChainIoError> {
+        let registry = OperatorsRegistry::new(self.address(), &self.provider);
+        let call = registry.getAllOperators().block(BlockId::number(block_number));
+        let operators = call.call().await?._0;
+
+        Ok(operators.into_iter().map(|o| (o.signer, o.rpcEndpoint)).collect())
+    }
+
+    /// Fetches the operator lifecycle events (registered, deregistered, paused, unpaused)
+    /// emitted in the inclusive block range `from..=to`, in the order they were emitted.
+    pub(crate) async fn get_operator_events(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<RegistryLog>, ChainIoError> {
+        let filter =
+            Filter::new().address(self.address()).from_block(from).to_block(to).event_signature(
+                vec![
+                    OperatorRegistered::SIGNATURE_HASH,
+                    OperatorDeregistered::SIGNATURE_HASH,
+                    OperatorPaused::SIGNATURE_HASH,
+                    OperatorUnpaused::SIGNATURE_HASH,
+                ],
+            );
+
+        let logs = self.provider.get_logs(&filter).await?;
+
+        logs.into_iter()
+            .map(|log| {
+                let block_number =
+                    log.block_number.ok_or(ChainIoError::MissingField("block number"))?;
+                let log_index = log.log_index.ok_or(ChainIoError::MissingField("log index"))?;
+                let event = OperatorsRegistryEvents::decode_log(&log.inner, true)?.data;
+
+                Ok(RegistryLog { block_number, log_index, event })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_epochs() {
+        let epochs = RegistryEpochs { start_timestamp: 1_000, epoch_duration: 100 };
+
+        assert_eq!(epochs.epoch_at(999), None);
+        assert_eq!(epochs.epoch_at(1_000), Some(0));
+        assert_eq!(epochs.epoch_at(1_099), Some(0));
+        assert_eq!(epochs.epoch_at(1_100), Some(1));
+        assert_eq!(epochs.epoch_start(3), 1_300);
+
+        // A misconfigured contract without epoch duration has no epochs
+        let epochs = RegistryEpochs { start_timestamp: 1_000, epoch_duration: 0 };
+        assert_eq!(epochs.epoch_at(2_000), None);
+    }
+}
@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use super::{Contracts, Network};
+use crate::{client::RetryPolicy, sync::SyncBootstrap};
+
+/// The default confirmation depth for contract events.
+const DEFAULT_CONFIRMATION_DEPTH: u64 = 12;
+
 /// The main configuration for the bolt registry server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Config {
@@ -10,6 +16,71 @@ pub(crate) struct Config {
     pub(crate) db_url: Option<String>,
     /// The URL of the remote Ethereum beacon node HTTP API.
     pub(crate) beacon_url: Url,
-    /// The URL of the Lido "keys API".
-    pub(crate) keys_api_url: String,
+    /// The URL of the remote Ethereum execution node JSON-RPC API.
+    pub(crate) execution_url: Url,
+    /// The network to run on. Selects the built-in contract deployments.
+    pub(crate) network: Network,
+    /// Custom contract deployments, overriding the ones of the network.
+    pub(crate) contracts: Option<Contracts>,
+    /// The number of blocks behind the execution head after which contract events are applied.
+    /// Reorgs deeper than this are still detected and rolled back.
+    #[serde(default = "default_confirmation_depth")]
+    pub(crate) confirmation_depth: u64,
+    /// How to initialize the sync state on a fresh database. Defaults to the current beacon head.
+    #[serde(default)]
+    pub(crate) bootstrap: SyncBootstrap,
+    /// The retry policy and circuit breaking of beacon node and external source calls.
+    #[serde(default)]
+    pub(crate) retry: RetryPolicy,
+    /// The URL of the Lido "keys API". Added as the first external source, with priority 0.
+    pub(crate) keys_api_url: Option<Url>,
+    /// Additional external sources of validator registrations.
+    #[serde(default)]
+    pub(crate) sources: Vec<SourceConfig>,
+}
+
+impl Config {
+    /// Returns the contract deployments to sync from: the custom ones if configured, otherwise
+    /// the built-in ones of the network.
+    pub(crate) fn contracts(&self) -> Contracts {
+        self.contracts.unwrap_or_else(|| self.network.contracts())
+    }
+
+    /// Returns all external sources to sync from, in their configured order.
+    pub(crate) fn sources(&self) -> Vec<SourceConfig> {
+        let keys_api = self.keys_api_url.clone().map(|url| SourceConfig {
+            kind: SourceKind::Lido,
+            url,
+            priority: 0,
+        });
+
+        keys_api.into_iter().chain(self.sources.iter().cloned()).collect()
+    }
+}
+
+/// An external source of validator registrations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SourceConfig {
+    /// The kind of the source.
+    pub(crate) kind: SourceKind,
+    /// The URL of the source.
+    pub(crate) url: Url,
+    /// The priority of the source. When sources return different operators for a validator, the
+    /// source with the highest priority wins, and ties go to the source configured first.
+    #[serde(default)]
+    pub(crate) priority: u8,
+}
+
+/// The kind of an external source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SourceKind {
+    /// A Lido "keys API".
+    Lido,
+    /// An operator feed, serving the registry entries of an operator as a JSON array.
+    Feed,
+}
+
+const fn default_confirmation_depth() -> u64 {
+    DEFAULT_CONFIRMATION_DEPTH
 }
@@ -14,7 +14,11 @@ use figment::{
 
 /// The program configuration structs.
 mod config;
-pub(crate) use config::Config;
+pub(crate) use config::{Config, SourceKind};
+
+/// Built-in network presets and contract deployments.
+mod networks;
+pub(crate) use networks::{Contracts, Deployment, Network};
 
 #[derive(Debug, Clone, Parser)]
 #[command(author, version, styles = cli_styles(), about)]
@@ -0,0 +1,169 @@
+use alloy::primitives::{address, Address};
+use serde::{Deserialize, Serialize};
+
+/// A lower bound of the bolt contract deployment blocks on mainnet (May 31, 2024).
+const MAINNET_DEPLOYMENT_BLOCK: u64 = 20_000_000;
+
+/// A lower bound of the bolt contract deployment blocks on Holesky (Feb 18, 2024).
+const HOLESKY_DEPLOYMENT_BLOCK: u64 = 1_000_000;
+
+/// A network with built-in bolt contract deployments. Presets match the deployments of the
+/// `contracts/script/<network>/Deploy.s.sol` scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Network {
+    /// Ethereum mainnet.
+    Mainnet,
+    /// The Holesky testnet.
+    Holesky,
+}
+
+impl Network {
+    /// Returns the execution layer chain ID of the network.
+    pub(crate) const fn chain_id(self) -> u64 {
+        match self {
+            Self::Mainnet => 1,
+            Self::Holesky => 17000,
+        }
+    }
+
+    /// Returns the bolt contract deployments of the network.
+    ///
+    /// NOTE: deployment blocks are not recorded by the deploy scripts. The presets use blocks
+    /// predating the deployment of the Symbiotic core contracts the bolt contracts are initialized
+    /// with, so they are lower bounds of the actual deployment blocks. Syncing from an earlier
+    /// block is correct, only slower.
+    pub(crate) const fn contracts(self) -> Contracts {
+        match self {
+            Self::Mainnet => Contracts {
+                operators_registry: Deployment::new(
+                    address!("630869F51C012C797FEb3D9006F4280587C78b3f"),
+                    MAINNET_DEPLOYMENT_BLOCK,
+                ),
+                symbiotic_middleware: Deployment::new(
+                    address!("74c4eF33fce5bbfDb786c65efca513C68C7d19C3"),
+                    MAINNET_DEPLOYMENT_BLOCK,
+                ),
+                eigenlayer_middleware: Deployment::new(
+                    address!("35DebC00531Ac8771be5dbEf015feFD084efA958"),
+                    MAINNET_DEPLOYMENT_BLOCK,
+                ),
+            },
+            Self::Holesky => Contracts {
+                operators_registry: Deployment::new(
+                    address!("030ffdbb1e90ebfd763cfa7a7127ee055039e6ec"),
+                    HOLESKY_DEPLOYMENT_BLOCK,
+                ),
+                symbiotic_middleware: Deployment::new(
+                    address!("0696A10F54F387483c95F260bb09eEAcF041E9Ba"),
+                    HOLESKY_DEPLOYMENT_BLOCK,
+                ),
+                eigenlayer_middleware: Deployment::new(
+                    address!("0bF214EaC2E3992Ff054fa8EAF1D13E84d45A885"),
+                    HOLESKY_DEPLOYMENT_BLOCK,
+                ),
+            },
+        }
+    }
+}
+
+/// The bolt contract deployments of a network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Contracts {
+    /// The `OperatorsRegistryV1` contract.
+    pub(crate) operators_registry: Deployment,
+    /// The `SymbioticMiddlewareV1` contract.
+    pub(crate) symbiotic_middleware: Deployment,
+    /// The `EigenLayerMiddlewareV3` contract.
+    pub(crate) eigenlayer_middleware: Deployment,
+}
+
+/// A contract deployment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Deployment {
+    /// The address of the contract (proxy).
+    pub(crate) address: Address,
+    /// The block in which the contract was deployed. Syncing starts from this block.
+    #[serde(default)]
+    pub(crate) deployment_block: u64,
+}
+
+impl Deployment {
+    /// Creates a new deployment.
+    pub(crate) const fn new(address: Address, deployment_block: u64) -> Self {
+        Self { address, deployment_block }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use figment::{
+        providers::{Format, Toml},
+        Figment,
+    };
+
+    use super::*;
+    use crate::cli::Config;
+
+    #[test]
+    fn test_network_presets() {
+        let config: Config = Figment::new()
+            .merge(Toml::string(
+                r#"
+                network = "holesky"
+                beacon_url = "http://localhost:5052"
+                execution_url = "http://localhost:8545"
+                keys_api_url = "http://localhost:3000"
+                "#,
+            ))
+            .extract()
+            .unwrap();
+
+        assert_eq!(config.network, Network::Holesky);
+        assert_eq!(config.contracts(), Network::Holesky.contracts());
+    }
+
+    #[test]
+    fn test_network_deployment_blocks() {
+        for network in [Network::Mainnet, Network::Holesky] {
+            let contracts = network.contracts();
+            for deployment in [
+                contracts.operators_registry,
+                contracts.symbiotic_middleware,
+                contracts.eigenlayer_middleware,
+            ] {
+                assert_ne!(deployment.deployment_block, 0, "{network:?} {}", deployment.address);
+            }
+        }
+    }
+
+    #[test]
+    fn test_contracts_override() {
+        let config: Config = Figment::new()
+            .merge(Toml::string(
+                r#"
+                network = "mainnet"
+                beacon_url = "http://localhost:5052"
+                execution_url = "http://localhost:8545"
+                keys_api_url = "http://localhost:3000"
+
+                [contracts.operators_registry]
+                address = "0x0000000000000000000000000000000000000001"
+                deployment_block = 100
+
+                [contracts.symbiotic_middleware]
+                address = "0x0000000000000000000000000000000000000002"
+
+                [contracts.eigenlayer_middleware]
+                address = "0x0000000000000000000000000000000000000003"
+                "#,
+            ))
+            .extract()
+            .unwrap();
+
+        let contracts = config.contracts();
+        assert_eq!(contracts.operators_registry, Deployment::new(Address::with_last_byte(1), 100));
+        assert_eq!(contracts.symbiotic_middleware, Deployment::new(Address::with_last_byte(2), 0));
+        assert_eq!(contracts.eigenlayer_middleware.address, Address::with_last_byte(3));
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use alloy::{
     primitives::{Address, B256},
@@ -13,9 +13,13 @@ use serde::{Deserialize, Serialize};
 use tokio_stream::{Stream, StreamExt};
 use tracing::warn;
 
-use crate::primitives::{
-    beacon::{NewHead, NewHeadsTopic, PayloadAttribute},
-    BlsPublicKey,
+use crate::{
+    client::retry::{CircuitBreaker, CircuitOpen, RetryPolicy},
+    primitives::{
+        beacon::{NewHead, NewHeadsTopic, PayloadAttribute},
+        signing::{SigningContext, Version},
+        BlsPublicKey,
+    },
 };
 
 /// Errors that can occur while interacting with the beacon API.
@@ -36,6 +40,8 @@ pub(crate) enum BeaconClientError {
     Inner(#[from] beacon_api_client::Error),
     #[error("Failed to parse or build URL")]
     Url,
+    #[error(transparent)]
+    CircuitOpen(#[from] CircuitOpen),
 }
 
 /// A type alias for the result of a beacon client operation.
@@ -53,6 +59,8 @@ pub(crate) type BeaconClientResult<T> = Result<T, BeaconClientError>;
 pub(crate) struct BeaconClient {
     client: reqwest::Client,
     beacon_rpc_url: Url,
+    retry: RetryPolicy,
+    breaker: Arc<CircuitBreaker>,
 
     // Inner client re-exported from the beacon_api_client crate.
     // By wrapping this, we can automatically use its existing methods
@@ -65,7 +73,18 @@ impl BeaconClient {
     /// Create a new [BeaconClient] instance with the given beacon RPC URL.
     pub(crate) fn new(beacon_rpc_url: Url) -> Self {
         let inner = beacon_api_client::mainnet::Client::new(beacon_rpc_url.clone());
-        Self { client: reqwest::Client::new(), beacon_rpc_url, inner }
+        Self {
+            client: reqwest::Client::new(),
+            beacon_rpc_url,
+            retry: RetryPolicy::default(),
+            breaker: Arc::default(),
+            inner,
+        }
+    }
+
+    /// Set the [`RetryPolicy`] of the retried methods of the client.
+    pub(crate) fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
     }
 
     /// Fetch a list of active validator summaries from their public keys from the beacon chain.
@@ -123,6 +142,49 @@ impl BeaconClient {
         Ok(B256::from_slice(res.as_slice()))
     }
 
+    /// Fetch the [`SigningContext`] of the network from the genesis details and deposit contract
+    /// of the beacon node.
+    pub(crate) async fn get_signing_context(&self) -> BeaconClientResult<SigningContext> {
+        let genesis_url = self
+            .beacon_rpc_url
+            .join("/eth/v1/beacon/genesis")
+            .map_err(|_| BeaconClientError::Url)?;
+
+        let deposit_contract_url = self
+            .beacon_rpc_url
+            .join("/eth/v1/config/deposit_contract")
+            .map_err(|_| BeaconClientError::Url)?;
+
+        #[derive(Deserialize)]
+        struct Genesis {
+            genesis_validators_root: B256,
+            genesis_fork_version: Version,
+        }
+
+        #[derive(Deserialize)]
+        struct DepositContract {
+            chain_id: String,
+        }
+
+        // parse from /data
+        let genesis =
+            self.client.get(genesis_url).send().await?.json::<ResponseData<Genesis>>().await?.data;
+        let deposit_contract = self
+            .client
+            .get(deposit_contract_url)
+            .send()
+            .await?
+            .json::<ResponseData<DepositContract>>()
+            .await?
+            .data;
+
+        Ok(SigningContext::new(
+            genesis.genesis_fork_version,
+            genesis.genesis_validators_root,
+            deposit_contract.chain_id.parse()?,
+        ))
+    }
+
     /// Subscribes to the payload attributes events. Returns a stream of filtered payload attributes
     /// in the form of [`PayloadAttribute`]. These events are emitted by beacon nodes in 3 cases:
     /// 1. Around the 12s mark.
@@ -179,53 +241,64 @@ impl BeaconClient {
     /// lookahead for the next epoch, which is considered unstable.
     ///
     /// # Retries
-    /// This method will retry indefinitely in case of a failure.
+    /// This method is retried according to the [`RetryPolicy`] of the client.
     pub(crate) async fn get_lookahead(
         &self,
         epoch: u64,
         extended: bool,
     ) -> Result<Vec<ProposerDuty>, BeaconClientError> {
-        loop {
-            if extended {
-                match tokio::try_join!(
-                    self.inner.get_proposer_duties(epoch),
-                    self.inner.get_proposer_duties(epoch + 1),
-                ) {
-                    Ok(((_, mut duties), (_, next_duties))) => {
-                        duties.extend(next_duties);
-                        break Ok(duties)
-                    }
-                    Err(e) => {
-                        warn!(error = ?e, "Failed to get proposer duties, retrying...");
-                        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                    }
+        self.retry
+            .retry(&self.breaker, "proposer duties", || async {
+                if extended {
+                    let ((_, mut duties), (_, next_duties)) = tokio::try_join!(
+                        self.inner.get_proposer_duties(epoch),
+                        self.inner.get_proposer_duties(epoch + 1),
+                    )?;
+                    duties.extend(next_duties);
+                    Ok::<_, BeaconClientError>(duties)
+                } else {
+                    Ok(self.inner.get_proposer_duties(epoch).await?.1)
                 }
-            } else {
-                match self.inner.get_proposer_duties(epoch).await {
-                    Ok((_, duties)) => break Ok(duties),
-                    Err(e) => {
-                        warn!(error = ?e, "Failed to get proposer duties, retrying...");
-                        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                    }
-                }
-            };
-        }
+            })
+            .await
     }
 
     /// Gets the current epoch from the sync status `head_slot`.
     ///
     /// # Retries
-    /// This method will retry indefinitely in case of a failure.
+    /// This method is retried according to the [`RetryPolicy`] of the client.
     pub(crate) async fn get_epoch(&self) -> Result<u64, BeaconClientError> {
-        loop {
-            match self.inner.get_sync_status().await {
-                Ok(status) => break Ok(status.head_slot / 32),
-                Err(e) => {
-                    warn!(error = ?e, "Failed to get epoch, retrying...");
-                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                }
-            }
+        Ok(self.get_head_slot().await? / 32)
+    }
+
+    /// Gets the current head slot from the sync status.
+    ///
+    /// # Retries
+    /// This method is retried according to the [`RetryPolicy`] of the client.
+    pub(crate) async fn get_head_slot(&self) -> Result<u64, BeaconClientError> {
+        self.retry
+            .retry(&self.breaker, "head slot", || async {
+                Ok::<_, BeaconClientError>(self.inner.get_sync_status().await?.head_slot)
+            })
+            .await
+    }
+
+    /// Fetch the genesis time of the network, in UNIX seconds.
+    pub(crate) async fn get_genesis_time(&self) -> BeaconClientResult<u64> {
+        let url = self
+            .beacon_rpc_url
+            .join("/eth/v1/beacon/genesis")
+            .map_err(|_| BeaconClientError::Url)?;
+
+        #[derive(Deserialize)]
+        struct Genesis {
+            genesis_time: String,
         }
+
+        // parse from /data/genesis_time
+        let genesis =
+            self.client.get(url).send().await?.json::<ResponseData<Genesis>>().await?.data;
+        Ok(genesis.genesis_time.parse()?)
     }
 
     /// Gets validator indices from their public keys.
@@ -236,26 +309,22 @@ impl BeaconClient {
     /// comparing the length of the returned summaries.
     ///
     /// # Retries
-    /// This method will retry indefinitely in case of a failure.
+    /// This method is retried according to the [`RetryPolicy`] of the client.
     pub(crate) async fn get_active_validator_summaries(
         &self,
         pubkeys: &[BlsPublicKey],
     ) -> Result<Vec<ValidatorSummary>, BeaconClientError> {
         let pubkeys = pubkeys.iter().map(|pk| pk.to_consensus().into()).collect::<Vec<_>>();
 
-        loop {
-            match self
-                .inner
-                .get_validators(StateId::Head, &pubkeys, &[ValidatorStatus::Active])
-                .await
-            {
-                Ok(validators) => break Ok(validators),
-                Err(e) => {
-                    warn!(error = ?e, "Failed to get active validators, retrying...");
-                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                }
-            }
-        }
+        self.retry
+            .retry(&self.breaker, "active validators", || async {
+                Ok::<_, BeaconClientError>(
+                    self.inner
+                        .get_validators(StateId::Head, &pubkeys, &[ValidatorStatus::Active])
+                        .await?,
+                )
+            })
+            .await
     }
 }
 
@@ -316,4 +385,20 @@ mod tests {
 
         assert!(beacon_api.get_parent_beacon_block_root().await.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_get_signing_context() {
+        let url = Url::from_str("http://remotebeast:44400").unwrap();
+
+        if reqwest::get(url.clone()).await.is_err_and(|err| err.is_timeout() || err.is_connect()) {
+            eprintln!("Skipping test because remotebeast is not reachable");
+            return;
+        }
+
+        let beacon_api = BeaconClient::new(url);
+
+        let ctx = beacon_api.get_signing_context().await.unwrap();
+        assert_ne!(ctx.genesis_validators_root, B256::ZERO);
+        assert_ne!(ctx.chain_id, 0);
+    }
 }
@@ -2,3 +2,7 @@
 /// It extends the [`beacon_api_client::mainnet::Client`] with custom error handling and methods.
 pub(crate) mod beacon;
 pub(crate) use beacon::BeaconClient;
+
+/// Module defining the retry policy and circuit breaking of calls to external services.
+pub(crate) mod retry;
+pub(crate) use retry::{CircuitBreaker, RetryPolicy};
@@ -0,0 +1,282 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt::Debug,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Error of a call that was not attempted, because the circuit breaker of its upstream is open.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("Circuit breaker open, call not attempted")]
+pub(crate) struct CircuitOpen;
+
+/// The retry policy of calls to upstream services, such as the beacon node and external sources.
+///
+/// Failed calls are retried with exponential backoff and jitter, up to a maximum number of
+/// attempts. Every upstream has a [`CircuitBreaker`], which opens after a number of consecutive
+/// failed attempts. While open, calls fail immediately with [`CircuitOpen`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RetryPolicy {
+    /// The maximum number of attempts of a call, including the first one.
+    pub(crate) max_attempts: u32,
+    /// The delay before the first retry, in milliseconds. Doubled after every retry.
+    pub(crate) initial_backoff_ms: u64,
+    /// The maximum delay between two attempts, in milliseconds.
+    pub(crate) max_backoff_ms: u64,
+    /// The fraction by which every delay is randomly shortened or lengthened, between 0 and 1.
+    pub(crate) jitter: f64,
+    /// The number of consecutive failed attempts after which the circuit breaker of an upstream
+    /// opens. 0 disables circuit breaking.
+    pub(crate) failure_threshold: u32,
+    /// How long an open circuit breaker fails calls immediately, in milliseconds. Afterwards, a
+    /// single probe attempt is let through, and its failure re-opens the circuit breaker.
+    pub(crate) open_duration_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+            jitter: 0.2,
+            failure_threshold: 10,
+            open_duration_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Calls `call` until it succeeds, the maximum number of attempts is reached, or the circuit
+    /// `breaker` of the upstream is open. Returns the error of the last attempt on failure, or
+    /// [`CircuitOpen`] if the breaker was open before the first attempt.
+    pub(crate) async fn retry<T, E, F, Fut>(
+        &self,
+        breaker: &CircuitBreaker,
+        upstream: &str,
+        mut call: F,
+    ) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: From<CircuitOpen> + Debug,
+    {
+        let mut backoff = Duration::from_millis(self.initial_backoff_ms);
+        let mut attempt = 1;
+        let mut last_error = None;
+
+        loop {
+            // The breaker may have been opened by concurrent calls to the same upstream
+            if !breaker.allows_attempt(self) {
+                return Err(last_error.unwrap_or_else(|| CircuitOpen.into()));
+            }
+
+            let error = match call().await {
+                Ok(value) => {
+                    breaker.record_success();
+                    return Ok(value);
+                }
+                Err(error) => error,
+            };
+
+            if breaker.record_failure(self) {
+                warn!(upstream, open_duration_ms = self.open_duration_ms, "Circuit breaker opened");
+                warn!(upstream, attempt, ?error, "Call failed, giving up");
+                return Err(error);
+            }
+
+            if attempt >= self.max_attempts {
+                warn!(upstream, attempt, ?error, "Call failed, giving up");
+                return Err(error);
+            }
+
+            let delay = self.jittered(backoff);
+            warn!(upstream, attempt, ?delay, ?error, "Call failed, retrying...");
+            tokio::time::sleep(delay).await;
+
+            last_error = Some(error);
+
+            backoff = backoff.saturating_mul(2).min(Duration::from_millis(self.max_backoff_ms));
+            attempt += 1;
+        }
+    }
+
+    /// Randomly shortens or lengthens the given delay by up to the jitter fraction.
+    fn jittered(&self, delay: Duration) -> Duration {
+        // NOTE: every `RandomState` is randomly keyed, which is enough randomness for jitter
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        let jitter = self.jitter.clamp(0.0, 1.0);
+
+        delay.mul_f64(1.0 - jitter + 2.0 * jitter * random)
+    }
+}
+
+/// The circuit breaker of an upstream, tracking its consecutive failed attempts. Shared by all
+/// calls to the upstream.
+#[derive(Debug, Default)]
+pub(crate) struct CircuitBreaker {
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    /// The number of consecutive failed attempts.
+    failures: u32,
+    /// Until when the circuit is open, if it was opened. Once elapsed, the circuit is half-open.
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Returns whether an attempt may be made. Attempts are always allowed while the circuit is
+    /// closed, and never while it is open. While it is half-open, a single probe attempt is let
+    /// through, and the circuit stays open until the probe succeeds or fails. If the probe is
+    /// abandoned, another one is let through after the open duration.
+    fn allows_attempt(&self, policy: &RetryPolicy) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        match state.open_until {
+            None => true,
+            Some(until) if now < until => false,
+            Some(_) => {
+                state.open_until = Some(now + Duration::from_millis(policy.open_duration_ms));
+                true
+            }
+        }
+    }
+
+    /// Records a successful attempt, closing the circuit.
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        *state = BreakerState::default();
+    }
+
+    /// Records a failed attempt, and opens the circuit if the failure threshold of the policy is
+    /// reached. Returns whether the circuit was opened.
+    fn record_failure(&self, policy: &RetryPolicy) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.failures = state.failures.saturating_add(1);
+
+        if policy.failure_threshold == 0 || state.failures < policy.failure_threshold {
+            return false;
+        }
+
+        state.open_until = Some(Instant::now() + Duration::from_millis(policy.open_duration_ms));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum TestError {
+        Failed,
+        CircuitOpen,
+    }
+
+    impl From<CircuitOpen> for TestError {
+        fn from(_: CircuitOpen) -> Self {
+            Self::CircuitOpen
+        }
+    }
+
+    const fn policy(max_attempts: u32, failure_threshold: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 2,
+            jitter: 0.5,
+            failure_threshold,
+            open_duration_ms: 60_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_max_attempts() {
+        let breaker = CircuitBreaker::default();
+        let calls = AtomicU32::new(0);
+
+        // Fails until the third attempt
+        let res = policy(3, 0)
+            .retry(&breaker, "test", || async {
+                let call = calls.fetch_add(1, Ordering::Relaxed) + 1;
+                if call < 3 {
+                    Err(TestError::Failed)
+                } else {
+                    Ok(call)
+                }
+            })
+            .await;
+        assert_eq!(res, Ok(3));
+
+        // Gives up after the maximum number of attempts
+        calls.store(0, Ordering::Relaxed);
+        let res = policy(2, 0)
+            .retry(&breaker, "test", || async {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Err::<(), _>(TestError::Failed)
+            })
+            .await;
+        assert_eq!(res, Err(TestError::Failed));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        let breaker = CircuitBreaker::default();
+        let calls = AtomicU32::new(0);
+        let fail = || async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err::<(), _>(TestError::Failed)
+        };
+
+        // A success resets the consecutive failures
+        assert_eq!(policy(2, 3).retry(&breaker, "test", fail).await, Err(TestError::Failed));
+        let succeed = || async { Ok::<_, TestError>(()) };
+        assert_eq!(policy(2, 3).retry(&breaker, "test", succeed).await, Ok(()));
+
+        // The circuit opens after 3 consecutive failed attempts, across calls. The call that opens
+        // it gives up with its last error, without retrying.
+        assert_eq!(policy(2, 3).retry(&breaker, "test", fail).await, Err(TestError::Failed));
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+        assert_eq!(policy(2, 3).retry(&breaker, "test", fail).await, Err(TestError::Failed));
+        assert_eq!(calls.load(Ordering::Relaxed), 5);
+
+        // Calls are not attempted while the circuit is open
+        assert_eq!(policy(2, 3).retry(&breaker, "test", fail).await, Err(TestError::CircuitOpen));
+        assert_eq!(calls.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_circuit_breaker_half_open() {
+        let breaker = CircuitBreaker::default();
+        let policy = RetryPolicy { open_duration_ms: 10, ..policy(1, 1) };
+
+        assert!(breaker.record_failure(&policy));
+        assert!(!breaker.allows_attempt(&policy));
+
+        // Once the open duration elapsed, a single probe is let through
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(breaker.allows_attempt(&policy));
+        assert!(!breaker.allows_attempt(&policy));
+
+        // A failed probe re-opens the circuit, a successful one closes it
+        assert!(breaker.record_failure(&policy));
+        assert!(!breaker.allows_attempt(&policy));
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(breaker.allows_attempt(&policy));
+        breaker.record_success();
+        assert!(breaker.allows_attempt(&policy));
+        assert!(breaker.allows_attempt(&policy));
+    }
+}
@@ -1,14 +1,20 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{Arc, RwLock},
 };
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256, U256};
 use tracing::info;
+use url::Url;
 
 use crate::primitives::{
-    registry::{Deregistration, RegistryEntry},
-    SyncStateUpdate,
+    registry::{
+        whitelist_from_events, ContractEventStream, DataSource, Deregistration, DiscoveryFilter,
+        OperatorEvent, OperatorMetadata, OperatorSetEvent, OperatorSets, OperatorSettings,
+        OperatorStatus, RegistryEntry, RestakingProtocol, RpcEndpointUpdate, SourceConflict,
+        StakeSnapshot, WhitelistEvent, WhitelistedCollateral,
+    },
+    unix_seconds, SyncStateUpdate,
 };
 
 use super::{BlsPublicKey, DbResult, Operator, Registration, RegistryDb, SyncTransaction};
@@ -18,13 +24,34 @@ pub(crate) struct InMemoryDb {
     validator_registrations: Arc<RwLock<HashMap<BlsPublicKey, Registration>>>,
     index_to_pubkey: Arc<RwLock<HashMap<u64, BlsPublicKey>>>,
     operator_registrations: Arc<RwLock<HashMap<Address, Operator>>>,
-    sync_state: Arc<RwLock<SyncStateUpdate>>,
+    operator_settings: Arc<RwLock<HashMap<Address, OperatorSettings>>>,
+    nonces: Arc<RwLock<HashMap<BlsPublicKey, u64>>>,
+    operator_deregistration_nonces: Arc<RwLock<HashMap<Address, u64>>>,
+    operator_events: Arc<RwLock<Vec<OperatorEvent>>>,
+    whitelist_events: Arc<RwLock<Vec<WhitelistEvent>>>,
+    operator_set_events: Arc<RwLock<Vec<OperatorSetEvent>>>,
+    operator_sets: Arc<RwLock<OperatorSets>>,
+    contract_checkpoints: Arc<RwLock<BTreeMap<u64, B256>>>,
+    contract_event_streams: Arc<RwLock<HashSet<ContractEventStream>>>,
+    stake_snapshots: Arc<RwLock<BTreeMap<(Address, u64), StakeSnapshot>>>,
+    rpc_endpoint_history: Arc<RwLock<HashMap<Address, Vec<RpcEndpointUpdate>>>>,
+    source_conflicts: Arc<RwLock<HashMap<BlsPublicKey, SourceConflict>>>,
+    sync_state: Arc<RwLock<Option<SyncStateUpdate>>>,
 }
 
 pub(crate) struct InMemorySyncTransaction {
     validator_registrations: Arc<RwLock<HashMap<BlsPublicKey, Registration>>>,
     operator_registrations: Arc<RwLock<HashMap<Address, Operator>>>,
-    sync_state: Arc<RwLock<SyncStateUpdate>>,
+    operator_events: Arc<RwLock<Vec<OperatorEvent>>>,
+    whitelist_events: Arc<RwLock<Vec<WhitelistEvent>>>,
+    operator_set_events: Arc<RwLock<Vec<OperatorSetEvent>>>,
+    operator_sets: Arc<RwLock<OperatorSets>>,
+    contract_checkpoints: Arc<RwLock<BTreeMap<u64, B256>>>,
+    contract_event_streams: Arc<RwLock<HashSet<ContractEventStream>>>,
+    stake_snapshots: Arc<RwLock<BTreeMap<(Address, u64), StakeSnapshot>>>,
+    rpc_endpoint_history: Arc<RwLock<HashMap<Address, Vec<RpcEndpointUpdate>>>>,
+    source_conflicts: Arc<RwLock<HashMap<BlsPublicKey, SourceConflict>>>,
+    sync_state: Arc<RwLock<Option<SyncStateUpdate>>>,
 }
 
 #[async_trait::async_trait]
@@ -32,7 +59,19 @@ impl SyncTransaction for InMemorySyncTransaction {
     async fn register_validators(&mut self, registrations: &[Registration]) -> DbResult<()> {
         let mut cache = self.validator_registrations.write().unwrap();
         for registration in registrations {
-            cache.insert(registration.validator_pubkey.clone(), registration.clone());
+            let existing = cache.get(&registration.validator_pubkey);
+            if existing.is_some_and(|r| r.priority > registration.priority) {
+                continue;
+            }
+
+            // The re-delegation mark is kept until the validator changes operator
+            let needs_redelegation = existing
+                .is_some_and(|r| r.needs_redelegation && r.operator == registration.operator);
+
+            cache.insert(
+                registration.validator_pubkey.clone(),
+                Registration { needs_redelegation, ..registration.clone() },
+            );
         }
 
         Ok(())
@@ -40,19 +79,312 @@ impl SyncTransaction for InMemorySyncTransaction {
 
     async fn register_operator(&mut self, operator: Operator) -> DbResult<()> {
         let mut operators = self.operator_registrations.write().unwrap();
-        operators.insert(operator.signer, operator);
+        upsert_operator(&mut operators, operator);
+
+        Ok(())
+    }
+
+    async fn update_operator_status(
+        &mut self,
+        signer: Address,
+        status: OperatorStatus,
+    ) -> DbResult<()> {
+        let mut operators = self.operator_registrations.write().unwrap();
+        if let Some(operator) = operators.get_mut(&signer) {
+            operator.status = status;
+        }
+
+        Ok(())
+    }
+
+    async fn mark_for_redelegation(&mut self, operator: Address) -> DbResult<u64> {
+        let mut registrations = self.validator_registrations.write().unwrap();
+
+        let mut count = 0;
+        for registration in registrations.values_mut() {
+            if registration.operator == operator && !registration.needs_redelegation {
+                registration.needs_redelegation = true;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn get_operator_signers(&mut self) -> DbResult<Vec<Address>> {
+        let operators = self.operator_registrations.read().unwrap();
+        Ok(operators
+            .values()
+            .filter(|o| o.status != OperatorStatus::Deregistered)
+            .map(|o| o.signer)
+            .collect())
+    }
+
+    async fn update_operator_collateral(
+        &mut self,
+        signer: Address,
+        tokens: Vec<Address>,
+        amounts: Vec<U256>,
+    ) -> DbResult<()> {
+        let mut operators = self.operator_registrations.write().unwrap();
+        if let Some(operator) = operators.get_mut(&signer) {
+            operator.collateral_tokens = tokens;
+            operator.collateral_amounts = amounts;
+        }
+
+        Ok(())
+    }
+
+    async fn update_operator_rpc_endpoint(&mut self, update: &RpcEndpointUpdate) -> DbResult<bool> {
+        let mut operators = self.operator_registrations.write().unwrap();
+        let Some(operator) = operators.get_mut(&update.operator) else {
+            return Ok(false);
+        };
+
+        // The current endpoint is always overwritten, as rollbacks restore it from the last
+        // registration event
+        operator.rpc_endpoint = update.rpc_endpoint.clone();
+
+        let mut history = self.rpc_endpoint_history.write().unwrap();
+        let history = history.entry(update.operator).or_default();
+        if history.last().is_some_and(|last| last.rpc_endpoint == update.rpc_endpoint) {
+            return Ok(false);
+        }
+
+        history.retain(|u| u.block_number != update.block_number);
+        history.push(update.clone());
+
+        Ok(true)
+    }
+
+    async fn record_operator_event(&mut self, event: &OperatorEvent) -> DbResult<()> {
+        let mut events = self.operator_events.write().unwrap();
+        events.push(event.clone());
+
+        Ok(())
+    }
+
+    async fn update_registration_metadata(
+        &mut self,
+        block_number: u64,
+        log_index: u64,
+        metadata: Option<&OperatorMetadata>,
+    ) -> DbResult<()> {
+        let mut events = self.operator_events.write().unwrap();
+        let Some(event) =
+            events.iter_mut().find(|e| e.block_number == block_number && e.log_index == log_index)
+        else {
+            return Ok(());
+        };
+        event.metadata = metadata.cloned();
+
+        let mut operators = self.operator_registrations.write().unwrap();
+        if let Some(operator) =
+            operators.get_mut(&event.signer).filter(|o| o.source == DataSource::Onchain)
+        {
+            operator.metadata = metadata.cloned();
+        }
+
+        Ok(())
+    }
+
+    async fn record_whitelist_event(&mut self, event: &WhitelistEvent) -> DbResult<()> {
+        let mut events = self.whitelist_events.write().unwrap();
+        events.push(event.clone());
+
+        Ok(())
+    }
+
+    async fn record_operator_set_event(&mut self, event: &OperatorSetEvent) -> DbResult<()> {
+        let mut events = self.operator_set_events.write().unwrap();
+        events.push(event.clone());
+        self.operator_sets.write().unwrap().apply(event);
+
+        Ok(())
+    }
+
+    async fn rollback_contract_events(&mut self, block_number: u64) -> DbResult<()> {
+        let mut events = self.operator_events.write().unwrap();
+        let (kept, reverted): (Vec<_>, Vec<_>) =
+            events.drain(..).partition(|e| e.block_number <= block_number);
+        *events = kept;
+        let signers = reverted.into_iter().map(|e| e.signer).collect::<HashSet<_>>();
+
+        // Lock in the same order as the discovery reads
+        let mut registrations = self.validator_registrations.write().unwrap();
+        let mut operators = self.operator_registrations.write().unwrap();
+        for &signer in &signers {
+            let remaining = events.iter().filter(|e| e.signer == signer).collect::<Vec<_>>();
+
+            // On-chain operators whose registration was reverted are removed, unless validators
+            // registered to them in the meantime
+            if remaining.is_empty() &&
+                operators.get(&signer).is_some_and(|o| o.source == DataSource::Onchain) &&
+                !registrations.values().any(|r| r.operator == signer)
+            {
+                operators.remove(&signer);
+                continue;
+            }
+
+            let Some(operator) = operators.get_mut(&signer) else { continue };
+
+            operator.status = match remaining.last() {
+                Some(event) => event.status,
+                None if operator.source == DataSource::Onchain => OperatorStatus::Deregistered,
+                // Operators of other sources have no on-chain status left
+                None => OperatorStatus::Active,
+            };
+            // Registrations are the only events carrying an RPC endpoint
+            if let Some(registration) = remaining.iter().rev().find(|e| e.rpc_endpoint.is_some()) {
+                operator.protocol = registration.protocol;
+                operator.metadata.clone_from(&registration.metadata);
+            }
+
+            if operator.status != OperatorStatus::Deregistered {
+                for registration in registrations.values_mut().filter(|r| r.operator == signer) {
+                    registration.needs_redelegation = false;
+                }
+            }
+        }
+
+        // Endpoint updates observed after the given block are reverted as well
+        let mut history = self.rpc_endpoint_history.write().unwrap();
+        let mut rpc_signers = signers;
+        for (signer, updates) in history.iter_mut() {
+            let len = updates.len();
+            updates.retain(|u| u.block_number <= block_number);
+            if updates.len() < len {
+                rpc_signers.insert(*signer);
+            }
+        }
+
+        // Restore the endpoint of the last remaining registration or update, updates taking
+        // precedence within the same block
+        for signer in rpc_signers {
+            let Some(operator) = operators.get_mut(&signer) else { continue };
+
+            let last_registration = events
+                .iter()
+                .rev()
+                .filter(|e| e.signer == signer)
+                .find_map(|e| Some((e.block_number, e.rpc_endpoint.as_ref()?)));
+            let last_update = history
+                .get(&signer)
+                .and_then(|h| h.last())
+                .map(|u| (u.block_number, &u.rpc_endpoint));
+            if let Some((_, rpc_endpoint)) =
+                last_registration.into_iter().chain(last_update).max_by_key(|(n, _)| *n)
+            {
+                operator.rpc_endpoint.clone_from(rpc_endpoint);
+            }
+        }
+
+        self.whitelist_events.write().unwrap().retain(|e| e.block_number <= block_number);
+
+        // Rebuild the affected operator sets from their remaining events
+        let mut set_events = self.operator_set_events.write().unwrap();
+        let (kept, reverted): (Vec<_>, Vec<_>) =
+            set_events.drain(..).partition(|e| e.block_number <= block_number);
+        *set_events = kept;
+        let operator_set_ids = reverted.into_iter().map(|e| e.operator_set_id).collect();
+        self.operator_sets.write().unwrap().rebuild(&operator_set_ids, set_events.iter());
+
+        self.contract_checkpoints.write().unwrap().retain(|n, _| *n <= block_number);
+
+        Ok(())
+    }
+
+    async fn get_contract_checkpoints(&mut self, limit: usize) -> DbResult<Vec<(u64, B256)>> {
+        let checkpoints = self.contract_checkpoints.read().unwrap();
+        Ok(checkpoints.iter().rev().take(limit).map(|(n, h)| (*n, *h)).collect())
+    }
+
+    async fn insert_contract_checkpoint(
+        &mut self,
+        block_number: u64,
+        block_hash: B256,
+        keep: usize,
+    ) -> DbResult<()> {
+        let mut checkpoints = self.contract_checkpoints.write().unwrap();
+        checkpoints.insert(block_number, block_hash);
+        while checkpoints.len() > keep {
+            checkpoints.pop_first();
+        }
+
+        Ok(())
+    }
+
+    async fn get_contract_event_streams(&mut self) -> DbResult<Vec<ContractEventStream>> {
+        Ok(self.contract_event_streams.read().unwrap().iter().copied().collect())
+    }
+
+    async fn insert_contract_event_streams(
+        &mut self,
+        streams: &[ContractEventStream],
+    ) -> DbResult<()> {
+        self.contract_event_streams.write().unwrap().extend(streams);
+
+        Ok(())
+    }
+
+    async fn get_last_stake_snapshot_epoch(&mut self) -> DbResult<Option<u64>> {
+        let snapshots = self.stake_snapshots.read().unwrap();
+        Ok(snapshots.keys().map(|(_, epoch)| *epoch).max())
+    }
+
+    async fn insert_stake_snapshot(&mut self, snapshot: &StakeSnapshot) -> DbResult<()> {
+        let mut snapshots = self.stake_snapshots.write().unwrap();
+        snapshots.insert((snapshot.operator, snapshot.epoch), snapshot.clone());
+
+        Ok(())
+    }
+
+    async fn update_source_conflicts(
+        &mut self,
+        pubkeys: &[BlsPublicKey],
+        conflicts: &[SourceConflict],
+    ) -> DbResult<()> {
+        let mut source_conflicts = self.source_conflicts.write().unwrap();
+        for pubkey in pubkeys {
+            source_conflicts.remove(pubkey);
+        }
+
+        for conflict in conflicts {
+            source_conflicts.insert(conflict.validator_pubkey.clone(), conflict.clone());
+        }
 
         Ok(())
     }
 
     async fn commit(self, state: SyncStateUpdate) -> DbResult<()> {
         let mut sync_state = self.sync_state.write().unwrap();
-        *sync_state = state;
+        *sync_state = Some(state);
 
         Ok(())
     }
 }
 
+impl InMemoryDb {
+    /// Applies the stored settings of the operator to the given operator.
+    fn with_settings(&self, mut operator: Operator) -> Operator {
+        let settings = self.operator_settings.read().unwrap();
+        operator.requires_acceptance =
+            settings.get(&operator.signer).is_some_and(|s| s.requires_acceptance);
+
+        operator
+    }
+
+    /// Applies the materialized EigenLayer operator sets to the given operators.
+    fn with_operator_sets(&self, mut operators: Vec<Operator>) -> Vec<Operator> {
+        let sets = self.operator_sets.read().unwrap();
+        for operator in &mut operators {
+            operator.operator_sets = sets.memberships(operator.signer);
+        }
+
+        operators
+    }
+}
+
 #[async_trait::async_trait]
 impl RegistryDb for InMemoryDb {
     type SyncTransaction = InMemorySyncTransaction;
@@ -61,6 +393,15 @@ impl RegistryDb for InMemoryDb {
         Ok(InMemorySyncTransaction {
             validator_registrations: Arc::clone(&self.validator_registrations),
             operator_registrations: Arc::clone(&self.operator_registrations),
+            operator_events: Arc::clone(&self.operator_events),
+            whitelist_events: Arc::clone(&self.whitelist_events),
+            operator_set_events: Arc::clone(&self.operator_set_events),
+            operator_sets: Arc::clone(&self.operator_sets),
+            contract_checkpoints: Arc::clone(&self.contract_checkpoints),
+            contract_event_streams: Arc::clone(&self.contract_event_streams),
+            stake_snapshots: Arc::clone(&self.stake_snapshots),
+            rpc_endpoint_history: Arc::clone(&self.rpc_endpoint_history),
+            source_conflicts: Arc::clone(&self.source_conflicts),
             sync_state: Arc::clone(&self.sync_state),
         })
     }
@@ -69,8 +410,7 @@ impl RegistryDb for InMemoryDb {
         info!(signer = %operator.signer, "InMemoryDb: register_operator");
 
         let mut operators = self.operator_registrations.write().unwrap();
-
-        operators.insert(operator.signer, operator);
+        upsert_operator(&mut operators, operator);
 
         Ok(())
     }
@@ -80,10 +420,12 @@ impl RegistryDb for InMemoryDb {
 
         let mut cache = self.validator_registrations.write().unwrap();
         let mut index_cache = self.index_to_pubkey.write().unwrap();
+        let mut nonces = self.nonces.write().unwrap();
 
         for registration in registrations {
             cache.insert(registration.validator_pubkey.clone(), registration.clone());
             index_cache.insert(registration.validator_index, registration.validator_pubkey.clone());
+            nonces.insert(registration.validator_pubkey.clone(), registration.nonce);
         }
 
         Ok(())
@@ -93,17 +435,84 @@ impl RegistryDb for InMemoryDb {
         info!(count = deregistrations.len(), "InMemoryDb: deregister_validators");
 
         let mut cache = self.validator_registrations.write().unwrap();
+        let mut nonces = self.nonces.write().unwrap();
+
         for deregistration in deregistrations {
             cache.remove(&deregistration.validator_pubkey);
+            nonces.insert(deregistration.validator_pubkey.clone(), deregistration.nonce);
+        }
+
+        Ok(())
+    }
+
+    async fn operator_deregister_validators(
+        &self,
+        operator: Address,
+        pubkeys: &[BlsPublicKey],
+        nonce: u64,
+    ) -> DbResult<()> {
+        info!(count = pubkeys.len(), %operator, "InMemoryDb: operator_deregister_validators");
+
+        let mut cache = self.validator_registrations.write().unwrap();
+        let mut nonces = self.operator_deregistration_nonces.write().unwrap();
+
+        for pubkey in pubkeys {
+            cache.remove(pubkey);
         }
+        nonces.insert(operator, nonce);
+
+        Ok(())
+    }
+
+    async fn get_operator_deregistration_nonce(&self, operator: Address) -> DbResult<Option<u64>> {
+        let nonces = self.operator_deregistration_nonces.read().unwrap();
+        Ok(nonces.get(&operator).copied())
+    }
+
+    async fn get_nonces(&self, pubkeys: &[BlsPublicKey]) -> DbResult<HashMap<BlsPublicKey, u64>> {
+        let nonces = self.nonces.read().unwrap();
+
+        Ok(pubkeys
+            .iter()
+            .filter_map(|pubkey| nonces.get(pubkey).map(|nonce| (pubkey.clone(), *nonce)))
+            .collect())
+    }
+
+    async fn get_operator_settings(&self, signer: Address) -> DbResult<OperatorSettings> {
+        let settings = self.operator_settings.read().unwrap();
+
+        Ok(settings.get(&signer).copied().unwrap_or_default())
+    }
+
+    async fn update_operator_settings(
+        &self,
+        signer: Address,
+        settings: OperatorSettings,
+    ) -> DbResult<()> {
+        info!(%signer, "InMemoryDb: update_operator_settings");
+
+        let mut operator_settings = self.operator_settings.write().unwrap();
+        operator_settings.insert(signer, settings);
 
         Ok(())
     }
 
+    async fn prune_expired_registrations(&self, now: u64) -> DbResult<u64> {
+        let mut cache = self.validator_registrations.write().unwrap();
+        let mut index_cache = self.index_to_pubkey.write().unwrap();
+
+        let count = cache.len();
+        cache.retain(|_, r| !r.is_expired(now));
+        index_cache.retain(|_, pubkey| cache.contains_key(pubkey));
+
+        Ok((count - cache.len()) as u64)
+    }
+
     async fn list_registrations(&self) -> DbResult<Vec<Registration>> {
         let registrations = self.validator_registrations.read().unwrap();
+        let now = unix_seconds();
 
-        Ok(registrations.values().cloned().collect())
+        Ok(registrations.values().filter(|r| !r.is_expired(now)).cloned().collect())
     }
 
     async fn get_registrations_by_pubkey(
@@ -111,18 +520,28 @@ impl RegistryDb for InMemoryDb {
         pubkeys: &[BlsPublicKey],
     ) -> DbResult<Vec<Registration>> {
         let registrations = self.validator_registrations.read().unwrap();
+        let now = unix_seconds();
 
-        Ok(pubkeys.iter().filter_map(|pubkey| registrations.get(pubkey)).cloned().collect())
+        Ok(pubkeys
+            .iter()
+            .filter_map(|pubkey| registrations.get(pubkey))
+            .filter(|r| !r.is_expired(now))
+            .cloned()
+            .collect())
     }
 
-    async fn list_validators(&self) -> DbResult<Vec<RegistryEntry>> {
+    async fn list_validators(&self, filter: DiscoveryFilter) -> DbResult<Vec<RegistryEntry>> {
         let registrations = self.validator_registrations.read().unwrap();
         let operators = self.operator_registrations.read().unwrap();
+        let now = unix_seconds();
 
         let entries = registrations
             .values()
             .filter_map(|r| {
                 let op = operators.get(&r.operator)?;
+                if !is_discoverable(r, op, now) || !filter.matches(op.protocol, r.source) {
+                    return None;
+                }
 
                 Some(RegistryEntry {
                     validator_pubkey: r.validator_pubkey.clone(),
@@ -142,12 +561,16 @@ impl RegistryDb for InMemoryDb {
     ) -> DbResult<Vec<RegistryEntry>> {
         let registrations = self.validator_registrations.read().unwrap();
         let operators = self.operator_registrations.read().unwrap();
+        let now = unix_seconds();
 
         Ok(pubkeys
             .iter()
             .filter_map(|pubkey| {
                 let registration = registrations.get(pubkey)?;
                 let operator = operators.get(&registration.operator)?;
+                if !is_discoverable(registration, operator, now) {
+                    return None;
+                }
 
                 Some(RegistryEntry {
                     validator_pubkey: registration.validator_pubkey.clone(),
@@ -163,6 +586,7 @@ impl RegistryDb for InMemoryDb {
         let registrations = self.validator_registrations.read().unwrap();
         let operators = self.operator_registrations.read().unwrap();
         let index_cache = self.index_to_pubkey.read().unwrap();
+        let now = unix_seconds();
 
         Ok(indices
             .iter()
@@ -170,6 +594,9 @@ impl RegistryDb for InMemoryDb {
                 let pubkey = index_cache.get(&index)?;
                 let registration = registrations.get(pubkey)?;
                 let operator = operators.get(&registration.operator)?;
+                if !is_discoverable(registration, operator, now) {
+                    return None;
+                }
 
                 Some(RegistryEntry {
                     validator_pubkey: registration.validator_pubkey.clone(),
@@ -181,26 +608,817 @@ impl RegistryDb for InMemoryDb {
             .collect())
     }
 
-    async fn list_operators(&self) -> DbResult<Vec<Operator>> {
+    async fn list_operators(&self, filter: DiscoveryFilter) -> DbResult<Vec<Operator>> {
         let operators = self.operator_registrations.read().unwrap();
 
-        Ok(operators.values().cloned().collect())
+        Ok(self.with_operator_sets(
+            operators
+                .values()
+                .filter(|o| filter.matches(o.protocol, o.source))
+                .map(|o| self.with_settings(o.clone()))
+                .collect(),
+        ))
     }
 
     async fn get_operators_by_signer(&self, signers: &[Address]) -> DbResult<Vec<Operator>> {
         let operators = self.operator_registrations.read().unwrap();
 
-        Ok(signers.iter().filter_map(|signer| operators.get(signer).cloned()).collect())
+        Ok(self.with_operator_sets(
+            signers
+                .iter()
+                .filter_map(|signer| operators.get(signer).map(|o| self.with_settings(o.clone())))
+                .collect(),
+        ))
     }
 
-    async fn get_sync_state(&self) -> DbResult<SyncStateUpdate> {
+    async fn list_whitelisted_collateral(
+        &self,
+        protocol: Option<RestakingProtocol>,
+    ) -> DbResult<Vec<WhitelistedCollateral>> {
+        let events = self.whitelist_events.read().unwrap();
+
+        Ok(whitelist_from_events(
+            events.iter().filter(|e| protocol.is_none_or(|p| e.protocol == p)),
+        ))
+    }
+
+    async fn list_rpc_endpoint_history(&self, signer: Address) -> DbResult<Vec<RpcEndpointUpdate>> {
+        let history = self.rpc_endpoint_history.read().unwrap();
+        Ok(history.get(&signer).cloned().unwrap_or_default())
+    }
+
+    async fn get_rpc_endpoints_at(
+        &self,
+        signers: &[Address],
+        epoch: u64,
+    ) -> DbResult<HashMap<Address, Url>> {
+        let history = self.rpc_endpoint_history.read().unwrap();
+
+        Ok(signers
+            .iter()
+            .filter_map(|signer| {
+                let update = history.get(signer)?.iter().rev().find(|u| u.epoch <= epoch)?;
+                Some((*signer, update.rpc_endpoint.clone()))
+            })
+            .collect())
+    }
+
+    async fn get_stake_snapshot(
+        &self,
+        signer: Address,
+        epoch: u64,
+    ) -> DbResult<Option<StakeSnapshot>> {
+        let snapshots = self.stake_snapshots.read().unwrap();
+        Ok(snapshots.get(&(signer, epoch)).cloned())
+    }
+
+    async fn list_stake_snapshots(&self, signer: Address) -> DbResult<Vec<StakeSnapshot>> {
+        let snapshots = self.stake_snapshots.read().unwrap();
+        Ok(snapshots.range((signer, 0)..=(signer, u64::MAX)).map(|(_, s)| s.clone()).collect())
+    }
+
+    async fn list_source_conflicts(&self) -> DbResult<Vec<SourceConflict>> {
+        let source_conflicts = self.source_conflicts.read().unwrap();
+        Ok(source_conflicts.values().cloned().collect())
+    }
+
+    async fn get_sync_state(&self) -> DbResult<Option<SyncStateUpdate>> {
         let sync_state = self.sync_state.read().unwrap();
         Ok(sync_state.clone())
     }
 
     async fn update_sync_state(&self, state: SyncStateUpdate) -> DbResult<()> {
         let mut sync_state = self.sync_state.write().unwrap();
-        *sync_state = state;
+        *sync_state = Some(state);
+
+        Ok(())
+    }
+}
+
+/// Inserts or overwrites an operator. On-chain operators keep their status, protocol and
+/// metadata, which external sources don't know about, so they are only overwritten by on-chain
+/// data.
+fn upsert_operator(operators: &mut HashMap<Address, Operator>, operator: Operator) {
+    let is_onchain = |o: &Operator| o.source == DataSource::Onchain;
+    if operators.get(&operator.signer).is_some_and(|o| is_onchain(o) && !is_onchain(&operator)) {
+        return;
+    }
+
+    operators.insert(operator.signer, operator);
+}
+
+/// Returns `true` if the validator of the given registration is served by discovery at `now`:
+/// its registration is not expired nor marked for re-delegation, and its operator is active.
+fn is_discoverable(registration: &Registration, operator: &Operator, now: u64) -> bool {
+    !registration.is_expired(now) &&
+        !registration.needs_redelegation &&
+        operator.status == OperatorStatus::Active
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::primitives::registry::{
+        OperatorSet, OperatorSetChange, OperatorStatus, SourceClaim, WhitelistAction,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_nonces_survive_deregistration() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let pubkey = BlsPublicKey::random();
+        let operator = Address::random();
+
+        let registration = Registration {
+            validator_pubkey: pubkey.clone(),
+            validator_index: 0,
+            operator,
+            gas_limit: 10_000,
+            expiry: 0,
+            nonce: 1,
+            signature: None,
+            source: DataSource::Api,
+            priority: Registration::SIGNED_PRIORITY,
+            needs_redelegation: false,
+        };
+
+        db.register_validators(&[registration]).await?;
+        assert_eq!(db.get_nonces(&[pubkey.clone()]).await?.get(&pubkey), Some(&1));
+
+        let deregistration = Deregistration {
+            validator_pubkey: pubkey.clone(),
+            operator,
+            nonce: 2,
+            signature: None,
+        };
+
+        db.deregister_validators(&[deregistration]).await?;
+        assert!(db.get_registrations_by_pubkey(&[pubkey.clone()]).await?.is_empty());
+        assert_eq!(db.get_nonces(&[pubkey.clone()]).await?.get(&pubkey), Some(&2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_operator_deregistration_nonces() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let pubkey = BlsPublicKey::random();
+        let operator = Address::random();
+
+        let registration = Registration {
+            validator_pubkey: pubkey.clone(),
+            validator_index: 0,
+            operator,
+            gas_limit: 10_000,
+            expiry: 0,
+            nonce: 1,
+            signature: None,
+            source: DataSource::Api,
+            priority: Registration::SIGNED_PRIORITY,
+            needs_redelegation: false,
+        };
+
+        db.register_validators(&[registration.clone()]).await?;
+        db.operator_deregister_validators(operator, &[pubkey.clone()], u64::MAX).await?;
+        assert!(db.get_registrations_by_pubkey(&[pubkey.clone()]).await?.is_empty());
+        assert_eq!(db.get_operator_deregistration_nonce(operator).await?, Some(u64::MAX));
+
+        // The validator nonce is untouched, so the validator can register again
+        assert_eq!(db.get_nonces(&[pubkey.clone()]).await?.get(&pubkey), Some(&1));
+        assert_eq!(db.get_operator_deregistration_nonce(Address::random()).await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_registrations() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let operator = Operator {
+            signer: Address::random(),
+            rpc_endpoint: "https://rpc.example.com".parse()?,
+            collateral_tokens: vec![],
+            collateral_amounts: vec![],
+            protocol: None,
+            source: DataSource::Onchain,
+            metadata: None,
+            requires_acceptance: false,
+            status: OperatorStatus::Active,
+            operator_sets: vec![],
+        };
+        db.register_operator(operator.clone()).await?;
+
+        let now = unix_seconds();
+        let registrations = [0, now - 1, now + 3600]
+            .into_iter()
+            .enumerate()
+            .map(|(i, expiry)| Registration {
+                validator_pubkey: BlsPublicKey::random(),
+                validator_index: i as u64,
+                operator: operator.signer,
+                gas_limit: 10_000,
+                expiry,
+                nonce: 1,
+                signature: None,
+                source: DataSource::Api,
+                priority: Registration::SIGNED_PRIORITY,
+                needs_redelegation: false,
+            })
+            .collect::<Vec<_>>();
+        let pubkeys = registrations.iter().map(|r| r.validator_pubkey.clone()).collect::<Vec<_>>();
+
+        db.register_validators(&registrations).await?;
+
+        // The expired registration is hidden from every read path
+        assert_eq!(db.list_registrations().await?.len(), 2);
+        assert_eq!(db.get_registrations_by_pubkey(&pubkeys).await?.len(), 2);
+        assert_eq!(db.list_validators(DiscoveryFilter::default()).await?.len(), 2);
+        assert_eq!(db.get_validators_by_pubkey(&pubkeys).await?.len(), 2);
+        assert_eq!(db.get_validators_by_index(vec![0, 1, 2]).await?.len(), 2);
+        assert!(db.get_validators_by_pubkey(&pubkeys[1..2]).await?.is_empty());
+
+        // Pruning removes it, but keeps its nonce
+        assert_eq!(db.prune_expired_registrations(now).await?, 1);
+        assert_eq!(db.prune_expired_registrations(now).await?, 0);
+        assert!(!db.validator_registrations.read().unwrap().contains_key(&pubkeys[1]));
+        assert!(!db.index_to_pubkey.read().unwrap().contains_key(&1));
+        assert_eq!(db.get_nonces(&pubkeys[1..2]).await?.get(&pubkeys[1]), Some(&1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rollback_operator_events() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let (a, b) = (Address::random(), Address::random());
+        let (rpc_1, rpc_2): (Url, Url) =
+            ("https://one.bolt.test".parse()?, "https://two.bolt.test".parse()?);
+
+        let events = [
+            (1, a, OperatorStatus::Active, Some(rpc_1.clone())),
+            (2, a, OperatorStatus::Paused, None),
+            (3, a, OperatorStatus::Active, None),
+            (3, b, OperatorStatus::Active, Some(rpc_2.clone())),
+            (4, a, OperatorStatus::Deregistered, None),
+            (4, a, OperatorStatus::Active, Some(rpc_2.clone())),
+        ];
+
+        for (log_index, (block_number, signer, status, rpc_endpoint)) in
+            events.into_iter().enumerate()
+        {
+            if let Some(rpc_endpoint) = rpc_endpoint.clone() {
+                tx.register_operator(Operator {
+                    signer,
+                    rpc_endpoint,
+                    collateral_tokens: vec![],
+                    collateral_amounts: vec![],
+                    protocol: None,
+                    source: DataSource::Onchain,
+                    metadata: None,
+                    requires_acceptance: false,
+                    status,
+                    operator_sets: vec![],
+                })
+                .await?;
+            } else {
+                tx.update_operator_status(signer, status).await?;
+            }
+
+            let event = OperatorEvent {
+                block_number,
+                log_index: log_index as u64,
+                signer,
+                status,
+                rpc_endpoint,
+                protocol: None,
+                metadata: None,
+            };
+            tx.record_operator_event(&event).await?;
+            tx.insert_contract_checkpoint(
+                block_number,
+                B256::with_last_byte(block_number as u8),
+                usize::MAX,
+            )
+            .await?;
+        }
+
+        tx.rollback_contract_events(2).await?;
+
+        let operators = db.get_operators_by_signer(&[a, b]).await?;
+        let a = operators.iter().find(|o| o.signer == a).unwrap();
+
+        // Restored from the remaining events
+        assert_eq!(a.status, OperatorStatus::Paused);
+        assert_eq!(a.rpc_endpoint, rpc_1);
+        // Registered in a rolled back block only
+        assert!(operators.iter().all(|o| o.signer != b));
+
+        let checkpoints = tx.get_contract_checkpoints(usize::MAX).await?;
+        assert_eq!(checkpoints, vec![(2, B256::with_last_byte(2)), (1, B256::with_last_byte(1))]);
+
+        // Only the newest checkpoints are kept and queried
+        tx.insert_contract_checkpoint(3, B256::with_last_byte(3), 2).await?;
+        let checkpoints = tx.get_contract_checkpoints(usize::MAX).await?;
+        assert_eq!(checkpoints, vec![(3, B256::with_last_byte(3)), (2, B256::with_last_byte(2))]);
+        assert_eq!(tx.get_contract_checkpoints(1).await?, vec![(3, B256::with_last_byte(3))]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_whitelist_events() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let (vault, strategy) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let removed = Address::with_last_byte(3);
+
+        let events = [
+            (1, RestakingProtocol::Symbiotic, vault, WhitelistAction::Whitelisted),
+            (1, RestakingProtocol::EigenLayer, strategy, WhitelistAction::Whitelisted),
+            (2, RestakingProtocol::Symbiotic, removed, WhitelistAction::Whitelisted),
+            (2, RestakingProtocol::Symbiotic, vault, WhitelistAction::Paused),
+            (3, RestakingProtocol::Symbiotic, removed, WhitelistAction::Removed),
+            (3, RestakingProtocol::EigenLayer, strategy, WhitelistAction::Paused),
+        ];
+
+        for (log_index, (block_number, protocol, address, action)) in events.into_iter().enumerate()
+        {
+            let event = WhitelistEvent {
+                block_number,
+                log_index: log_index as u64,
+                protocol,
+                address,
+                action,
+            };
+            tx.record_whitelist_event(&event).await?;
+        }
+
+        let whitelisted =
+            |protocol, address, paused| WhitelistedCollateral { protocol, address, paused };
+
+        assert_eq!(
+            db.list_whitelisted_collateral(None).await?,
+            vec![
+                whitelisted(RestakingProtocol::Symbiotic, vault, true),
+                whitelisted(RestakingProtocol::EigenLayer, strategy, true),
+            ]
+        );
+
+        // Rolling back restores the removed vault and unpauses the strategy
+        tx.rollback_contract_events(2).await?;
+
+        assert_eq!(
+            db.list_whitelisted_collateral(Some(RestakingProtocol::Symbiotic)).await?,
+            vec![
+                whitelisted(RestakingProtocol::Symbiotic, vault, true),
+                whitelisted(RestakingProtocol::Symbiotic, removed, false),
+            ]
+        );
+        assert_eq!(
+            db.list_whitelisted_collateral(Some(RestakingProtocol::EigenLayer)).await?,
+            vec![whitelisted(RestakingProtocol::EigenLayer, strategy, false)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stake_snapshots() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+        assert_eq!(tx.get_last_stake_snapshot_epoch().await?, None);
+
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let token = Address::with_last_byte(3);
+
+        for (operator, epoch) in [(a, 2), (b, 2), (a, 1), (a, 3)] {
+            let snapshot = StakeSnapshot {
+                operator,
+                epoch,
+                timestamp: epoch * 100,
+                collateral_tokens: vec![token],
+                collateral_amounts: vec![U256::from(epoch)],
+            };
+            tx.insert_stake_snapshot(&snapshot).await?;
+        }
+
+        assert_eq!(tx.get_last_stake_snapshot_epoch().await?, Some(3));
+
+        let history = db.list_stake_snapshots(a).await?;
+        assert_eq!(history.iter().map(|s| s.epoch).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let snapshot = db.get_stake_snapshot(b, 2).await?.unwrap();
+        assert_eq!(snapshot.collateral_amounts, vec![U256::from(2)]);
+        assert!(db.get_stake_snapshot(b, 3).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rpc_endpoint_history() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let (rpc_1, rpc_2): (Url, Url) =
+            ("https://one.bolt.test".parse()?, "https://two.bolt.test".parse()?);
+
+        tx.register_operator(Operator {
+            signer: a,
+            rpc_endpoint: rpc_1.clone(),
+            collateral_tokens: vec![],
+            collateral_amounts: vec![],
+            protocol: None,
+            source: DataSource::Onchain,
+            metadata: None,
+            requires_acceptance: false,
+            status: OperatorStatus::Active,
+            operator_sets: vec![],
+        })
+        .await?;
+
+        let update = |operator, rpc_endpoint: &Url, block_number, epoch| RpcEndpointUpdate {
+            operator,
+            rpc_endpoint: rpc_endpoint.clone(),
+            block_number,
+            epoch,
+        };
+
+        // Unknown operators are ignored, and unchanged endpoints are not recorded twice
+        assert!(!tx.update_operator_rpc_endpoint(&update(b, &rpc_1, 10, 1)).await?);
+        assert!(tx.update_operator_rpc_endpoint(&update(a, &rpc_1, 10, 1)).await?);
+        assert!(!tx.update_operator_rpc_endpoint(&update(a, &rpc_1, 20, 2)).await?);
+        assert!(tx.update_operator_rpc_endpoint(&update(a, &rpc_2, 30, 3)).await?);
+
+        let history = db.list_rpc_endpoint_history(a).await?;
+        assert_eq!(history.iter().map(|u| u.epoch).collect::<Vec<_>>(), vec![1, 3]);
+        assert!(db.list_rpc_endpoint_history(b).await?.is_empty());
+        assert_eq!(db.get_operators_by_signer(&[a]).await?[0].rpc_endpoint, rpc_2);
+
+        // The endpoint served for an epoch is the last one effective at or before it
+        assert!(db.get_rpc_endpoints_at(&[a, b], 0).await?.is_empty());
+        assert_eq!(db.get_rpc_endpoints_at(&[a, b], 2).await?.get(&a), Some(&rpc_1));
+        assert_eq!(db.get_rpc_endpoints_at(&[a], 3).await?.get(&a), Some(&rpc_2));
+
+        // Rollbacks revert later updates and restore the last remaining endpoint
+        tx.rollback_contract_events(25).await?;
+        assert_eq!(db.list_rpc_endpoint_history(a).await?.len(), 1);
+        assert_eq!(db.get_operators_by_signer(&[a]).await?[0].rpc_endpoint, rpc_1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_discovery_filter() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let operators = [
+            (Address::with_last_byte(1), Some(RestakingProtocol::Symbiotic), DataSource::Onchain),
+            (Address::with_last_byte(2), Some(RestakingProtocol::EigenLayer), DataSource::Onchain),
+            (Address::with_last_byte(3), None, DataSource::Lido),
+        ];
+
+        for (signer, protocol, source) in operators {
+            db.register_operator(Operator {
+                signer,
+                rpc_endpoint: "https://rpc.example.com".parse()?,
+                collateral_tokens: vec![],
+                collateral_amounts: vec![],
+                protocol,
+                source,
+                metadata: None,
+                requires_acceptance: false,
+                status: OperatorStatus::Active,
+                operator_sets: vec![],
+            })
+            .await?;
+
+            // Validators of on-chain operators register through the API
+            let registration_source =
+                if source == DataSource::Lido { DataSource::Lido } else { DataSource::Api };
+            db.register_validators(&[Registration {
+                validator_pubkey: BlsPublicKey::random(),
+                validator_index: signer[19] as u64,
+                operator: signer,
+                gas_limit: 10_000,
+                expiry: 0,
+                nonce: 1,
+                signature: None,
+                source: registration_source,
+                priority: 0,
+                needs_redelegation: false,
+            }])
+            .await?;
+        }
+
+        let filter = |protocol, source| DiscoveryFilter { protocol, source };
+
+        assert_eq!(db.list_operators(filter(None, None)).await?.len(), 3);
+        assert_eq!(db.list_operators(filter(None, Some(DataSource::Onchain))).await?.len(), 2);
+        let symbiotic = db.list_operators(filter(Some(RestakingProtocol::Symbiotic), None)).await?;
+        assert_eq!(symbiotic.iter().map(|o| o.signer).collect::<Vec<_>>(), vec![operators[0].0]);
+
+        assert_eq!(db.list_validators(filter(None, Some(DataSource::Api))).await?.len(), 2);
+        let eigenlayer = filter(Some(RestakingProtocol::EigenLayer), Some(DataSource::Api));
+        let validators = db.list_validators(eigenlayer).await?;
+        assert_eq!(validators.iter().map(|v| v.operator).collect::<Vec<_>>(), vec![operators[1].0]);
+        let lido = filter(Some(RestakingProtocol::Symbiotic), Some(DataSource::Lido));
+        assert!(db.list_validators(lido).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_inactive_operators_hide_validators() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        for signer in [a, b] {
+            tx.register_operator(Operator {
+                signer,
+                rpc_endpoint: "https://rpc.example.com".parse()?,
+                collateral_tokens: vec![],
+                collateral_amounts: vec![],
+                protocol: None,
+                source: DataSource::Onchain,
+                metadata: None,
+                requires_acceptance: false,
+                status: OperatorStatus::Active,
+                operator_sets: vec![],
+            })
+            .await?;
+        }
+
+        let registration = Registration {
+            validator_pubkey: BlsPublicKey::random(),
+            validator_index: 0,
+            operator: a,
+            gas_limit: 10_000,
+            expiry: 0,
+            nonce: 1,
+            signature: None,
+            source: DataSource::Api,
+            priority: Registration::SIGNED_PRIORITY,
+            needs_redelegation: false,
+        };
+        let pubkeys = [registration.validator_pubkey.clone()];
+        db.register_validators(&[registration.clone()]).await?;
+
+        // Paused operators hide their validators until they are unpaused
+        tx.update_operator_status(a, OperatorStatus::Paused).await?;
+        assert!(db.get_validators_by_pubkey(&pubkeys).await?.is_empty());
+        assert!(db.list_validators(DiscoveryFilter::default()).await?.is_empty());
+        tx.update_operator_status(a, OperatorStatus::Active).await?;
+        assert_eq!(db.get_validators_by_pubkey(&pubkeys).await?.len(), 1);
+
+        // Deregistered operators mark their validators for re-delegation, keeping registrations
+        let event = |block_number, status| OperatorEvent {
+            block_number,
+            log_index: 0,
+            signer: a,
+            status,
+            rpc_endpoint: None,
+            protocol: None,
+            metadata: None,
+        };
+        tx.record_operator_event(&event(1, OperatorStatus::Active)).await?;
+        tx.update_operator_status(a, OperatorStatus::Deregistered).await?;
+        assert_eq!(tx.mark_for_redelegation(a).await?, 1);
+        tx.record_operator_event(&event(2, OperatorStatus::Deregistered)).await?;
+
+        assert!(db.get_validators_by_index(vec![0]).await?.is_empty());
+        assert!(db.get_registrations_by_pubkey(&pubkeys).await?[0].needs_redelegation);
+
+        // Rolling back the deregistration restores the validators
+        tx.rollback_contract_events(1).await?;
+        assert!(!db.get_registrations_by_pubkey(&pubkeys).await?[0].needs_redelegation);
+        assert_eq!(db.get_validators_by_index(vec![0]).await?.len(), 1);
+
+        // Registering again with another operator clears the mark
+        tx.update_operator_status(a, OperatorStatus::Deregistered).await?;
+        tx.mark_for_redelegation(a).await?;
+        assert!(db.get_validators_by_pubkey(&pubkeys).await?.is_empty());
+        db.register_validators(&[Registration { operator: b, nonce: 2, ..registration }]).await?;
+        assert_eq!(db.get_validators_by_pubkey(&pubkeys).await?[0].operator, b);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_external_sources_keep_onchain_operators() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let onchain = Operator {
+            signer: Address::with_last_byte(1),
+            rpc_endpoint: "https://onchain.bolt.test".parse()?,
+            collateral_tokens: vec![],
+            collateral_amounts: vec![],
+            protocol: Some(RestakingProtocol::Symbiotic),
+            source: DataSource::Onchain,
+            metadata: None,
+            requires_acceptance: false,
+            status: OperatorStatus::Paused,
+            operator_sets: vec![],
+        };
+        tx.register_operator(onchain.clone()).await?;
+
+        // Operators built from external source entries don't revive paused on-chain operators
+        let lido = Operator {
+            rpc_endpoint: "https://lido.bolt.test".parse()?,
+            protocol: None,
+            source: DataSource::Lido,
+            status: OperatorStatus::Active,
+            ..onchain.clone()
+        };
+        tx.register_operator(lido.clone()).await?;
+        db.register_operator(lido).await?;
+
+        let operator = db.get_operators_by_signer(&[onchain.signer]).await?.remove(0);
+        assert_eq!(operator.status, OperatorStatus::Paused);
+        assert_eq!(operator.source, DataSource::Onchain);
+        assert_eq!(operator.protocol, Some(RestakingProtocol::Symbiotic));
+        assert_eq!(operator.rpc_endpoint, onchain.rpc_endpoint);
+
+        // On-chain data still overwrites them
+        tx.register_operator(Operator { status: OperatorStatus::Active, ..onchain.clone() })
+            .await?;
+        let operator = db.get_operators_by_signer(&[onchain.signer]).await?.remove(0);
+        assert_eq!(operator.status, OperatorStatus::Active);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_registration_metadata_backfill() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let (onchain, lido) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        for (log_index, (signer, source)) in
+            [(onchain, DataSource::Onchain), (lido, DataSource::Lido)].into_iter().enumerate()
+        {
+            tx.register_operator(Operator {
+                signer,
+                rpc_endpoint: "https://rpc.bolt.test".parse()?,
+                collateral_tokens: vec![],
+                collateral_amounts: vec![],
+                protocol: None,
+                source,
+                metadata: None,
+                requires_acceptance: false,
+                status: OperatorStatus::Active,
+                operator_sets: vec![],
+            })
+            .await?;
+            tx.record_operator_event(&OperatorEvent {
+                block_number: 1,
+                log_index: log_index as u64,
+                signer,
+                status: OperatorStatus::Active,
+                rpc_endpoint: Some("https://rpc.bolt.test".parse()?),
+                protocol: None,
+                metadata: None,
+            })
+            .await?;
+        }
+
+        // Registrations applied before their metadata was ingested get it backfilled, unless the
+        // operator was since taken over by an external source
+        let metadata =
+            OperatorMetadata { display_name: Some("Operator".into()), ..Default::default() };
+        for log_index in [0, 1, 2] {
+            tx.update_registration_metadata(1, log_index, Some(&metadata)).await?;
+        }
+
+        let operators = db.get_operators_by_signer(&[onchain, lido]).await?;
+        let metadata_of =
+            |signer| operators.iter().find(|o| o.signer == signer).unwrap().metadata.clone();
+        assert_eq!(metadata_of(onchain), Some(metadata.clone()));
+        assert_eq!(metadata_of(lido), None);
+        assert!(db
+            .operator_events
+            .read()
+            .unwrap()
+            .iter()
+            .all(|e| e.metadata == Some(metadata.clone())));
+
+        // Ingested streams are recorded once
+        assert!(tx.get_contract_event_streams().await?.is_empty());
+        let streams = [ContractEventStream::Operators, ContractEventStream::Whitelist];
+        tx.insert_contract_event_streams(&streams).await?;
+        tx.insert_contract_event_streams(&streams[1..]).await?;
+        let mut ingested = tx.get_contract_event_streams().await?;
+        ingested.sort_by_key(ContractEventStream::as_str);
+        assert_eq!(ingested, streams);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_source_priority_and_conflicts() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let registration = |operator, priority, source| Registration {
+            validator_pubkey: BlsPublicKey::random(),
+            validator_index: 0,
+            operator,
+            gas_limit: 10_000,
+            expiry: 0,
+            nonce: 0,
+            signature: None,
+            source,
+            priority,
+            needs_redelegation: false,
+        };
+
+        // External sources only replace registrations of equal or lower priority
+        let feed = registration(a, 5, DataSource::Feed);
+        let pubkeys = [feed.validator_pubkey.clone()];
+        tx.register_validators(&[feed.clone()]).await?;
+        tx.register_validators(&[Registration { operator: b, priority: 1, ..feed.clone() }])
+            .await?;
+        assert_eq!(db.get_registrations_by_pubkey(&pubkeys).await?[0].operator, a);
+        tx.register_validators(&[Registration { operator: b, ..feed.clone() }]).await?;
+        assert_eq!(db.get_registrations_by_pubkey(&pubkeys).await?[0].operator, b);
+
+        // Registrations signed by the validators are never replaced by external sources
+        let signed = registration(a, Registration::SIGNED_PRIORITY, DataSource::Api);
+        db.register_validators(&[signed.clone()]).await?;
+        let lido = Registration { operator: b, priority: u8::MAX.into(), ..signed.clone() };
+        tx.register_validators(&[lido]).await?;
+        let registrations = db.get_registrations_by_pubkey(&[signed.validator_pubkey]).await?;
+        assert_eq!(registrations[0].source, DataSource::Api);
+
+        // Conflicts of queried validators are replaced, others are kept
+        let conflict = |validator_pubkey: &BlsPublicKey| SourceConflict {
+            validator_pubkey: validator_pubkey.clone(),
+            operator: a,
+            claims: vec![
+                SourceClaim { source: "feed".to_owned(), priority: 5, operator: a },
+                SourceClaim { source: "lido".to_owned(), priority: 0, operator: b },
+            ],
+        };
+        let other = BlsPublicKey::random();
+        tx.update_source_conflicts(&[], &[conflict(&pubkeys[0]), conflict(&other)]).await?;
+        assert_eq!(db.list_source_conflicts().await?.len(), 2);
+        tx.update_source_conflicts(&pubkeys, &[]).await?;
+        assert_eq!(db.list_source_conflicts().await?, vec![conflict(&other)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_operator_set_members_rollback() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let mut tx = db.begin_sync().await?;
+
+        let (a, steth, reth) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        tx.register_operator(Operator {
+            signer: a,
+            rpc_endpoint: "https://rpc.example.com".parse()?,
+            collateral_tokens: vec![],
+            collateral_amounts: vec![],
+            protocol: None,
+            source: DataSource::Onchain,
+            metadata: None,
+            requires_acceptance: false,
+            status: OperatorStatus::Active,
+            operator_sets: vec![],
+        })
+        .await?;
+
+        for (block_number, log_index, change) in [
+            (1, 0, OperatorSetChange::StrategyAdded(steth)),
+            (1, 1, OperatorSetChange::OperatorAdded(a)),
+            (2, 0, OperatorSetChange::StrategyAdded(reth)),
+            (3, 0, OperatorSetChange::OperatorRemoved(a)),
+        ] {
+            let event = OperatorSetEvent { block_number, log_index, operator_set_id: 7, change };
+            tx.record_operator_set_event(&event).await?;
+        }
+
+        assert!(db.get_operators_by_signer(&[a]).await?[0].operator_sets.is_empty());
+
+        // Rolling back the last block restores the membership and strategies before it
+        tx.rollback_contract_events(2).await?;
+        assert_eq!(
+            db.get_operators_by_signer(&[a]).await?[0].operator_sets,
+            vec![OperatorSet { id: 7, strategies: vec![steth, reth] }]
+        );
+
+        tx.rollback_contract_events(1).await?;
+        assert_eq!(
+            db.get_operators_by_signer(&[a]).await?[0].operator_sets,
+            vec![OperatorSet { id: 7, strategies: vec![steth] }]
+        );
 
         Ok(())
     }
@@ -1,11 +1,17 @@
 //! Module `db` contains database related traits and implementations,
 //! with registry-specific abstractions.
-use std::array::TryFromSliceError;
+use std::{array::TryFromSliceError, collections::HashMap};
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256, U256};
+use url::Url;
 
 use crate::primitives::{
-    registry::{Deregistration, Operator, Registration, RegistryEntry},
+    registry::{
+        ContractEventStream, Deregistration, DiscoveryFilter, Operator, OperatorEvent,
+        OperatorMetadata, OperatorSetEvent, OperatorSettings, OperatorStatus, Registration,
+        RegistryEntry, RestakingProtocol, RpcEndpointUpdate, SourceConflict, StakeSnapshot,
+        WhitelistEvent, WhitelistedCollateral,
+    },
     BlsPublicKey, SyncStateUpdate,
 };
 
@@ -38,18 +44,114 @@ pub(crate) enum DbError {
     ParseUint(&'static str),
     #[error("Missing field from query result: {0}")]
     MissingField(&'static str),
+    #[error("Invalid {0} value: {1}")]
+    InvalidEnum(&'static str, String),
 }
 
 /// Sync transaction trait. Provides a way to atomically commit any mutations and finalize
 /// with the new sync state.
 #[async_trait::async_trait]
 pub(crate) trait SyncTransaction {
-    /// Register validators in the database.
+    /// Register validators in the database. Existing registrations are only overwritten by
+    /// registrations of equal or higher priority.
     async fn register_validators(&mut self, registrations: &[Registration]) -> DbResult<()>;
 
-    /// Register an operator in the database.
+    /// Register an operator in the database. Existing operators are overwritten, except on-chain
+    /// operators, which are only overwritten by on-chain data.
     async fn register_operator(&mut self, operator: Operator) -> DbResult<()>;
 
+    /// Update the status of an operator. Unknown operators are ignored.
+    async fn update_operator_status(
+        &mut self,
+        signer: Address,
+        status: OperatorStatus,
+    ) -> DbResult<()>;
+
+    /// Mark the registrations of all validators delegated to an operator for re-delegation.
+    /// Returns the number of registrations marked.
+    async fn mark_for_redelegation(&mut self, operator: Address) -> DbResult<u64>;
+
+    /// Get the signers of all operators that are not deregistered.
+    async fn get_operator_signers(&mut self) -> DbResult<Vec<Address>>;
+
+    /// Update the collateral of an operator, as parallel vectors of tokens and amounts.
+    /// Unknown operators are ignored.
+    async fn update_operator_collateral(
+        &mut self,
+        signer: Address,
+        tokens: Vec<Address>,
+        amounts: Vec<U256>,
+    ) -> DbResult<()>;
+
+    /// Record the RPC endpoint of an operator as observed on-chain. If it differs from the last
+    /// recorded endpoint of the operator, it becomes the current endpoint and is appended to the
+    /// endpoint history. Unknown operators are ignored. Returns whether the history changed.
+    async fn update_operator_rpc_endpoint(&mut self, update: &RpcEndpointUpdate) -> DbResult<bool>;
+
+    /// Record an applied operator event of the operators registry contract.
+    async fn record_operator_event(&mut self, event: &OperatorEvent) -> DbResult<()>;
+
+    /// Set the metadata of an applied operator registration event, and of its operator if it is
+    /// an on-chain operator. Unknown events are ignored.
+    async fn update_registration_metadata(
+        &mut self,
+        block_number: u64,
+        log_index: u64,
+        metadata: Option<&OperatorMetadata>,
+    ) -> DbResult<()>;
+
+    /// Record an applied whitelist event of a restaking middleware contract.
+    async fn record_whitelist_event(&mut self, event: &WhitelistEvent) -> DbResult<()>;
+
+    /// Record an applied operator set event of the EigenLayer allocation manager, and apply it to
+    /// the operator sets.
+    async fn record_operator_set_event(&mut self, event: &OperatorSetEvent) -> DbResult<()>;
+
+    /// Roll back all contract events, checkpoints and RPC endpoint updates after the given block
+    /// number. The status and RPC endpoint of the affected operators are restored from their
+    /// remaining events and endpoint updates.
+    /// On-chain operators without remaining events are removed, or marked as deregistered if
+    /// validators are registered to them. Operators of other sources without remaining events are
+    /// restored as active. Validators of affected operators that are no longer deregistered are
+    /// unmarked for re-delegation.
+    async fn rollback_contract_events(&mut self, block_number: u64) -> DbResult<()>;
+
+    /// Get the last `limit` contract checkpoints, i.e. the last block number and hash of the
+    /// ingested ranges of contract logs, ordered from newest to oldest.
+    async fn get_contract_checkpoints(&mut self, limit: usize) -> DbResult<Vec<(u64, B256)>>;
+
+    /// Insert a contract checkpoint, and prune all but the `keep` newest checkpoints.
+    async fn insert_contract_checkpoint(
+        &mut self,
+        block_number: u64,
+        block_hash: B256,
+        keep: usize,
+    ) -> DbResult<()>;
+
+    /// Get the contract event streams ingested up to the contract checkpoints.
+    async fn get_contract_event_streams(&mut self) -> DbResult<Vec<ContractEventStream>>;
+
+    /// Record contract event streams as ingested up to the contract checkpoints.
+    async fn insert_contract_event_streams(
+        &mut self,
+        streams: &[ContractEventStream],
+    ) -> DbResult<()>;
+
+    /// Get the last operators registry epoch for which stake snapshots were taken, if any.
+    async fn get_last_stake_snapshot_epoch(&mut self) -> DbResult<Option<u64>>;
+
+    /// Insert a stake snapshot. An existing snapshot of the same operator and epoch is
+    /// overwritten.
+    async fn insert_stake_snapshot(&mut self, snapshot: &StakeSnapshot) -> DbResult<()>;
+
+    /// Replace the recorded source conflicts of the given validators, i.e. the validators queried
+    /// from the external sources, with the given conflicts.
+    async fn update_source_conflicts(
+        &mut self,
+        pubkeys: &[BlsPublicKey],
+        conflicts: &[SourceConflict],
+    ) -> DbResult<()>;
+
     /// Commit and finalize the sync transaction with the updated state.
     async fn commit(self, state: SyncStateUpdate) -> DbResult<()>;
 }
@@ -63,44 +165,119 @@ pub(crate) trait RegistryDb: Clone + Send + Sync + 'static {
     /// A sync transaction groups database mutations together in a single atomic operation.
     async fn begin_sync(&self) -> DbResult<Self::SyncTransaction>;
 
-    /// Register validators in the database.
+    /// Register validators in the database. Also records the registration nonces of the
+    /// validators.
     async fn register_validators(&self, registrations: &[Registration]) -> DbResult<()>;
 
-    /// Deregister validators in the database.
+    /// Deregister validators in the database. Also records the deregistration nonces of the
+    /// validators.
     async fn deregister_validators(&self, deregistrations: &[Deregistration]) -> DbResult<()>;
 
-    /// Register an operator in the database.
+    /// Deregister validators in the database on behalf of their operator. Records the nonce of
+    /// the deregistration message as the operator deregistration nonce, leaving the nonces of the
+    /// validators untouched.
+    async fn operator_deregister_validators(
+        &self,
+        operator: Address,
+        pubkeys: &[BlsPublicKey],
+        nonce: u64,
+    ) -> DbResult<()>;
+
+    /// Get the nonce of the last accepted operator-signed deregistration of an operator, if any.
+    async fn get_operator_deregistration_nonce(&self, operator: Address) -> DbResult<Option<u64>>;
+
+    /// Get the nonces of the last accepted (de)registration messages of the given validators.
+    /// Nonces are kept after deregistration. Validators without any accepted message are omitted.
+    async fn get_nonces(&self, pubkeys: &[BlsPublicKey]) -> DbResult<HashMap<BlsPublicKey, u64>>;
+
+    /// Register an operator in the database. Existing operators are overwritten, except on-chain
+    /// operators, which are only overwritten by on-chain data.
     async fn register_operator(&self, operator: Operator) -> DbResult<()>;
 
-    /// List all registrations in the database.
+    /// Get the settings of an operator. Operators that never updated their settings have the
+    /// default settings.
+    async fn get_operator_settings(&self, signer: Address) -> DbResult<OperatorSettings>;
+
+    /// Update the settings of an operator. Settings are kept independently of the operator
+    /// data, so they survive operator re-registrations.
+    async fn update_operator_settings(
+        &self,
+        signer: Address,
+        settings: OperatorSettings,
+    ) -> DbResult<()>;
+
+    /// Delete all registrations that have expired at the given UNIX timestamp. Nonces of
+    /// the pruned validators are kept. Returns the number of pruned registrations.
+    async fn prune_expired_registrations(&self, now: u64) -> DbResult<u64>;
+
+    /// List all registrations in the database. Expired registrations are omitted.
     async fn list_registrations(&self) -> DbResult<Vec<Registration>>;
 
     /// Get a batch of registrations from the database, by their public keys.
+    /// Expired registrations are omitted.
     async fn get_registrations_by_pubkey(
         &self,
         pubkeys: &[BlsPublicKey],
     ) -> DbResult<Vec<Registration>>;
 
-    /// List all validators in the database.
-    async fn list_validators(&self) -> DbResult<Vec<RegistryEntry>>;
+    /// List the validators in the database that pass the given filter. Validators with expired
+    /// registrations, marked for re-delegation, or of inactive operators are omitted.
+    async fn list_validators(&self, filter: DiscoveryFilter) -> DbResult<Vec<RegistryEntry>>;
 
-    /// Get a batch of validators from the database, by their public keys..
+    /// Get a batch of validators from the database, by their public keys.
+    /// Validators with expired registrations, marked for re-delegation, or of inactive operators
+    /// are omitted.
     async fn get_validators_by_pubkey(
         &self,
         pubkeys: &[BlsPublicKey],
     ) -> DbResult<Vec<RegistryEntry>>;
 
     /// Get a batch of validators from the database, by their beacon chain indices.
+    /// Validators with expired registrations, marked for re-delegation, or of inactive operators
+    /// are omitted.
     async fn get_validators_by_index(&self, indices: Vec<u64>) -> DbResult<Vec<RegistryEntry>>;
 
-    /// List all operators in the database.
-    async fn list_operators(&self) -> DbResult<Vec<Operator>>;
+    /// List the operators in the database that pass the given filter, with their EigenLayer
+    /// operator sets.
+    async fn list_operators(&self, filter: DiscoveryFilter) -> DbResult<Vec<Operator>>;
 
-    /// Get a batch of operators from the database, by their signer addresses.
+    /// Get a batch of operators from the database, by their signer addresses, with their
+    /// EigenLayer operator sets.
     async fn get_operators_by_signer(&self, signers: &[Address]) -> DbResult<Vec<Operator>>;
 
-    /// Get the current sync state from the database.
-    async fn get_sync_state(&self) -> DbResult<SyncStateUpdate>;
+    /// List the collateral currently whitelisted in the restaking middlewares, optionally
+    /// filtered by protocol. Removed collateral is omitted, paused collateral is included.
+    async fn list_whitelisted_collateral(
+        &self,
+        protocol: Option<RestakingProtocol>,
+    ) -> DbResult<Vec<WhitelistedCollateral>>;
+
+    /// List the RPC endpoint history of an operator, ordered from oldest to newest.
+    async fn list_rpc_endpoint_history(&self, signer: Address) -> DbResult<Vec<RpcEndpointUpdate>>;
+
+    /// Get the RPC endpoints of the given operators that were effective at the given beacon
+    /// chain epoch. Operators without recorded endpoints at that epoch are omitted.
+    async fn get_rpc_endpoints_at(
+        &self,
+        signers: &[Address],
+        epoch: u64,
+    ) -> DbResult<HashMap<Address, Url>>;
+
+    /// Get the stake snapshot of an operator at the given operators registry epoch, if any.
+    async fn get_stake_snapshot(
+        &self,
+        signer: Address,
+        epoch: u64,
+    ) -> DbResult<Option<StakeSnapshot>>;
+
+    /// List all stake snapshots of an operator, ordered by epoch.
+    async fn list_stake_snapshots(&self, signer: Address) -> DbResult<Vec<StakeSnapshot>>;
+
+    /// List the validators that external sources currently claim for different operators.
+    async fn list_source_conflicts(&self) -> DbResult<Vec<SourceConflict>>;
+
+    /// Get the current sync state from the database, if the registry was ever synced.
+    async fn get_sync_state(&self) -> DbResult<Option<SyncStateUpdate>>;
 
     /// Update the sync state in the database.
     async fn update_sync_state(&self, state: SyncStateUpdate) -> DbResult<()>;
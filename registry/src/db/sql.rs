@@ -1,13 +1,27 @@
-use std::sync::{atomic::AtomicU64, Arc};
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicU64, Arc},
+};
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256, U256};
 use sqlx::Postgres;
 use tracing::{debug, info};
+use url::Url;
+
+use crate::primitives::{
+    registry::{whitelist_from_events, ContractEventStream, OperatorSet, OperatorSetChange},
+    unix_seconds,
+};
 
 use super::{
-    types::{OperatorRow, ValidatorRegistrationRow},
-    BlsPublicKey, DbResult, Deregistration, Operator, Registration, RegistryDb, RegistryEntry,
-    SyncStateUpdate, SyncTransaction,
+    types::{
+        OperatorRow, OperatorSettingsRow, SourceConflictRow, StakeSnapshotRow, ValidatorNonceRow,
+        ValidatorRegistrationRow, WhitelistEventRow,
+    },
+    BlsPublicKey, DbResult, Deregistration, DiscoveryFilter, Operator, OperatorEvent,
+    OperatorMetadata, OperatorSetEvent, OperatorSettings, OperatorStatus, Registration, RegistryDb,
+    RegistryEntry, RestakingProtocol, RpcEndpointUpdate, SourceConflict, StakeSnapshot,
+    SyncStateUpdate, SyncTransaction, WhitelistEvent, WhitelistedCollateral,
 };
 
 /// Generic SQL database implementation, that supports all `SQLx` backends.
@@ -40,6 +54,41 @@ impl SQLDb<sqlx::Postgres> {
 
         Ok(())
     }
+
+    /// Attaches the EigenLayer operator sets the given operators are members of.
+    async fn with_operator_sets(&self, mut operators: Vec<Operator>) -> DbResult<Vec<Operator>> {
+        let signers = operators.iter().map(|o| o.signer.to_vec()).collect::<Vec<_>>();
+        let rows: Vec<(Vec<u8>, i64, Option<Vec<u8>>)> = sqlx::query_as(
+            "
+            SELECT m.operator, m.operator_set_id, s.strategy
+            FROM operator_set_members m
+            LEFT JOIN operator_set_strategies s ON s.operator_set_id = m.operator_set_id
+            WHERE m.operator = ANY($1)
+            ORDER BY m.operator, m.operator_set_id, s.strategy
+            ",
+        )
+        .bind(signers)
+        .fetch_all(&self.conn)
+        .await?;
+
+        let mut memberships: HashMap<Address, Vec<OperatorSet>> = HashMap::new();
+        for (operator, id, strategy) in rows {
+            let sets = memberships.entry(Address::try_from(operator.as_slice())?).or_default();
+            if sets.last().is_none_or(|set| set.id != id as u32) {
+                sets.push(OperatorSet { id: id as u32, strategies: vec![] });
+            }
+
+            if let (Some(set), Some(strategy)) = (sets.last_mut(), strategy) {
+                set.strategies.push(Address::try_from(strategy.as_slice())?);
+            }
+        }
+
+        for operator in &mut operators {
+            operator.operator_sets = memberships.remove(&operator.signer).unwrap_or_default();
+        }
+
+        Ok(operators)
+    }
 }
 
 // Manual clone implementation is required for satisfying the `RegistryDb` trait bound.
@@ -60,19 +109,27 @@ impl SyncTransaction for SQLSyncTransaction {
     async fn register_validators(&mut self, registrations: &[Registration]) -> DbResult<()> {
         let mut rows_affected = 0;
         for registration in registrations {
+            // Only registrations of equal or higher priority replace the existing registration.
+            // The re-delegation mark is kept until the validator changes operator.
             let result = sqlx::query(
                 "
-                INSERT INTO validator_registrations (pubkey, index, signature, expiry, operator, priority, source, last_update)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+                INSERT INTO validator_registrations (pubkey, index, signature, expiry, gas_limit, operator, nonce, priority, source, last_update)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::source_enum, NOW())
+                ON CONFLICT (pubkey)
+                DO UPDATE SET index = $2, signature = $3, expiry = $4, gas_limit = $5, operator = $6, nonce = $7, priority = $8, source = $9::source_enum,
+                    needs_redelegation = validator_registrations.needs_redelegation AND validator_registrations.operator = $6, last_update = NOW()
+                WHERE validator_registrations.priority <= $8
                 "
             )
             .bind(registration.validator_pubkey.serialize())
             .bind(registration.validator_index as i64)
             .bind(registration.signature.as_ref().map(|s| s.serialize()))
-            .bind(registration.expiry.to_string())
+            .bind(registration.expiry as i64)
+            .bind(registration.gas_limit as i64)
             .bind(registration.operator.to_vec())
-            .bind(0) // TODO: priority
-            .bind("none") // TODO: source
+            .bind(registration.nonce as i64)
+            .bind(registration.priority as i16)
+            .bind(registration.source.as_str())
             .execute(&mut *self.transaction).await?.rows_affected();
 
             rows_affected += result;
@@ -84,19 +141,28 @@ impl SyncTransaction for SQLSyncTransaction {
     }
 
     async fn register_operator(&mut self, operator: Operator) -> DbResult<()> {
+        // On-chain operators keep their status, protocol and metadata, which external sources
+        // don't know about
         let rows_affected = sqlx::query(
             "
-            INSERT INTO operators (signer, rpc, protocol, source, collateral_tokens, collateral_amounts, last_update)
-            VALUES ($1, $2, $3, $4, $5, $6, NOW())
+            INSERT INTO operators (signer, rpc, protocol, source, collateral_tokens, collateral_amounts, status, metadata, last_update)
+            VALUES ($1, $2, $3::protocol_enum, $4::source_enum, $5, $6, $7::operator_status_enum, $8, NOW())
+            ON CONFLICT (signer)
+            DO UPDATE SET rpc = EXCLUDED.rpc, protocol = EXCLUDED.protocol, source = EXCLUDED.source,
+                collateral_tokens = EXCLUDED.collateral_tokens, collateral_amounts = EXCLUDED.collateral_amounts,
+                status = EXCLUDED.status, metadata = EXCLUDED.metadata, last_update = NOW()
+            WHERE operators.source <> 'onchain' OR EXCLUDED.source = 'onchain'
             ",
         )
         .bind(operator.signer.to_vec())
         .bind(operator.rpc_endpoint.to_string())
-        .bind("none") // TODO: protocol
-        .bind("none") // TODO: source
+        .bind(operator.protocol.map(|p| p.as_str()))
+        .bind(operator.source.as_str())
         // parse arrays as bytea[] with address bytes and little endian u256 bytes
         .bind(operator.collateral_tokens.into_iter().map(|a| a.to_vec()).collect::<Vec<_>>())
         .bind(operator.collateral_amounts.into_iter().map(|a| a.to_le_bytes_vec()).collect::<Vec<_>>())
+        .bind(operator.status.as_str())
+        .bind(operator.metadata.as_ref().map(OperatorMetadata::to_json))
         .execute(&mut *self.transaction)
         .await?
         .rows_affected();
@@ -106,6 +172,605 @@ impl SyncTransaction for SQLSyncTransaction {
         Ok(())
     }
 
+    async fn update_operator_status(
+        &mut self,
+        signer: Address,
+        status: OperatorStatus,
+    ) -> DbResult<()> {
+        let rows_affected = sqlx::query(
+            "
+            UPDATE operators
+            SET status = $2::operator_status_enum, last_update = NOW()
+            WHERE signer = $1
+            ",
+        )
+        .bind(signer.to_vec())
+        .bind(status.as_str())
+        .execute(&mut *self.transaction)
+        .await?
+        .rows_affected();
+
+        debug!(transaction_id = self.id, rows_affected, "update_operator_status");
+
+        Ok(())
+    }
+
+    async fn mark_for_redelegation(&mut self, operator: Address) -> DbResult<u64> {
+        let rows_affected = sqlx::query(
+            "
+            UPDATE validator_registrations
+            SET needs_redelegation = TRUE, last_update = NOW()
+            WHERE operator = $1 AND NOT needs_redelegation
+            ",
+        )
+        .bind(operator.to_vec())
+        .execute(&mut *self.transaction)
+        .await?
+        .rows_affected();
+
+        debug!(transaction_id = self.id, rows_affected, "mark_for_redelegation");
+
+        Ok(rows_affected)
+    }
+
+    async fn get_operator_signers(&mut self) -> DbResult<Vec<Address>> {
+        let rows: Vec<(Vec<u8>,)> = sqlx::query_as(
+            "
+            SELECT signer
+            FROM operators
+            WHERE status <> 'deregistered'
+            ",
+        )
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        rows.into_iter().map(|(signer,)| Ok(Address::try_from(signer.as_slice())?)).collect()
+    }
+
+    async fn update_operator_collateral(
+        &mut self,
+        signer: Address,
+        tokens: Vec<Address>,
+        amounts: Vec<U256>,
+    ) -> DbResult<()> {
+        let rows_affected = sqlx::query(
+            "
+            UPDATE operators
+            SET collateral_tokens = $2, collateral_amounts = $3, last_update = NOW()
+            WHERE signer = $1
+            ",
+        )
+        .bind(signer.to_vec())
+        // parse arrays as bytea[] with address bytes and little endian u256 bytes
+        .bind(tokens.into_iter().map(|a| a.to_vec()).collect::<Vec<_>>())
+        .bind(amounts.into_iter().map(|a| a.to_le_bytes_vec()).collect::<Vec<_>>())
+        .execute(&mut *self.transaction)
+        .await?
+        .rows_affected();
+
+        debug!(transaction_id = self.id, rows_affected, "update_operator_collateral");
+
+        Ok(())
+    }
+
+    async fn update_operator_rpc_endpoint(&mut self, update: &RpcEndpointUpdate) -> DbResult<bool> {
+        let rpc = update.rpc_endpoint.to_string();
+
+        // The current endpoint is always overwritten, as rollbacks restore it from the last
+        // registration event
+        let rows_affected =
+            sqlx::query("UPDATE operators SET rpc = $2, last_update = NOW() WHERE signer = $1")
+                .bind(update.operator.to_vec())
+                .bind(&rpc)
+                .execute(&mut *self.transaction)
+                .await?
+                .rows_affected();
+
+        // Unknown operator
+        if rows_affected == 0 {
+            return Ok(false);
+        }
+
+        let last: Option<(String,)> = sqlx::query_as(
+            "
+            SELECT rpc FROM operator_rpc_history
+            WHERE signer = $1
+            ORDER BY block_number DESC
+            LIMIT 1
+            ",
+        )
+        .bind(update.operator.to_vec())
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        if last.is_some_and(|(last,)| last == rpc) {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "
+            INSERT INTO operator_rpc_history (signer, block_number, epoch, rpc)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (signer, block_number)
+            DO UPDATE SET epoch = EXCLUDED.epoch, rpc = EXCLUDED.rpc
+            ",
+        )
+        .bind(update.operator.to_vec())
+        .bind(update.block_number as i64)
+        .bind(update.epoch as i64)
+        .bind(rpc)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        debug!(transaction_id = self.id, signer = %update.operator, "update_operator_rpc_endpoint");
+
+        Ok(true)
+    }
+
+    async fn record_operator_event(&mut self, event: &OperatorEvent) -> DbResult<()> {
+        sqlx::query(
+            "
+            INSERT INTO operator_events (block_number, log_index, signer, status, rpc, protocol, metadata)
+            VALUES ($1, $2, $3, $4::operator_status_enum, $5, $6::protocol_enum, $7)
+            ON CONFLICT (block_number, log_index)
+            DO UPDATE SET signer = EXCLUDED.signer, status = EXCLUDED.status, rpc = EXCLUDED.rpc,
+                protocol = EXCLUDED.protocol, metadata = EXCLUDED.metadata
+            ",
+        )
+        .bind(event.block_number as i64)
+        .bind(event.log_index as i64)
+        .bind(event.signer.to_vec())
+        .bind(event.status.as_str())
+        .bind(event.rpc_endpoint.as_ref().map(|url| url.to_string()))
+        .bind(event.protocol.map(|p| p.as_str()))
+        .bind(event.metadata.as_ref().map(OperatorMetadata::to_json))
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_registration_metadata(
+        &mut self,
+        block_number: u64,
+        log_index: u64,
+        metadata: Option<&OperatorMetadata>,
+    ) -> DbResult<()> {
+        let metadata = metadata.map(OperatorMetadata::to_json);
+
+        let rows_affected = sqlx::query(
+            "
+            WITH event AS (
+                UPDATE operator_events SET metadata = $3
+                WHERE block_number = $1 AND log_index = $2
+                RETURNING signer
+            )
+            UPDATE operators o
+            SET metadata = $3, last_update = NOW()
+            FROM event
+            WHERE o.signer = event.signer AND o.source = 'onchain'
+            ",
+        )
+        .bind(block_number as i64)
+        .bind(log_index as i64)
+        .bind(metadata)
+        .execute(&mut *self.transaction)
+        .await?
+        .rows_affected();
+
+        debug!(transaction_id = self.id, rows_affected, "update_registration_metadata");
+
+        Ok(())
+    }
+
+    async fn record_whitelist_event(&mut self, event: &WhitelistEvent) -> DbResult<()> {
+        sqlx::query(
+            "
+            INSERT INTO whitelist_events (block_number, log_index, protocol, address, action)
+            VALUES ($1, $2, $3::protocol_enum, $4, $5::whitelist_action_enum)
+            ON CONFLICT (block_number, log_index)
+            DO UPDATE SET protocol = EXCLUDED.protocol, address = EXCLUDED.address, action = EXCLUDED.action
+            ",
+        )
+        .bind(event.block_number as i64)
+        .bind(event.log_index as i64)
+        .bind(event.protocol.as_str())
+        .bind(event.address.to_vec())
+        .bind(event.action.as_str())
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_operator_set_event(&mut self, event: &OperatorSetEvent) -> DbResult<()> {
+        sqlx::query(
+            "
+            INSERT INTO operator_set_events (block_number, log_index, operator_set_id, action, address)
+            VALUES ($1, $2, $3, $4::operator_set_action_enum, $5)
+            ON CONFLICT (block_number, log_index)
+            DO UPDATE SET operator_set_id = EXCLUDED.operator_set_id, action = EXCLUDED.action, address = EXCLUDED.address
+            ",
+        )
+        .bind(event.block_number as i64)
+        .bind(event.log_index as i64)
+        .bind(i64::from(event.operator_set_id))
+        .bind(event.change.as_str())
+        .bind(event.change.address().map(|a| a.to_vec()))
+        .execute(&mut *self.transaction)
+        .await?;
+
+        // Apply the change to the materialized operator sets
+        let query = match event.change {
+            OperatorSetChange::Created => return Ok(()),
+            OperatorSetChange::OperatorAdded(_) => {
+                "
+                INSERT INTO operator_set_members (operator_set_id, operator) VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                "
+            }
+            OperatorSetChange::OperatorRemoved(_) => {
+                "DELETE FROM operator_set_members WHERE operator_set_id = $1 AND operator = $2"
+            }
+            OperatorSetChange::StrategyAdded(_) => {
+                "
+                INSERT INTO operator_set_strategies (operator_set_id, strategy) VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                "
+            }
+            OperatorSetChange::StrategyRemoved(_) => {
+                "DELETE FROM operator_set_strategies WHERE operator_set_id = $1 AND strategy = $2"
+            }
+        };
+
+        sqlx::query(query)
+            .bind(i64::from(event.operator_set_id))
+            .bind(event.change.address().map(|a| a.to_vec()))
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn rollback_contract_events(&mut self, block_number: u64) -> DbResult<()> {
+        let reverted: Vec<(Vec<u8>,)> = sqlx::query_as(
+            "
+            DELETE FROM operator_events
+            WHERE block_number > $1
+            RETURNING signer
+            ",
+        )
+        .bind(block_number as i64)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        let mut signers = reverted.into_iter().map(|(signer,)| signer).collect::<Vec<_>>();
+        signers.sort_unstable();
+        signers.dedup();
+
+        // On-chain operators whose registration was reverted are removed, unless validators
+        // registered to them in the meantime
+        sqlx::query(
+            "
+            DELETE FROM operators o
+            WHERE o.signer = ANY($1) AND o.source = 'onchain'
+                AND NOT EXISTS (SELECT 1 FROM operator_events e WHERE e.signer = o.signer)
+                AND NOT EXISTS (
+                    SELECT 1 FROM validator_registrations vr WHERE vr.operator = o.signer
+                )
+            ",
+        )
+        .bind(&signers)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        // Restore the remaining operators from their last remaining events. Operators of other
+        // sources have no on-chain status left without them.
+        let rows_affected = sqlx::query(
+            "
+            UPDATE operators o
+            SET status = COALESCE((
+                    SELECT e.status FROM operator_events e
+                    WHERE e.signer = o.signer
+                    ORDER BY e.block_number DESC, e.log_index DESC
+                    LIMIT 1
+                ), CASE
+                    WHEN o.source = 'onchain' THEN 'deregistered'
+                    ELSE 'active'
+                END::operator_status_enum),
+                last_update = NOW()
+            WHERE o.signer = ANY($1)
+            ",
+        )
+        .bind(&signers)
+        .execute(&mut *self.transaction)
+        .await?
+        .rows_affected();
+
+        // Endpoint updates observed after the given block are reverted as well
+        let reverted: Vec<(Vec<u8>,)> = sqlx::query_as(
+            "
+            DELETE FROM operator_rpc_history
+            WHERE block_number > $1
+            RETURNING signer
+            ",
+        )
+        .bind(block_number as i64)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        let mut rpc_signers = signers.clone();
+        rpc_signers.extend(reverted.into_iter().map(|(signer,)| signer));
+        rpc_signers.sort_unstable();
+        rpc_signers.dedup();
+
+        // Registrations are the only events carrying an RPC endpoint. Operators without remaining
+        // registrations keep their registration data.
+        sqlx::query(
+            "
+            UPDATE operators o
+            SET rpc = r.rpc, protocol = r.protocol, metadata = r.metadata, last_update = NOW()
+            FROM (
+                SELECT DISTINCT ON (signer) signer, rpc, protocol, metadata
+                FROM operator_events
+                WHERE signer = ANY($1) AND rpc IS NOT NULL
+                ORDER BY signer, block_number DESC, log_index DESC
+            ) r
+            WHERE o.signer = r.signer
+            ",
+        )
+        .bind(&rpc_signers)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        // Endpoint updates observed since the last remaining registration take precedence
+        sqlx::query(
+            "
+            UPDATE operators o
+            SET rpc = h.rpc, last_update = NOW()
+            FROM (
+                SELECT DISTINCT ON (signer) signer, block_number, rpc
+                FROM operator_rpc_history
+                WHERE signer = ANY($1)
+                ORDER BY signer, block_number DESC
+            ) h
+            WHERE o.signer = h.signer AND NOT EXISTS (
+                SELECT 1 FROM operator_events e
+                WHERE e.signer = h.signer AND e.rpc IS NOT NULL AND e.block_number > h.block_number
+            )
+            ",
+        )
+        .bind(&rpc_signers)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        // Only deregistrations mark validators for re-delegation
+        sqlx::query(
+            "
+            UPDATE validator_registrations vr
+            SET needs_redelegation = FALSE, last_update = NOW()
+            FROM operators o
+            WHERE o.signer = vr.operator AND vr.operator = ANY($1)
+                AND vr.needs_redelegation AND o.status <> 'deregistered'
+            ",
+        )
+        .bind(signers)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        sqlx::query("DELETE FROM whitelist_events WHERE block_number > $1")
+            .bind(block_number as i64)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let reverted: Vec<(i64,)> = sqlx::query_as(
+            "
+            DELETE FROM operator_set_events
+            WHERE block_number > $1
+            RETURNING operator_set_id
+            ",
+        )
+        .bind(block_number as i64)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        let mut operator_set_ids = reverted.into_iter().map(|(id,)| id).collect::<Vec<_>>();
+        operator_set_ids.sort_unstable();
+        operator_set_ids.dedup();
+
+        // Rebuild the affected operator sets from their remaining events
+        for (table, column, added, removed) in [
+            ("operator_set_members", "operator", "operator_added", "operator_removed"),
+            ("operator_set_strategies", "strategy", "strategy_added", "strategy_removed"),
+        ] {
+            sqlx::query(&format!("DELETE FROM {table} WHERE operator_set_id = ANY($1)"))
+                .bind(&operator_set_ids)
+                .execute(&mut *self.transaction)
+                .await?;
+
+            sqlx::query(&format!(
+                "
+                INSERT INTO {table} (operator_set_id, {column})
+                SELECT operator_set_id, address FROM (
+                    SELECT DISTINCT ON (operator_set_id, address) operator_set_id, address, action
+                    FROM operator_set_events
+                    WHERE operator_set_id = ANY($1) AND action IN ('{added}', '{removed}')
+                    ORDER BY operator_set_id, address, block_number DESC, log_index DESC
+                ) e
+                WHERE action = '{added}'
+                "
+            ))
+            .bind(&operator_set_ids)
+            .execute(&mut *self.transaction)
+            .await?;
+        }
+
+        sqlx::query("DELETE FROM contract_checkpoints WHERE block_number > $1")
+            .bind(block_number as i64)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        debug!(transaction_id = self.id, block_number, rows_affected, "rollback_contract_events");
+
+        Ok(())
+    }
+
+    async fn get_contract_checkpoints(&mut self, limit: usize) -> DbResult<Vec<(u64, B256)>> {
+        let rows: Vec<(i64, Vec<u8>)> = sqlx::query_as(
+            "
+            SELECT block_number, block_hash
+            FROM contract_checkpoints
+            ORDER BY block_number DESC
+            LIMIT $1
+            ",
+        )
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        rows.into_iter()
+            .map(|(block_number, block_hash)| {
+                Ok((block_number as u64, B256::try_from(block_hash.as_slice())?))
+            })
+            .collect()
+    }
+
+    async fn insert_contract_checkpoint(
+        &mut self,
+        block_number: u64,
+        block_hash: B256,
+        keep: usize,
+    ) -> DbResult<()> {
+        sqlx::query(
+            "
+            INSERT INTO contract_checkpoints (block_number, block_hash)
+            VALUES ($1, $2)
+            ON CONFLICT (block_number)
+            DO UPDATE SET block_hash = EXCLUDED.block_hash
+            ",
+        )
+        .bind(block_number as i64)
+        .bind(block_hash.to_vec())
+        .execute(&mut *self.transaction)
+        .await?;
+
+        let rows_affected = sqlx::query(
+            "
+            DELETE FROM contract_checkpoints
+            WHERE block_number NOT IN (
+                SELECT block_number FROM contract_checkpoints
+                ORDER BY block_number DESC
+                LIMIT $1
+            )
+            ",
+        )
+        .bind(i64::try_from(keep).unwrap_or(i64::MAX))
+        .execute(&mut *self.transaction)
+        .await?
+        .rows_affected();
+
+        debug!(transaction_id = self.id, block_number, rows_affected, "insert_contract_checkpoint");
+
+        Ok(())
+    }
+
+    async fn get_contract_event_streams(&mut self) -> DbResult<Vec<ContractEventStream>> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT stream FROM contract_event_streams")
+            .fetch_all(&mut *self.transaction)
+            .await?;
+
+        // Streams unknown to this version are ignored
+        Ok(rows.into_iter().filter_map(|(stream,)| ContractEventStream::parse(&stream)).collect())
+    }
+
+    async fn insert_contract_event_streams(
+        &mut self,
+        streams: &[ContractEventStream],
+    ) -> DbResult<()> {
+        sqlx::query(
+            "
+            INSERT INTO contract_event_streams (stream)
+            SELECT * FROM UNNEST($1::text[])
+            ON CONFLICT DO NOTHING
+            ",
+        )
+        .bind(streams.iter().map(ContractEventStream::as_str).collect::<Vec<_>>())
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_last_stake_snapshot_epoch(&mut self) -> DbResult<Option<u64>> {
+        let (epoch,): (Option<i64>,) = sqlx::query_as("SELECT MAX(epoch) FROM stake_snapshots")
+            .fetch_one(&mut *self.transaction)
+            .await?;
+
+        Ok(epoch.map(|e| e as u64))
+    }
+
+    async fn insert_stake_snapshot(&mut self, snapshot: &StakeSnapshot) -> DbResult<()> {
+        sqlx::query(
+            "
+            INSERT INTO stake_snapshots (signer, epoch, timestamp, collateral_tokens, collateral_amounts, last_update)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (signer, epoch)
+            DO UPDATE SET timestamp = EXCLUDED.timestamp, collateral_tokens = EXCLUDED.collateral_tokens,
+                collateral_amounts = EXCLUDED.collateral_amounts, last_update = NOW()
+            ",
+        )
+        .bind(snapshot.operator.to_vec())
+        .bind(snapshot.epoch as i64)
+        .bind(snapshot.timestamp as i64)
+        // parse arrays as bytea[] with address bytes and little endian u256 bytes
+        .bind(snapshot.collateral_tokens.iter().map(|a| a.to_vec()).collect::<Vec<_>>())
+        .bind(snapshot.collateral_amounts.iter().map(|a| a.to_le_bytes_vec()).collect::<Vec<_>>())
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_source_conflicts(
+        &mut self,
+        pubkeys: &[BlsPublicKey],
+        conflicts: &[SourceConflict],
+    ) -> DbResult<()> {
+        sqlx::query(
+            "
+            DELETE FROM source_conflicts
+            WHERE pubkey = ANY($1)
+            ",
+        )
+        .bind(pubkeys.iter().map(|pk| pk.serialize()).collect::<Vec<_>>())
+        .execute(&mut *self.transaction)
+        .await?;
+
+        for conflict in conflicts {
+            sqlx::query(
+                "
+                INSERT INTO source_conflicts (pubkey, operator, claim_sources, claim_priorities, claim_operators, last_update)
+                VALUES ($1, $2, $3, $4, $5, NOW())
+                ON CONFLICT (pubkey)
+                DO UPDATE SET operator = EXCLUDED.operator, claim_sources = EXCLUDED.claim_sources,
+                    claim_priorities = EXCLUDED.claim_priorities, claim_operators = EXCLUDED.claim_operators, last_update = NOW()
+                ",
+            )
+            .bind(conflict.validator_pubkey.serialize())
+            .bind(conflict.operator.to_vec())
+            // parse claims as parallel arrays
+            .bind(conflict.claims.iter().map(|c| c.source.clone()).collect::<Vec<_>>())
+            .bind(conflict.claims.iter().map(|c| i16::from(c.priority)).collect::<Vec<_>>())
+            .bind(conflict.claims.iter().map(|c| c.operator.to_vec()).collect::<Vec<_>>())
+            .execute(&mut *self.transaction)
+            .await?;
+        }
+
+        debug!(transaction_id = self.id, count = conflicts.len(), "update_source_conflicts");
+
+        Ok(())
+    }
+
     async fn commit(mut self, state: SyncStateUpdate) -> DbResult<()> {
         sqlx::query(
             "
@@ -144,20 +809,28 @@ impl RegistryDb for SQLDb<Postgres> {
         let mut transaction = self.conn.begin().await?;
 
         for registration in registrations {
+            // Newer registrations replace the existing registration of the validator
             sqlx::query(
                 "
-                INSERT INTO validator_registrations (pubkey, index, signature, expiry, operator, priority, source, last_update)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+                INSERT INTO validator_registrations (pubkey, index, signature, expiry, gas_limit, operator, nonce, priority, source, last_update)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::source_enum, NOW())
+                ON CONFLICT (pubkey)
+                DO UPDATE SET index = $2, signature = $3, expiry = $4, gas_limit = $5, operator = $6, nonce = $7, priority = $8, source = $9::source_enum, needs_redelegation = FALSE, last_update = NOW()
                 "
             )
             .bind(registration.validator_pubkey.serialize())
             .bind(registration.validator_index as i64)
             .bind(registration.signature.as_ref().map(|s| s.serialize()))
-            .bind(registration.expiry.to_string())
+            .bind(registration.expiry as i64)
+            .bind(registration.gas_limit as i64)
             .bind(registration.operator.to_vec())
-            .bind(0) // TODO: priority
-            .bind("none") // TODO: source
+            .bind(registration.nonce as i64)
+            .bind(registration.priority as i16)
+            .bind(registration.source.as_str())
             .execute(&mut *transaction).await?;
+
+            upsert_nonce(&mut transaction, &registration.validator_pubkey, registration.nonce)
+                .await?;
         }
 
         transaction.commit().await?;
@@ -167,6 +840,8 @@ impl RegistryDb for SQLDb<Postgres> {
 
     // TODO: do we really want to delete the rows from the DB or just mark them as inactive?
     async fn deregister_validators(&self, deregistrations: &[Deregistration]) -> DbResult<()> {
+        let mut transaction = self.conn.begin().await?;
+
         sqlx::query(
             "
             DELETE FROM validator_registrations
@@ -174,39 +849,173 @@ impl RegistryDb for SQLDb<Postgres> {
             ",
         )
         .bind(deregistrations.iter().map(|d| d.validator_pubkey.serialize()).collect::<Vec<_>>())
-        .execute(&self.conn)
+        .execute(&mut *transaction)
         .await?;
 
+        for deregistration in deregistrations {
+            upsert_nonce(&mut transaction, &deregistration.validator_pubkey, deregistration.nonce)
+                .await?;
+        }
+
+        transaction.commit().await?;
+
         Ok(())
     }
 
+    async fn operator_deregister_validators(
+        &self,
+        operator: Address,
+        pubkeys: &[BlsPublicKey],
+        nonce: u64,
+    ) -> DbResult<()> {
+        let mut transaction = self.conn.begin().await?;
+
+        sqlx::query(
+            "
+            DELETE FROM validator_registrations
+            WHERE pubkey = ANY($1)
+            ",
+        )
+        .bind(pubkeys.iter().map(|p| p.serialize()).collect::<Vec<_>>())
+        .execute(&mut *transaction)
+        .await?;
+
+        sqlx::query(
+            "
+            INSERT INTO operator_deregistration_nonces (signer, nonce, last_update)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (signer)
+            DO UPDATE SET nonce = $2, last_update = NOW()
+            ",
+        )
+        .bind(operator.to_vec())
+        .bind(nonce as i64)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_operator_deregistration_nonce(&self, operator: Address) -> DbResult<Option<u64>> {
+        let row: Option<(i64,)> = sqlx::query_as(
+            "
+            SELECT nonce
+            FROM operator_deregistration_nonces
+            WHERE signer = $1
+            ",
+        )
+        .bind(operator.to_vec())
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(row.map(|(nonce,)| nonce as u64))
+    }
+
+    async fn get_nonces(&self, pubkeys: &[BlsPublicKey]) -> DbResult<HashMap<BlsPublicKey, u64>> {
+        let rows: Vec<ValidatorNonceRow> = sqlx::query_as(
+            "
+            SELECT pubkey, nonce
+            FROM validator_nonces
+            WHERE pubkey = ANY($1)
+            ",
+        )
+        .bind(pubkeys.iter().map(|p| p.serialize()).collect::<Vec<_>>())
+        .fetch_all(&self.conn)
+        .await?;
+
+        rows.into_iter().map(TryInto::try_into).collect()
+    }
+
     async fn register_operator(&self, operator: Operator) -> DbResult<()> {
         sqlx::query(
             "
-            INSERT INTO operators (signer, rpc, protocol, source, collateral_tokens, collateral_amounts, last_update)
-            VALUES ($1, $2, $3, $4, $5, $6, NOW())
+            INSERT INTO operators (signer, rpc, protocol, source, collateral_tokens, collateral_amounts, status, metadata, last_update)
+            VALUES ($1, $2, $3::protocol_enum, $4::source_enum, $5, $6, $7::operator_status_enum, $8, NOW())
+            ON CONFLICT (signer)
+            DO UPDATE SET rpc = EXCLUDED.rpc, protocol = EXCLUDED.protocol, source = EXCLUDED.source,
+                collateral_tokens = EXCLUDED.collateral_tokens, collateral_amounts = EXCLUDED.collateral_amounts,
+                status = EXCLUDED.status, metadata = EXCLUDED.metadata, last_update = NOW()
+            WHERE operators.source <> 'onchain' OR EXCLUDED.source = 'onchain'
             ",
         )
         .bind(operator.signer.to_vec())
         .bind(operator.rpc_endpoint.to_string())
-        .bind("none") // TODO: protocol
-        .bind("none") // TODO: source
+        .bind(operator.protocol.map(|p| p.as_str()))
+        .bind(operator.source.as_str())
         // parse arrays as bytea[] with address bytes and little endian u256 bytes
         .bind(operator.collateral_tokens.into_iter().map(|a| a.to_vec()).collect::<Vec<_>>())
         .bind(operator.collateral_amounts.into_iter().map(|a| a.to_le_bytes_vec()).collect::<Vec<_>>())
+        .bind(operator.status.as_str())
+        .bind(operator.metadata.as_ref().map(OperatorMetadata::to_json))
         .execute(&self.conn)
         .await?;
 
         Ok(())
     }
 
+    async fn get_operator_settings(&self, signer: Address) -> DbResult<OperatorSettings> {
+        let row: Option<OperatorSettingsRow> = sqlx::query_as(
+            "
+            SELECT requires_acceptance, nonce
+            FROM operator_settings
+            WHERE signer = $1
+            ",
+        )
+        .bind(signer.to_vec())
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(row.map(Into::into).unwrap_or_default())
+    }
+
+    async fn update_operator_settings(
+        &self,
+        signer: Address,
+        settings: OperatorSettings,
+    ) -> DbResult<()> {
+        sqlx::query(
+            "
+            INSERT INTO operator_settings (signer, requires_acceptance, nonce, last_update)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (signer)
+            DO UPDATE SET requires_acceptance = $2, nonce = $3, last_update = NOW()
+            ",
+        )
+        .bind(signer.to_vec())
+        .bind(settings.requires_acceptance)
+        .bind(settings.nonce as i64)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn prune_expired_registrations(&self, now: u64) -> DbResult<u64> {
+        let rows_affected = sqlx::query(
+            "
+            DELETE FROM validator_registrations
+            WHERE expiry <> 0 AND expiry <= $1
+            ",
+        )
+        .bind(now as i64)
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected)
+    }
+
     async fn list_registrations(&self) -> DbResult<Vec<Registration>> {
         let rows: Vec<ValidatorRegistrationRow> = sqlx::query_as(
             "
-            SELECT pubkey, index, signature, expiry, gas_limit, operator, priority, source, last_update
+            SELECT pubkey, index, signature, expiry, gas_limit, operator, nonce, priority, source::text AS source, needs_redelegation, last_update
             FROM validator_registrations
+            WHERE expiry = 0 OR expiry > $1
             ",
         )
+        .bind(unix_seconds() as i64)
         .fetch_all(&self.conn)
         .await?;
 
@@ -220,25 +1029,32 @@ impl RegistryDb for SQLDb<Postgres> {
         let rows: Vec<ValidatorRegistrationRow> =
             sqlx::query_as(
                     "
-                    SELECT pubkey, index, signature, expiry, gas_limit, operator, priority, source, last_update
+                    SELECT pubkey, index, signature, expiry, gas_limit, operator, nonce, priority, source::text AS source, needs_redelegation, last_update
                     FROM validator_registrations
-                    WHERE pubkey = ANY($1)
+                    WHERE pubkey = ANY($1) AND (expiry = 0 OR expiry > $2)
                     ",
                 )
                 .bind(pubkeys.iter().map(|p| p.serialize()).collect::<Vec<_>>())
+                .bind(unix_seconds() as i64)
                 .fetch_all(&self.conn)
                 .await?;
 
         rows.into_iter().map(TryInto::try_into).collect()
     }
 
-    async fn list_validators(&self) -> DbResult<Vec<RegistryEntry>> {
+    async fn list_validators(&self, filter: DiscoveryFilter) -> DbResult<Vec<RegistryEntry>> {
         let rows: Vec<ValidatorRegistrationRow> = sqlx::query_as(
             "
-            SELECT vr.pubkey, vr.index, vr.signature, vr.expiry, vr.gas_limit, vr.operator, vr.priority, vr.source, vr.last_update, o.rpc
+            SELECT vr.pubkey, vr.index, vr.signature, vr.expiry, vr.gas_limit, vr.operator, vr.nonce, vr.priority, vr.source::text AS source, vr.needs_redelegation, vr.last_update, o.rpc
             FROM validator_registrations vr LEFT JOIN operators o ON o.signer = vr.operator
+            WHERE (vr.expiry = 0 OR vr.expiry > $1) AND NOT vr.needs_redelegation AND o.status = 'active'
+                AND ($2::protocol_enum IS NULL OR o.protocol = $2::protocol_enum)
+                AND ($3::source_enum IS NULL OR vr.source = $3::source_enum)
             ",
         )
+        .bind(unix_seconds() as i64)
+        .bind(filter.protocol.map(|p| p.as_str()))
+        .bind(filter.source.map(|s| s.as_str()))
         .fetch_all(&self.conn)
         .await?;
 
@@ -252,12 +1068,14 @@ impl RegistryDb for SQLDb<Postgres> {
         let rows: Vec<ValidatorRegistrationRow> =
             sqlx::query_as(
                 "
-                SELECT vr.pubkey, vr.index, vr.signature, vr.expiry, vr.gas_limit, vr.operator, vr.priority, vr.source, vr.last_update, o.rpc
+                SELECT vr.pubkey, vr.index, vr.signature, vr.expiry, vr.gas_limit, vr.operator, vr.nonce, vr.priority, vr.source::text AS source, vr.needs_redelegation, vr.last_update, o.rpc
                 FROM validator_registrations vr LEFT JOIN operators o ON o.signer = vr.operator
-                WHERE vr.pubkey = ANY($1)
+                WHERE vr.pubkey = ANY($1) AND (vr.expiry = 0 OR vr.expiry > $2)
+                    AND NOT vr.needs_redelegation AND o.status = 'active'
                 ",
             )
             .bind(pubkeys.iter().map(|p| p.serialize()).collect::<Vec<_>>())
+            .bind(unix_seconds() as i64)
             .fetch_all(&self.conn)
             .await?;
 
@@ -268,62 +1086,192 @@ impl RegistryDb for SQLDb<Postgres> {
         let rows: Vec<ValidatorRegistrationRow> =
             sqlx::query_as(
                 "
-                SELECT vr.pubkey, vr.index, vr.signature, vr.expiry, vr.gas_limit, vr.operator, vr.priority, vr.source, vr.last_update, o.rpc
+                SELECT vr.pubkey, vr.index, vr.signature, vr.expiry, vr.gas_limit, vr.operator, vr.nonce, vr.priority, vr.source::text AS source, vr.needs_redelegation, vr.last_update, o.rpc
                 FROM validator_registrations vr LEFT JOIN operators o ON o.signer = vr.operator
-                WHERE vr.index = ANY($1)
+                WHERE vr.index = ANY($1) AND (vr.expiry = 0 OR vr.expiry > $2)
+                    AND NOT vr.needs_redelegation AND o.status = 'active'
                 ",
             )
             .bind(indices.into_iter().map(|i| i as i64).collect::<Vec<_>>())
+            .bind(unix_seconds() as i64)
             .fetch_all(&self.conn)
             .await?;
 
         rows.into_iter().map(TryInto::try_into).collect()
     }
 
-    async fn list_operators(&self) -> DbResult<Vec<Operator>> {
+    async fn list_operators(&self, filter: DiscoveryFilter) -> DbResult<Vec<Operator>> {
         let rows: Vec<OperatorRow> = sqlx::query_as(
             "
-            SELECT signer, rpc, protocol, source, collateral_tokens, collateral_amounts, last_update
-            FROM operators
+            SELECT o.signer, o.rpc, o.protocol::text AS protocol, o.source::text AS source, o.collateral_tokens, o.collateral_amounts, o.status::text AS status, o.metadata, o.last_update,
+                COALESCE(s.requires_acceptance, FALSE) AS requires_acceptance
+            FROM operators o LEFT JOIN operator_settings s ON s.signer = o.signer
+            WHERE ($1::protocol_enum IS NULL OR o.protocol = $1::protocol_enum)
+                AND ($2::source_enum IS NULL OR o.source = $2::source_enum)
             ",
         )
+        .bind(filter.protocol.map(|p| p.as_str()))
+        .bind(filter.source.map(|s| s.as_str()))
         .fetch_all(&self.conn)
         .await?;
 
-        rows.into_iter().map(TryInto::try_into).collect()
+        let operators = rows.into_iter().map(TryInto::try_into).collect::<DbResult<Vec<_>>>()?;
+        self.with_operator_sets(operators).await
     }
 
     async fn get_operators_by_signer(&self, signers: &[Address]) -> DbResult<Vec<Operator>> {
         let rows: Vec<OperatorRow> =
             sqlx::query_as(
                 "
-                SELECT signer, rpc, protocol, source, collateral_tokens, collateral_amounts, last_update
-                FROM operators
-                WHERE signer = ANY($1)
+                SELECT o.signer, o.rpc, o.protocol::text AS protocol, o.source::text AS source, o.collateral_tokens, o.collateral_amounts, o.status::text AS status, o.metadata, o.last_update,
+                    COALESCE(s.requires_acceptance, FALSE) AS requires_acceptance
+                FROM operators o LEFT JOIN operator_settings s ON s.signer = o.signer
+                WHERE o.signer = ANY($1)
                 ",
             )
             .bind(signers.iter().map(|s| s.to_vec()).collect::<Vec<_>>())
             .fetch_all(&self.conn)
             .await?;
 
+        let operators = rows.into_iter().map(TryInto::try_into).collect::<DbResult<Vec<_>>>()?;
+        self.with_operator_sets(operators).await
+    }
+
+    async fn list_whitelisted_collateral(
+        &self,
+        protocol: Option<RestakingProtocol>,
+    ) -> DbResult<Vec<WhitelistedCollateral>> {
+        let rows: Vec<WhitelistEventRow> = sqlx::query_as(
+            "
+            SELECT block_number, log_index, protocol::text AS protocol, address, action::text AS action
+            FROM whitelist_events
+            WHERE $1::protocol_enum IS NULL OR protocol = $1::protocol_enum
+            ORDER BY block_number, log_index
+            ",
+        )
+        .bind(protocol.map(|p| p.as_str()))
+        .fetch_all(&self.conn)
+        .await?;
+
+        let events = rows.into_iter().map(TryInto::try_into).collect::<DbResult<Vec<_>>>()?;
+
+        Ok(whitelist_from_events(&events))
+    }
+
+    async fn list_rpc_endpoint_history(&self, signer: Address) -> DbResult<Vec<RpcEndpointUpdate>> {
+        let rows: Vec<(i64, i64, String)> = sqlx::query_as(
+            "
+            SELECT block_number, epoch, rpc
+            FROM operator_rpc_history
+            WHERE signer = $1
+            ORDER BY block_number
+            ",
+        )
+        .bind(signer.to_vec())
+        .fetch_all(&self.conn)
+        .await?;
+
+        rows.into_iter()
+            .map(|(block_number, epoch, rpc)| {
+                Ok(RpcEndpointUpdate {
+                    operator: signer,
+                    rpc_endpoint: rpc.parse()?,
+                    block_number: block_number as u64,
+                    epoch: epoch as u64,
+                })
+            })
+            .collect()
+    }
+
+    async fn get_rpc_endpoints_at(
+        &self,
+        signers: &[Address],
+        epoch: u64,
+    ) -> DbResult<HashMap<Address, Url>> {
+        let rows: Vec<(Vec<u8>, String)> = sqlx::query_as(
+            "
+            SELECT DISTINCT ON (signer) signer, rpc
+            FROM operator_rpc_history
+            WHERE signer = ANY($1) AND epoch <= $2
+            ORDER BY signer, block_number DESC
+            ",
+        )
+        .bind(signers.iter().map(|s| s.to_vec()).collect::<Vec<_>>())
+        .bind(epoch as i64)
+        .fetch_all(&self.conn)
+        .await?;
+
+        rows.into_iter()
+            .map(|(signer, rpc)| Ok((Address::try_from(signer.as_slice())?, rpc.parse()?)))
+            .collect()
+    }
+
+    async fn get_stake_snapshot(
+        &self,
+        signer: Address,
+        epoch: u64,
+    ) -> DbResult<Option<StakeSnapshot>> {
+        let row: Option<StakeSnapshotRow> = sqlx::query_as(
+            "
+            SELECT signer, epoch, timestamp, collateral_tokens, collateral_amounts
+            FROM stake_snapshots
+            WHERE signer = $1 AND epoch = $2
+            ",
+        )
+        .bind(signer.to_vec())
+        .bind(epoch as i64)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        row.map(TryInto::try_into).transpose()
+    }
+
+    async fn list_stake_snapshots(&self, signer: Address) -> DbResult<Vec<StakeSnapshot>> {
+        let rows: Vec<StakeSnapshotRow> = sqlx::query_as(
+            "
+            SELECT signer, epoch, timestamp, collateral_tokens, collateral_amounts
+            FROM stake_snapshots
+            WHERE signer = $1
+            ORDER BY epoch
+            ",
+        )
+        .bind(signer.to_vec())
+        .fetch_all(&self.conn)
+        .await?;
+
+        rows.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn list_source_conflicts(&self) -> DbResult<Vec<SourceConflict>> {
+        let rows: Vec<SourceConflictRow> = sqlx::query_as(
+            "
+            SELECT pubkey, operator, claim_sources, claim_priorities, claim_operators
+            FROM source_conflicts
+            ",
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
         rows.into_iter().map(TryInto::try_into).collect()
     }
 
-    async fn get_sync_state(&self) -> DbResult<SyncStateUpdate> {
-        let (block_number, epoch, slot): (i64, i64, i64) = sqlx::query_as(
+    async fn get_sync_state(&self) -> DbResult<Option<SyncStateUpdate>> {
+        let row: Option<(i64, i64, i64)> = sqlx::query_as(
             "
             SELECT block_number, epoch, slot
             FROM sync_state
+            ORDER BY block_number DESC
+            LIMIT 1
             ",
         )
-        .fetch_one(&self.conn)
+        .fetch_optional(&self.conn)
         .await?;
 
-        Ok(SyncStateUpdate {
+        Ok(row.map(|(block_number, epoch, slot)| SyncStateUpdate {
             block_number: block_number as u64,
             epoch: epoch as u64,
             slot: slot as u64,
-        })
+        }))
     }
 
     async fn update_sync_state(&self, state: SyncStateUpdate) -> DbResult<()> {
@@ -344,3 +1292,231 @@ impl RegistryDb for SQLDb<Postgres> {
         Ok(())
     }
 }
+
+/// Records the nonce of the last accepted (de)registration message of a validator.
+async fn upsert_nonce(
+    transaction: &mut sqlx::Transaction<'static, Postgres>,
+    pubkey: &BlsPublicKey,
+    nonce: u64,
+) -> DbResult<()> {
+    sqlx::query(
+        "
+        INSERT INTO validator_nonces (pubkey, nonce, last_update)
+        VALUES ($1, $2, NOW())
+        ON CONFLICT (pubkey)
+        DO UPDATE SET nonce = $2, last_update = NOW()
+        ",
+    )
+    .bind(pubkey.serialize())
+    .bind(nonce as i64)
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitives::registry::DataSource;
+
+    use super::*;
+
+    /// Connects to the Postgres database at `DATABASE_URL`, running the DDL queries.
+    async fn test_db() -> eyre::Result<Option<SQLDb<Postgres>>> {
+        let Ok(url) = std::env::var("DATABASE_URL") else {
+            tracing::warn!("Skipping test because of missing DATABASE_URL");
+            return Ok(None)
+        };
+
+        Ok(Some(SQLDb::new(&url).await?))
+    }
+
+    #[tokio::test]
+    async fn test_legacy_signed_registration_priority() -> eyre::Result<()> {
+        let Some(db) = test_db().await? else { return Ok(()) };
+
+        let (a, b) = (Address::random(), Address::random());
+        for signer in [a, b] {
+            db.register_operator(Operator {
+                signer,
+                rpc_endpoint: "https://rpc.example.com".parse()?,
+                collateral_tokens: vec![],
+                collateral_amounts: vec![],
+                protocol: None,
+                source: DataSource::Onchain,
+                metadata: None,
+                requires_acceptance: false,
+                status: OperatorStatus::Active,
+                operator_sets: vec![],
+            })
+            .await?;
+        }
+
+        // A signed registration stored with priority 0, before source priorities were introduced
+        let pubkey = BlsPublicKey::random();
+        sqlx::query(
+            "
+            INSERT INTO validator_registrations (pubkey, index, signature, expiry, gas_limit, operator, nonce, priority, source, last_update)
+            VALUES ($1, 0, $2, 0, 10000, $3, 1, 0, 'none', NOW())
+            ",
+        )
+        .bind(pubkey.serialize())
+        .bind(vec![0u8; 96])
+        .bind(a.to_vec())
+        .execute(&db.conn)
+        .await?;
+
+        // The migration raises it to the signed priority
+        db.ddl().await?;
+
+        // A priority 0 source no longer replaces it
+        let mut tx = db.begin_sync().await?;
+        tx.register_validators(&[Registration {
+            validator_pubkey: pubkey.clone(),
+            validator_index: 0,
+            operator: b,
+            gas_limit: 10_000,
+            expiry: 0,
+            nonce: 0,
+            signature: None,
+            source: DataSource::Lido,
+            priority: 0,
+            needs_redelegation: false,
+        }])
+        .await?;
+
+        let (priority, operator): (i16, Vec<u8>) = sqlx::query_as(
+            "SELECT priority, operator FROM validator_registrations WHERE pubkey = $1",
+        )
+        .bind(pubkey.serialize())
+        .fetch_one(&mut *tx.transaction)
+        .await?;
+        assert_eq!(priority as u16, Registration::SIGNED_PRIORITY);
+        assert_eq!(operator, a.to_vec());
+
+        // Roll back the sync transaction, and clean up
+        drop(tx);
+        sqlx::query("DELETE FROM validator_registrations WHERE pubkey = $1")
+            .bind(pubkey.serialize())
+            .execute(&db.conn)
+            .await?;
+        sqlx::query("DELETE FROM operators WHERE signer = ANY($1)")
+            .bind(vec![a.to_vec(), b.to_vec()])
+            .execute(&db.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rollback_reverted_operators() -> eyre::Result<()> {
+        let Some(db) = test_db().await? else { return Ok(()) };
+
+        let (onchain, lido) = (Address::random(), Address::random());
+        let block_number = u64::from(u32::MAX);
+
+        let mut tx = db.begin_sync().await?;
+        for (log_index, (signer, source, status)) in [
+            (onchain, DataSource::Onchain, OperatorStatus::Active),
+            (lido, DataSource::Lido, OperatorStatus::Paused),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            tx.register_operator(Operator {
+                signer,
+                rpc_endpoint: "https://rpc.example.com".parse()?,
+                collateral_tokens: vec![],
+                collateral_amounts: vec![],
+                protocol: None,
+                source,
+                metadata: None,
+                requires_acceptance: false,
+                status,
+                operator_sets: vec![],
+            })
+            .await?;
+
+            let event = OperatorEvent {
+                block_number: block_number + 1,
+                log_index: log_index as u64,
+                signer,
+                status,
+                rpc_endpoint: None,
+                protocol: None,
+                metadata: None,
+            };
+            tx.record_operator_event(&event).await?;
+        }
+
+        // The reverted on-chain operator is removed, and the other one has no on-chain status left
+        tx.rollback_contract_events(block_number).await?;
+        let statuses: Vec<(Vec<u8>, String)> =
+            sqlx::query_as("SELECT signer, status::TEXT FROM operators WHERE signer = ANY($1)")
+                .bind(vec![onchain.to_vec(), lido.to_vec()])
+                .fetch_all(&mut *tx.transaction)
+                .await?;
+        assert_eq!(statuses, vec![(lido.to_vec(), "active".to_owned())]);
+
+        // Roll back the sync transaction
+        drop(tx);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_operator_set_members_rollback() -> eyre::Result<()> {
+        let Some(db) = test_db().await? else { return Ok(()) };
+
+        /// Returns the materialized operators and strategies of the operator set.
+        async fn operator_set(
+            tx: &mut SQLSyncTransaction,
+            id: u32,
+        ) -> eyre::Result<(Vec<Vec<u8>>, Vec<Vec<u8>>)> {
+            let operators: Vec<(Vec<u8>,)> = sqlx::query_as(
+                "SELECT operator FROM operator_set_members WHERE operator_set_id = $1",
+            )
+            .bind(i64::from(id))
+            .fetch_all(&mut *tx.transaction)
+            .await?;
+            let strategies: Vec<(Vec<u8>,)> = sqlx::query_as(
+                "SELECT strategy FROM operator_set_strategies WHERE operator_set_id = $1 ORDER BY strategy",
+            )
+            .bind(i64::from(id))
+            .fetch_all(&mut *tx.transaction)
+            .await?;
+
+            Ok((
+                operators.into_iter().map(|(o,)| o).collect(),
+                strategies.into_iter().map(|(s,)| s).collect(),
+            ))
+        }
+
+        let id = u32::from_be_bytes(Address::random()[..4].try_into()?);
+        let (a, steth, reth) =
+            (Address::random(), Address::with_last_byte(1), Address::with_last_byte(2));
+        let block_number = u64::from(u32::MAX);
+
+        let mut tx = db.begin_sync().await?;
+        for (block_number, log_index, change) in [
+            (block_number, 0, OperatorSetChange::StrategyAdded(steth)),
+            (block_number, 1, OperatorSetChange::OperatorAdded(a)),
+            (block_number + 1, 0, OperatorSetChange::OperatorRemoved(a)),
+            (block_number + 1, 1, OperatorSetChange::StrategyAdded(reth)),
+        ] {
+            let event = OperatorSetEvent { block_number, log_index, operator_set_id: id, change };
+            tx.record_operator_set_event(&event).await?;
+        }
+
+        assert_eq!(operator_set(&mut tx, id).await?, (vec![], vec![steth.to_vec(), reth.to_vec()]));
+
+        // Rolling back the last block restores the membership and strategies before it
+        tx.rollback_contract_events(block_number).await?;
+        assert_eq!(operator_set(&mut tx, id).await?, (vec![a.to_vec()], vec![steth.to_vec()]));
+
+        // Roll back the sync transaction
+        drop(tx);
+
+        Ok(())
+    }
+}
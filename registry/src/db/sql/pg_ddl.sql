@@ -4,7 +4,7 @@
 DO $$ 
 BEGIN
     IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'source_enum') THEN
-        CREATE TYPE source_enum AS ENUM ('lido', 'none');
+        CREATE TYPE source_enum AS ENUM ('lido', 'none', 'api', 'onchain', 'feed');
     END IF;
 END $$;
 
@@ -12,7 +12,41 @@ END $$;
 DO $$ 
 BEGIN
     IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'protocol_enum') THEN
-        CREATE TYPE protocol_enum AS ENUM ('lido', 'none');
+        CREATE TYPE protocol_enum AS ENUM ('lido', 'none', 'symbiotic', 'eigenlayer');
+    END IF;
+END $$;
+
+-- Add the restaking protocols to protocol_enum types created before they were supported
+ALTER TYPE protocol_enum ADD VALUE IF NOT EXISTS 'symbiotic';
+ALTER TYPE protocol_enum ADD VALUE IF NOT EXISTS 'eigenlayer';
+
+-- Add the API and on-chain sources to source_enum types created before they were supported.
+-- Rows stored before sources were tracked keep 'none'.
+ALTER TYPE source_enum ADD VALUE IF NOT EXISTS 'api';
+ALTER TYPE source_enum ADD VALUE IF NOT EXISTS 'onchain';
+ALTER TYPE source_enum ADD VALUE IF NOT EXISTS 'feed';
+
+-- Create the operator_status_enum type if it does not exist
+DO $$ 
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'operator_status_enum') THEN
+        CREATE TYPE operator_status_enum AS ENUM ('active', 'paused', 'deregistered');
+    END IF;
+END $$;
+
+-- Create the whitelist_action_enum type if it does not exist
+DO $$ 
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'whitelist_action_enum') THEN
+        CREATE TYPE whitelist_action_enum AS ENUM ('whitelisted', 'paused', 'unpaused', 'removed');
+    END IF;
+END $$;
+
+-- Create the operator_set_action_enum type if it does not exist
+DO $$ 
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'operator_set_action_enum') THEN
+        CREATE TYPE operator_set_action_enum AS ENUM ('created', 'operator_added', 'operator_removed', 'strategy_added', 'strategy_removed');
     END IF;
 END $$;
 
@@ -20,10 +54,29 @@ END $$;
 CREATE TABLE IF NOT EXISTS operators (
     signer BYTEA PRIMARY KEY,             -- Unique identifier for the operator
     rpc TEXT NOT NULL,                    -- RPC endpoint
-    protocol protocol_enum,               -- Protocol type (optional)
+    protocol protocol_enum,               -- Restaking protocol (optional)
     source source_enum NOT NULL,          -- Source of the operator data
     collateral_tokens BYTEA[] NOT NULL,   -- Array of collateral token identifiers
     collateral_amounts BYTEA[] NOT NULL,  -- Array of collateral token amounts
+    status operator_status_enum NOT NULL, -- On-chain status of the operator
+    metadata TEXT,                        -- Operator metadata as JSON (optional)
+    last_update TIMESTAMP NOT NULL        -- Last time this record was updated
+);
+
+-- Add the status to operators tables created before it was tracked. Operators stored before are
+-- considered active, until the syncer observes otherwise.
+ALTER TABLE operators ADD COLUMN IF NOT EXISTS status operator_status_enum NOT NULL DEFAULT 'active';
+
+-- Add the metadata to operators tables created before it was stored
+ALTER TABLE operators ADD COLUMN IF NOT EXISTS metadata TEXT;
+
+-- Create the operator_settings table if it does not exist.
+-- Settings are managed by operators through signed updates, and are kept separately from the
+-- synced operator data.
+CREATE TABLE IF NOT EXISTS operator_settings (
+    signer BYTEA PRIMARY KEY,             -- Operator signer address
+    requires_acceptance BOOLEAN NOT NULL, -- Whether registrations require the operator acceptance signature
+    nonce BIGINT NOT NULL,                -- Nonce of the last accepted settings update
     last_update TIMESTAMP NOT NULL        -- Last time this record was updated
 );
 
@@ -31,18 +84,191 @@ CREATE TABLE IF NOT EXISTS operators (
 CREATE TABLE IF NOT EXISTS validator_registrations (
     pubkey BYTEA PRIMARY KEY,                              -- BLS public key of the validator
     index BIGINT NOT NULL,                                 -- Index of the validator in the beacon chain
-    signature BYTEA NOT NULL,                              -- Signature of the registration
-    expiry BIGINT NOT NULL,                                -- Expiry timestamp of the registration
+    signature BYTEA,                                       -- Signature of the registration (if individually signed)
+    expiry BIGINT NOT NULL,                                -- Expiry timestamp of the registration (0 = never)
     gas_limit BIGINT NOT NULL,                             -- Gas limit for the validator
     operator BYTEA NOT NULL REFERENCES operators(signer),  -- Operator address (foreign key)
+    nonce BIGINT NOT NULL,                                 -- Nonce of the registration message
     priority SMALLINT NOT NULL,                            -- Priority level of this registration
     source source_enum NOT NULL,                           -- Source of the registration data
+    needs_redelegation BOOLEAN NOT NULL DEFAULT FALSE,     -- Whether the operator was deregistered on-chain
     last_update TIMESTAMP NOT NULL                         -- Last time this record was updated
 );
 
+-- Add the nonce to validator_registrations tables created before registrations were nonced.
+-- Registrations stored before keep nonce 0.
+ALTER TABLE validator_registrations ADD COLUMN IF NOT EXISTS nonce BIGINT NOT NULL DEFAULT 0;
+
+-- Drop the NOT NULL constraint on the signature of validator_registrations tables created before
+-- aggregate signatures were accepted, whose registrations have no individual signature
+ALTER TABLE validator_registrations ALTER COLUMN signature DROP NOT NULL;
+
+-- Add the re-delegation mark to validator_registrations tables created before it was tracked
+ALTER TABLE validator_registrations ADD COLUMN IF NOT EXISTS needs_redelegation BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Raise registrations signed by the validators, stored with priority 0 before source priorities
+-- were introduced, to the signed priority (`Registration::SIGNED_PRIORITY`), so that external
+-- sources never replace them
+UPDATE validator_registrations SET priority = 256 WHERE signature IS NOT NULL AND priority < 256;
+
+-- Index on the operator of registrations, for marking validators of deregistered operators
+CREATE INDEX IF NOT EXISTS validator_registrations_operator_idx ON validator_registrations (operator);
+
+-- Index on the expiry of registrations, for pruning expired registrations
+CREATE INDEX IF NOT EXISTS validator_registrations_expiry_idx ON validator_registrations (expiry);
+
+-- Create the validator_nonces table if it does not exist.
+-- Nonces outlive registrations, so that deregistered validators can't be re-registered with a
+-- replayed message.
+CREATE TABLE IF NOT EXISTS validator_nonces (
+    pubkey BYTEA PRIMARY KEY,      -- BLS public key of the validator
+    nonce BIGINT NOT NULL,         -- Nonce of the last accepted (de)registration message
+    last_update TIMESTAMP NOT NULL -- Last time this record was updated
+);
+
+-- Create the operator_deregistration_nonces table if it does not exist.
+-- Nonces of operator-signed deregistrations, kept per operator so that operators can't block the
+-- validator nonces of the validators they deregister.
+CREATE TABLE IF NOT EXISTS operator_deregistration_nonces (
+    signer BYTEA PRIMARY KEY,      -- Operator signer address
+    nonce BIGINT NOT NULL,         -- Nonce of the last accepted operator deregistration message
+    last_update TIMESTAMP NOT NULL -- Last time this record was updated
+);
+
+-- Create the operator_rpc_history table if it does not exist.
+-- The RPC endpoints of operators over time, as observed on-chain. Endpoint updates emit no
+-- contract events, so they are polled on every epoch.
+CREATE TABLE IF NOT EXISTS operator_rpc_history (
+    signer BYTEA NOT NULL,        -- Operator signer address
+    block_number BIGINT NOT NULL, -- Block number at which the endpoint was observed
+    epoch BIGINT NOT NULL,        -- Beacon chain epoch from which the endpoint is effective
+    rpc TEXT NOT NULL,            -- RPC endpoint
+    PRIMARY KEY (signer, block_number)
+);
+
+-- Create the operator_events table if it does not exist.
+-- Applied events of the operators registry contract, kept to roll back operators on reorgs.
+CREATE TABLE IF NOT EXISTS operator_events (
+    block_number BIGINT NOT NULL,          -- Block number of the event
+    log_index BIGINT NOT NULL,             -- Index of the event log in the block
+    signer BYTEA NOT NULL,                 -- Operator signer address
+    status operator_status_enum NOT NULL,  -- Status of the operator after the event
+    rpc TEXT,                              -- RPC endpoint (registrations only)
+    protocol protocol_enum,                -- Restaking protocol of the middleware (registrations only)
+    metadata TEXT,                         -- Operator metadata as JSON (registrations only)
+    PRIMARY KEY (block_number, log_index)
+);
+
+-- Add the protocol and metadata to operator_events tables created before they were recorded
+ALTER TABLE operator_events ADD COLUMN IF NOT EXISTS protocol protocol_enum;
+ALTER TABLE operator_events ADD COLUMN IF NOT EXISTS metadata TEXT;
+
+-- Index on the signer of operator events, for restoring operators on rollbacks
+CREATE INDEX IF NOT EXISTS operator_events_signer_idx ON operator_events (signer);
+
+-- Create the whitelist_events table if it does not exist.
+-- Applied whitelist events of the restaking middleware contracts. The current whitelist is the
+-- last event of every collateral, so rolling back on reorgs only deletes events.
+CREATE TABLE IF NOT EXISTS whitelist_events (
+    block_number BIGINT NOT NULL,          -- Block number of the event
+    log_index BIGINT NOT NULL,             -- Index of the event log in the block
+    protocol protocol_enum NOT NULL,       -- Restaking protocol of the middleware
+    address BYTEA NOT NULL,                -- Vault or strategy address
+    action whitelist_action_enum NOT NULL, -- Whitelist change
+    PRIMARY KEY (block_number, log_index)
+);
+
+-- Create the operator_set_events table if it does not exist.
+-- Applied operator set events of the EigenLayer allocation manager for the bolt AVS, kept to roll
+-- back the operator set members and strategies on reorgs.
+CREATE TABLE IF NOT EXISTS operator_set_events (
+    block_number BIGINT NOT NULL,             -- Block number of the event
+    log_index BIGINT NOT NULL,                -- Index of the event log in the block
+    operator_set_id BIGINT NOT NULL,          -- ID of the operator set
+    action operator_set_action_enum NOT NULL, -- Operator set change
+    address BYTEA,                            -- Operator or strategy address (none on creation)
+    PRIMARY KEY (block_number, log_index)
+);
+
+-- Create the operator_set_members table if it does not exist.
+-- The current operators of every operator set, materialized from the operator set events.
+CREATE TABLE IF NOT EXISTS operator_set_members (
+    operator_set_id BIGINT NOT NULL, -- ID of the operator set
+    operator BYTEA NOT NULL,         -- Operator address
+    PRIMARY KEY (operator_set_id, operator)
+);
+
+-- Create the operator_set_strategies table if it does not exist.
+-- The current strategies of every operator set, materialized from the operator set events.
+CREATE TABLE IF NOT EXISTS operator_set_strategies (
+    operator_set_id BIGINT NOT NULL, -- ID of the operator set
+    strategy BYTEA NOT NULL,         -- Strategy address
+    PRIMARY KEY (operator_set_id, strategy)
+);
+
+-- Materialize the operator sets of databases created before the tables above. The last
+-- addition or removal of every operator and strategy determines whether it's in the set.
+INSERT INTO operator_set_members (operator_set_id, operator)
+SELECT operator_set_id, address FROM (
+    SELECT DISTINCT ON (operator_set_id, address) operator_set_id, address, action
+    FROM operator_set_events
+    WHERE action IN ('operator_added', 'operator_removed')
+    ORDER BY operator_set_id, address, block_number DESC, log_index DESC
+) e
+WHERE action = 'operator_added'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO operator_set_strategies (operator_set_id, strategy)
+SELECT operator_set_id, address FROM (
+    SELECT DISTINCT ON (operator_set_id, address) operator_set_id, address, action
+    FROM operator_set_events
+    WHERE action IN ('strategy_added', 'strategy_removed')
+    ORDER BY operator_set_id, address, block_number DESC, log_index DESC
+) e
+WHERE action = 'strategy_added'
+ON CONFLICT DO NOTHING;
+
+-- Create the contract_checkpoints table if it does not exist.
+-- The last block of every ingested range of contract logs, used to detect reorgs.
+CREATE TABLE IF NOT EXISTS contract_checkpoints (
+    block_number BIGINT PRIMARY KEY,  -- Last block number of the ingested range
+    block_hash BYTEA NOT NULL         -- Hash of the block at ingestion time
+);
+
+-- Create the contract_event_streams table if it does not exist.
+-- The contract event streams ingested up to the contract checkpoints. Streams missing from it are
+-- backfilled on their own.
+CREATE TABLE IF NOT EXISTS contract_event_streams (
+    stream TEXT PRIMARY KEY -- Name of the event stream
+);
+
+-- Create the stake_snapshots table if it does not exist.
+-- The stake of every operator at the start of every operators registry epoch.
+CREATE TABLE IF NOT EXISTS stake_snapshots (
+    signer BYTEA NOT NULL,               -- Operator signer address
+    epoch BIGINT NOT NULL,               -- Operators registry epoch
+    timestamp BIGINT NOT NULL,           -- UNIX timestamp at which the stake was measured
+    collateral_tokens BYTEA[] NOT NULL,  -- Array of collateral token identifiers
+    collateral_amounts BYTEA[] NOT NULL, -- Array of collateral token amounts
+    last_update TIMESTAMP NOT NULL,      -- Last time this record was updated
+    PRIMARY KEY (signer, epoch)
+);
+
+-- Create the source_conflicts table if it does not exist.
+-- Validators that external sources claim for different operators, with the claims as parallel
+-- arrays ordered by descending priority.
+CREATE TABLE IF NOT EXISTS source_conflicts (
+    pubkey BYTEA PRIMARY KEY,              -- BLS public key of the validator
+    operator BYTEA NOT NULL,               -- Operator of the registered claim
+    claim_sources TEXT[] NOT NULL,         -- Names of the claiming sources
+    claim_priorities SMALLINT[] NOT NULL,  -- Priorities of the claiming sources
+    claim_operators BYTEA[] NOT NULL,      -- Operators claimed by the sources
+    last_update TIMESTAMP NOT NULL         -- Last time this record was updated
+);
+
 -- Create the sync_state table if it doesn't exist
 CREATE TABLE IF NOT EXISTS sync_state (
     block_number BIGINT PRIMARY KEY,  -- Last synced block number
     epoch BIGINT NOT NULL,            -- Last synced epoch
-    slot BIGINT NOT NULL,             -- Last synced slot
+    slot BIGINT NOT NULL              -- Last synced slot
 );
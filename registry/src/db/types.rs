@@ -2,9 +2,17 @@
 
 use alloy::primitives::{Address, U256};
 
-use crate::primitives::{BlsPublicKey, BlsSignature};
+use crate::primitives::{
+    registry::{
+        DataSource, OperatorMetadata, OperatorStatus, SourceClaim, SourceConflict, WhitelistAction,
+    },
+    BlsPublicKey, BlsSignature,
+};
 
-use super::{DbError, Operator, Registration, RegistryEntry};
+use super::{
+    DbError, Operator, OperatorSettings, Registration, RegistryEntry, RestakingProtocol,
+    StakeSnapshot, WhitelistEvent,
+};
 
 #[derive(sqlx::FromRow, Debug)]
 pub(crate) struct OperatorRow {
@@ -15,6 +23,9 @@ pub(crate) struct OperatorRow {
     pub collateral_tokens: Vec<Vec<u8>>,    // BYTEA[]
     pub collateral_amounts: Vec<Vec<u8>>,   // BYTEA[]
     pub last_update: chrono::NaiveDateTime, // TIMESTAMP
+    pub status: String,                     // OPERATOR_STATUS_ENUM
+    pub metadata: Option<String>,           // TEXT (JSON, optional)
+    pub requires_acceptance: bool,          // BOOLEAN (from operator_settings table)
 }
 
 impl TryFrom<OperatorRow> for Operator {
@@ -38,10 +49,30 @@ impl TryFrom<OperatorRow> for Operator {
             rpc_endpoint: value.rpc.parse()?,
             collateral_tokens,
             collateral_amounts,
+            // Operators stored before protocols were tracked have a legacy value or none
+            protocol: value.protocol.as_deref().and_then(RestakingProtocol::parse),
+            source: parse_source(&value.source)?,
+            metadata: value.metadata.as_deref().and_then(OperatorMetadata::parse),
+            requires_acceptance: value.requires_acceptance,
+            status: parse_operator_status(&value.status)?,
+            // Operator sets are attached from the operator set events
+            operator_sets: vec![],
         })
     }
 }
 
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct OperatorSettingsRow {
+    pub requires_acceptance: bool, // BOOLEAN
+    pub nonce: i64,                // BIGINT
+}
+
+impl From<OperatorSettingsRow> for OperatorSettings {
+    fn from(value: OperatorSettingsRow) -> Self {
+        Self { requires_acceptance: value.requires_acceptance, nonce: value.nonce as u64 }
+    }
+}
+
 #[derive(sqlx::FromRow, Debug)]
 pub(crate) struct ValidatorRegistrationRow {
     pub pubkey: Vec<u8>,                    // BYTEA
@@ -50,8 +81,10 @@ pub(crate) struct ValidatorRegistrationRow {
     pub expiry: i64,                        // BIGINT
     pub gas_limit: i64,                     // BIGINT
     pub operator: Vec<u8>,                  // BYTEA
-    pub priority: i32,                      // SMALLINT
+    pub nonce: i64,                         // BIGINT
+    pub priority: i16,                      // SMALLINT
     pub source: String,                     // SOURCE_ENUM
+    pub needs_redelegation: bool,           // BOOLEAN
     pub last_update: chrono::NaiveDateTime, // TIMESTAMP
     pub rpc: Option<String>,                // TEXT (from operators table)
 }
@@ -67,6 +100,10 @@ impl TryFrom<ValidatorRegistrationRow> for Registration {
             operator: parse_address(&value.operator)?,
             gas_limit: value.gas_limit as u64,
             expiry: value.expiry as u64,
+            nonce: value.nonce as u64,
+            source: parse_source(&value.source)?,
+            priority: value.priority as u16,
+            needs_redelegation: value.needs_redelegation,
         })
     }
 }
@@ -84,6 +121,113 @@ impl TryFrom<ValidatorRegistrationRow> for RegistryEntry {
     }
 }
 
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct ValidatorNonceRow {
+    pub pubkey: Vec<u8>, // BYTEA
+    pub nonce: i64,      // BIGINT
+}
+
+impl TryFrom<ValidatorNonceRow> for (BlsPublicKey, u64) {
+    type Error = DbError;
+
+    fn try_from(value: ValidatorNonceRow) -> Result<Self, Self::Error> {
+        Ok((parse_pubkey(&value.pubkey)?, value.nonce as u64))
+    }
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct WhitelistEventRow {
+    pub block_number: i64, // BIGINT
+    pub log_index: i64,    // BIGINT
+    pub protocol: String,  // PROTOCOL_ENUM
+    pub address: Vec<u8>,  // BYTEA
+    pub action: String,    // WHITELIST_ACTION_ENUM
+}
+
+impl TryFrom<WhitelistEventRow> for WhitelistEvent {
+    type Error = DbError;
+
+    fn try_from(value: WhitelistEventRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_number: value.block_number as u64,
+            log_index: value.log_index as u64,
+            protocol: RestakingProtocol::parse(&value.protocol)
+                .ok_or_else(|| DbError::InvalidEnum("protocol_enum", value.protocol.clone()))?,
+            address: parse_address(&value.address)?,
+            action: WhitelistAction::parse(&value.action).ok_or_else(|| {
+                DbError::InvalidEnum("whitelist_action_enum", value.action.clone())
+            })?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct StakeSnapshotRow {
+    pub signer: Vec<u8>,                  // BYTEA
+    pub epoch: i64,                       // BIGINT
+    pub timestamp: i64,                   // BIGINT
+    pub collateral_tokens: Vec<Vec<u8>>,  // BYTEA[]
+    pub collateral_amounts: Vec<Vec<u8>>, // BYTEA[]
+}
+
+impl TryFrom<StakeSnapshotRow> for StakeSnapshot {
+    type Error = DbError;
+
+    fn try_from(value: StakeSnapshotRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            operator: parse_address(&value.signer)?,
+            epoch: value.epoch as u64,
+            timestamp: value.timestamp as u64,
+            collateral_tokens: value
+                .collateral_tokens
+                .into_iter()
+                .map(|a| parse_address(&a))
+                .collect::<Result<_, _>>()?,
+            collateral_amounts: value
+                .collateral_amounts
+                .into_iter()
+                .map(|a| parse_u256(&a))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct SourceConflictRow {
+    pub pubkey: Vec<u8>,               // BYTEA
+    pub operator: Vec<u8>,             // BYTEA
+    pub claim_sources: Vec<String>,    // TEXT[]
+    pub claim_priorities: Vec<i16>,    // SMALLINT[]
+    pub claim_operators: Vec<Vec<u8>>, // BYTEA[]
+}
+
+impl TryFrom<SourceConflictRow> for SourceConflict {
+    type Error = DbError;
+
+    fn try_from(value: SourceConflictRow) -> Result<Self, Self::Error> {
+        let claims = value
+            .claim_sources
+            .into_iter()
+            .zip(value.claim_priorities)
+            .zip(value.claim_operators)
+            .map(|((source, priority), operator)| {
+                Ok(SourceClaim {
+                    source,
+                    priority: u8::try_from(priority)
+                        .map_err(|_| DbError::ParseUint("claim_priorities"))?,
+                    operator: parse_address(&operator)?,
+                })
+            })
+            .collect::<Result<_, DbError>>()?;
+
+        Ok(Self {
+            validator_pubkey: parse_pubkey(&value.pubkey)?,
+            operator: parse_address(&value.operator)?,
+            claims,
+        })
+    }
+}
+
 /// Utility function to parse an address from a byte array.
 fn parse_address(value: &[u8]) -> Result<Address, DbError> {
     Ok(Address::try_from(value)?)
@@ -94,11 +238,20 @@ fn parse_u256(value: &[u8]) -> Result<U256, DbError> {
     U256::try_from_le_slice(value).ok_or(DbError::ParseUint("invalid U256"))
 }
 
-/// Utility function to parse a BLS public key from a byte array.
+/// Utility function to parse an operator status from its database representation.
+fn parse_operator_status(value: &str) -> Result<OperatorStatus, DbError> {
+    OperatorStatus::parse(value)
+        .ok_or_else(|| DbError::InvalidEnum("operator_status_enum", value.to_string()))
+}
+
+/// Utility function to parse a data source from its database representation.
+fn parse_source(value: &str) -> Result<DataSource, DbError> {
+    DataSource::parse(value).ok_or_else(|| DbError::InvalidEnum("source_enum", value.to_string()))
+}
+
+/// Utility function to parse a BLS public key from a compressed byte array.
 fn parse_pubkey(value: &[u8]) -> Result<BlsPublicKey, DbError> {
-    Ok(BlsPublicKey::from(
-        bls::PublicKey::deserialize_uncompressed(value).map_err(DbError::ParseBLSKey)?,
-    ))
+    BlsPublicKey::from_bytes(value).map_err(DbError::ParseBLSKey)
 }
 
 /// Utility function to parse a BLS signature from a byte array.
@@ -51,9 +51,22 @@ async fn main() -> eyre::Result<()> {
     info!("Starting bolt registry server...");
 
     let config = cli::Opts::parse_config()?;
-    let beacon = BeaconClient::new(config.beacon_url.clone());
+    let mut beacon = BeaconClient::new(config.beacon_url.clone());
+    beacon.set_retry_policy(config.retry);
+
+    // Registry signatures are bound to the network of the beacon node.
+    let signing = beacon.get_signing_context().await?;
+    info!(?signing, "Loaded network signing context");
+
+    if signing.chain_id != config.network.chain_id() {
+        bail!(
+            "Beacon node chain ID {} does not match the configured network {:?}",
+            signing.chain_id,
+            config.network
+        );
+    }
 
-    let (srv, actions) = RegistryApi::new(ApiConfig::default());
+    let (srv, actions) = RegistryApi::new(ApiConfig::default(), signing);
 
     if let Err(e) = srv.spawn().await {
         bail!("Failed to start API server: {}", e);
@@ -67,12 +80,12 @@ async fn main() -> eyre::Result<()> {
         info!("Using PostgreSQL database backend");
         let db = SQLDb::new(db_url).await?;
 
-        Registry::new(config, db, beacon).handle_actions(actions).await;
+        Registry::new(config, db, beacon, signing).handle_actions(actions).await;
     } else {
         info!("Using In-memory database backend");
         let db = InMemoryDb::default();
 
-        Registry::new(config, db, beacon).handle_actions(actions).await;
+        Registry::new(config, db, beacon, signing).handle_actions(actions).await;
     }
 
     warn!("Action stream closed, shutting down...");
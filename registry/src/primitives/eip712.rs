@@ -0,0 +1,102 @@
+//! EIP-712 typed messages signed by operators.
+//!
+//! Operators sign these messages with the ECDSA key of their signer address, in the EIP-712
+//! domain of the network [`SigningContext`](super::signing::SigningContext).
+
+use alloy::{
+    primitives::{keccak256, Address, PrimitiveSignature, B256},
+    sol,
+    sol_types::{Eip712Domain, SolStruct},
+};
+
+use super::BlsPublicKey;
+
+sol! {
+    /// Acceptance by an operator of the validators of a registration batch.
+    ///
+    /// `validatorsHash` is the keccak256 hash of the concatenated compressed public keys of
+    /// the validators, in the order of the batch.
+    #[derive(Debug)]
+    struct AcceptRegistration {
+        bytes32 validatorsHash;
+        uint64 gasLimit;
+        uint64 expiry;
+        uint64 nonce;
+    }
+
+    /// De-registration by an operator of validators registered to it.
+    ///
+    /// `validatorsHash` is computed as in [`AcceptRegistration`].
+    #[derive(Debug)]
+    struct DeregisterValidators {
+        bytes32 validatorsHash;
+        uint64 nonce;
+    }
+
+    /// Update of the settings of an operator.
+    #[derive(Debug)]
+    struct UpdateOperatorSettings {
+        bool requiresAcceptance;
+        uint64 nonce;
+    }
+}
+
+/// Computes the `validatorsHash` of an [`AcceptRegistration`] or [`DeregisterValidators`] message.
+pub(crate) fn validators_hash(pubkeys: &[BlsPublicKey]) -> B256 {
+    let mut bytes = Vec::with_capacity(pubkeys.len() * 48);
+    for pubkey in pubkeys {
+        bytes.extend_from_slice(&pubkey.serialize());
+    }
+
+    keccak256(bytes)
+}
+
+/// Recovers the signer of an EIP-712 message from its 65-byte signature. Returns `None` if the
+/// signature is malformed.
+pub(crate) fn recover_signer<T: SolStruct>(
+    message: &T,
+    domain: &Eip712Domain,
+    signature: &[u8],
+) -> Option<Address> {
+    let signature = PrimitiveSignature::try_from(signature).ok()?;
+    signature.recover_address_from_prehash(&message.eip712_signing_hash(domain)).ok()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloy::{
+        primitives::Bytes,
+        signers::{local::PrivateKeySigner, SignerSync},
+    };
+
+    use crate::primitives::signing::tests::{HOLESKY, MAINNET};
+
+    use super::*;
+
+    /// Signs an EIP-712 message with the given signer, and returns the 65-byte signature.
+    pub(crate) fn sign<T: SolStruct>(
+        signer: &PrivateKeySigner,
+        message: &T,
+        domain: &Eip712Domain,
+    ) -> Bytes {
+        let signature = signer.sign_hash_sync(&message.eip712_signing_hash(domain)).unwrap();
+        Bytes::copy_from_slice(&signature.as_bytes())
+    }
+
+    #[test]
+    fn test_recover_signer() {
+        let signer = PrivateKeySigner::random();
+        let message = UpdateOperatorSettings { requiresAcceptance: true, nonce: 1 };
+        let signature = sign(&signer, &message, &MAINNET.eip712_domain());
+
+        let recovered = recover_signer(&message, &MAINNET.eip712_domain(), &signature);
+        assert_eq!(recovered, Some(signer.address()));
+
+        // Signatures are bound to the chain ID
+        let recovered = recover_signer(&message, &HOLESKY.eip712_domain(), &signature);
+        assert_ne!(recovered, Some(signer.address()));
+
+        // Malformed signatures are rejected
+        assert_eq!(recover_signer(&message, &MAINNET.eip712_domain(), &signature[..64]), None);
+    }
+}
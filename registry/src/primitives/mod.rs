@@ -1,3 +1,5 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use alloy::primitives::{Address, FixedBytes};
 use derive_more::derive::{Deref, DerefMut, From};
 use ethereum_consensus::crypto::PublicKey;
@@ -6,7 +8,9 @@ use sha2::{Digest as _, Sha256};
 use utoipa::ToSchema;
 
 pub(crate) mod beacon;
+pub(crate) mod eip712;
 pub(crate) mod registry;
+pub(crate) mod signing;
 
 #[derive(
     Debug, Clone, Serialize, Deserialize, Deref, DerefMut, From, PartialEq, Eq, Hash, ToSchema,
@@ -33,26 +37,37 @@ impl BlsPublicKey {
 
 pub(crate) type BlsSignature = bls::Signature;
 
+pub(crate) type BlsAggregateSignature = bls::AggregateSignature;
+
+/// A message digest. Note that validators do not sign digests directly, but their
+/// [signing root](signing::SigningContext::signing_root).
 pub(crate) type Digest = FixedBytes<32>;
 
 pub(crate) trait DigestExt {
-    fn from_parts(operator: Address, gas_limit: u64, expiry: u64) -> Self;
+    /// Computes the digest of a registration message from its parts.
+    fn from_parts(operator: Address, gas_limit: u64, expiry: u64, nonce: u64) -> Self;
 }
 
 impl DigestExt for Digest {
-    fn from_parts(operator: Address, gas_limit: u64, expiry: u64) -> Self {
+    fn from_parts(operator: Address, gas_limit: u64, expiry: u64, nonce: u64) -> Self {
         let mut hasher = Sha256::new();
         hasher.update(operator.0);
 
         // IMPORTANT: use big-endian encoding for cross-platform compatibility
         hasher.update(gas_limit.to_be_bytes());
         hasher.update(expiry.to_be_bytes());
+        hasher.update(nonce.to_be_bytes());
 
         let arr: [u8; 32] = hasher.finalize().into();
         arr.into()
     }
 }
 
+/// Returns the current UNIX timestamp in seconds.
+pub(crate) fn unix_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("time went backwards").as_secs()
+}
+
 /// Sync state of the registry database.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub(crate) struct SyncStateUpdate {
@@ -1,12 +1,24 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+};
 
-use alloy::primitives::{Address, U256};
+use alloy::primitives::{Address, Bytes, U256};
+use bls::SignatureSet;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest as _, Sha256};
 use url::Url;
 use utoipa::ToSchema;
 
-use super::{BlsPublicKey, BlsSignature, Digest};
+use super::{
+    eip712::{
+        recover_signer, validators_hash, AcceptRegistration, DeregisterValidators,
+        UpdateOperatorSettings,
+    },
+    signing::SigningContext,
+    BlsAggregateSignature, BlsPublicKey, BlsSignature, Digest, DigestExt,
+};
 
 /// A batch registration of validators.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -22,23 +34,94 @@ pub(crate) struct RegistrationBatch {
     /// Would also allow for a more dynamic setup if needed.
     /// If set to 0, never expires
     pub(crate) expiry: u64, // UNIX timestamp value in seconds
-    /// Signatures would be: sign(digest(`operator` + `gas_limit` + `expiry`))
+    /// Sequence number of this message. Must be strictly greater than the nonce of the last
+    /// accepted (de)registration of every validator in the batch, which prevents replays.
+    pub(crate) nonce: u64,
+    /// Signatures would be: sign(signing_root(digest(`operator` + `gas_limit` + `expiry` +
+    /// `nonce`))), one per validator in the same order as `validator_pubkeys`.
+    ///
+    /// Must be empty if `aggregate_signature` is set.
+    #[serde(default)]
     #[schema(value_type = Vec<String>)]
     pub(crate) signatures: Vec<BlsSignature>,
+    /// Alternative to `signatures` for large batches: the aggregate of all validator signatures
+    /// over the same signing root. It is verified with fast aggregate verification against
+    /// all `validator_pubkeys`, so the batch is accepted or rejected as a whole.
+    ///
+    /// Registrations accepted with an aggregate signature are stored without an individual
+    /// signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub(crate) aggregate_signature: Option<BlsAggregateSignature>,
+    /// Optional EIP-712 signature of the operator signer accepting the validators, over
+    /// `AcceptRegistration(bytes32 validatorsHash,uint64 gasLimit,uint64 expiry,uint64 nonce)`,
+    /// where `validatorsHash` is the keccak256 hash of the concatenated compressed validator
+    /// public keys.
+    ///
+    /// Required if the operator has opted into [requiring
+    /// acceptance](Operator::requires_acceptance).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub(crate) operator_signature: Option<Bytes>,
 }
 
 impl RegistrationBatch {
     /// Returns the digest of the registration.
     pub(crate) fn digest(&self) -> Digest {
-        let mut hasher = Sha256::new();
-        hasher.update(self.operator.0);
+        Digest::from_parts(self.operator, self.gas_limit, self.expiry, self.nonce)
+    }
 
-        // IMPORTANT: use big-endian encoding for cross-platform compatibility
-        hasher.update(self.gas_limit.to_be_bytes());
-        hasher.update(self.expiry.to_be_bytes());
+    /// Returns the signing root of the registration on the network of the given context.
+    pub(crate) fn signing_root(&self, ctx: &SigningContext) -> Digest {
+        ctx.signing_root(self.digest())
+    }
 
-        let arr: [u8; 32] = hasher.finalize().into();
-        arr.into()
+    /// Verifies the signature of every validator in the batch against the registration
+    /// [signing root](Self::signing_root).
+    ///
+    /// Returns the public keys of the validators whose signature is missing or invalid.
+    pub(crate) fn invalid_signers(&self, ctx: &SigningContext) -> Vec<BlsPublicKey> {
+        invalid_signers(&self.validator_pubkeys, &self.signatures, self.signing_root(ctx))
+    }
+
+    /// Returns `true` if the registration has already expired at the given UNIX timestamp.
+    pub(crate) const fn is_expired(&self, now: u64) -> bool {
+        is_expired(self.expiry, now)
+    }
+
+    /// Verifies the [aggregate signature](Self::aggregate_signature) of the batch against the
+    /// registration [signing root](Self::signing_root) and all validator public keys.
+    ///
+    /// Returns `false` if the batch has no aggregate signature.
+    ///
+    /// NOTE: fast aggregate verification is only safe against rogue key attacks if every public
+    /// key has a proof of possession. This holds for active beacon chain validators, whose
+    /// deposits are signed, so callers must check that all validators are active.
+    pub(crate) fn verify_aggregate_signature(&self, ctx: &SigningContext) -> bool {
+        let Some(aggregate_signature) = &self.aggregate_signature else { return false };
+
+        let pubkeys = self.validator_pubkeys.iter().map(|pubkey| &**pubkey).collect::<Vec<_>>();
+        aggregate_signature.fast_aggregate_verify(self.signing_root(ctx), &pubkeys)
+    }
+
+    /// Returns the EIP-712 message that the operator signs to accept the validators of the batch.
+    pub(crate) fn acceptance(&self) -> AcceptRegistration {
+        AcceptRegistration {
+            validatorsHash: validators_hash(&self.validator_pubkeys),
+            gasLimit: self.gas_limit,
+            expiry: self.expiry,
+            nonce: self.nonce,
+        }
+    }
+
+    /// Verifies that the [operator signature](Self::operator_signature) was produced by the
+    /// operator signer over the [acceptance](Self::acceptance) message of the batch.
+    ///
+    /// Returns `false` if the batch has no operator signature.
+    pub(crate) fn verify_operator_signature(&self, ctx: &SigningContext) -> bool {
+        let Some(signature) = &self.operator_signature else { return false };
+
+        recover_signer(&self.acceptance(), &ctx.eip712_domain(), signature) == Some(self.operator)
     }
 
     /// Consumes the batch and returns the individual registrations.
@@ -46,17 +129,25 @@ impl RegistrationBatch {
     ///
     /// Note: if a validator index is not found in the map, the registration is skipped.
     pub(crate) fn into_items(self, index_map: HashMap<BlsPublicKey, u64>) -> Vec<Registration> {
+        let mut signatures = self.signatures.into_iter();
+
         self.validator_pubkeys
             .into_iter()
-            .zip(self.signatures)
-            .filter_map(|(validator_pubkey, signature)| {
+            .filter_map(|validator_pubkey| {
+                // NOTE: always advance the signatures iterator to keep it aligned with the pubkeys
+                let signature = signatures.next();
+
                 Some(Registration {
                     validator_index: *index_map.get(&validator_pubkey)?,
                     validator_pubkey,
                     operator: self.operator,
                     gas_limit: self.gas_limit,
                     expiry: self.expiry,
-                    signature: Some(signature),
+                    nonce: self.nonce,
+                    signature,
+                    source: DataSource::Api,
+                    priority: Registration::SIGNED_PRIORITY,
+                    needs_redelegation: false,
                 })
             })
             .collect()
@@ -75,11 +166,37 @@ pub(crate) struct Registration {
     pub(crate) operator: Address,
     /// Gas limit reserved for commitments.
     pub(crate) gas_limit: u64,
-    /// The expiry of the registration.
+    /// The expiry of the registration, as a UNIX timestamp in seconds. If set to 0, never
+    /// expires.
     pub(crate) expiry: u64,
-    /// The BLS signature of the validator on the registration.
+    /// The nonce of the registration message.
+    pub(crate) nonce: u64,
+    /// The BLS signature of the validator on the registration. Not present for registrations
+    /// that were signed with an aggregate signature, or imported from external sources.
     #[schema(value_type = Option<String>)]
     pub(crate) signature: Option<BlsSignature>,
+    /// Where the registration comes from.
+    #[serde(default)]
+    pub(crate) source: DataSource,
+    /// The priority of the source of the registration. A registration is only replaced by
+    /// external sources of equal or higher priority.
+    #[serde(default)]
+    pub(crate) priority: u16,
+    /// Whether the operator of the validator was deregistered on-chain. The validator is hidden
+    /// from discovery until it registers again, delegating to an active operator.
+    #[serde(default)]
+    pub(crate) needs_redelegation: bool,
+}
+
+impl Registration {
+    /// The priority of registrations signed by the validators, above the priority of any
+    /// external source.
+    pub(crate) const SIGNED_PRIORITY: u16 = u8::MAX as u16 + 1;
+
+    /// Returns `true` if the registration has expired at the given UNIX timestamp.
+    pub(crate) const fn is_expired(&self, now: u64) -> bool {
+        is_expired(self.expiry, now)
+    }
 }
 
 /// A batch deregistration of validators.
@@ -87,15 +204,44 @@ pub(crate) struct Registration {
 pub(crate) struct DeregistrationBatch {
     /// Validators being de-registered.
     pub(crate) validator_pubkeys: Vec<BlsPublicKey>,
-    /// Not strictly needed, but will determine signature digest.
+    /// Operator the validators are currently registered to. Must match the registered operator,
+    /// and determines the signature digest.
     #[schema(value_type = String)]
     pub(crate) operator: Address,
-    /// Signatures would be: sign(digest(operator))
+    /// Sequence number of this message. Must be strictly greater than the nonce of the last
+    /// accepted (de)registration of every validator in the batch, which prevents replays.
+    pub(crate) nonce: u64,
+    /// Signatures would be: sign(signing_root(digest(`operator` + `nonce`)))
     #[schema(value_type = Vec<String>)]
     pub(crate) signatures: Vec<BlsSignature>,
 }
 
 impl DeregistrationBatch {
+    /// Returns the digest of the deregistration.
+    pub(crate) fn digest(&self) -> Digest {
+        let mut hasher = Sha256::new();
+        hasher.update(self.operator.0);
+
+        // IMPORTANT: use big-endian encoding for cross-platform compatibility
+        hasher.update(self.nonce.to_be_bytes());
+
+        let arr: [u8; 32] = hasher.finalize().into();
+        arr.into()
+    }
+
+    /// Returns the signing root of the deregistration on the network of the given context.
+    pub(crate) fn signing_root(&self, ctx: &SigningContext) -> Digest {
+        ctx.signing_root(self.digest())
+    }
+
+    /// Verifies the signature of every validator in the batch against the deregistration
+    /// [signing root](Self::signing_root).
+    ///
+    /// Returns the public keys of the validators whose signature is missing or invalid.
+    pub(crate) fn invalid_signers(&self, ctx: &SigningContext) -> Vec<BlsPublicKey> {
+        invalid_signers(&self.validator_pubkeys, &self.signatures, self.signing_root(ctx))
+    }
+
     /// Consumes the batch and returns the individual de-registrations.
     pub(crate) fn into_items(self) -> Vec<Deregistration> {
         self.validator_pubkeys
@@ -104,12 +250,48 @@ impl DeregistrationBatch {
             .map(|(validator_pubkey, signature)| Deregistration {
                 validator_pubkey,
                 operator: self.operator,
-                signature,
+                nonce: self.nonce,
+                signature: Some(signature),
             })
             .collect()
     }
 }
 
+/// A batch deregistration of validators, initiated by their operator.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct OperatorDeregistrationBatch {
+    /// Validators being de-registered. All of them must be registered to the operator.
+    pub(crate) validator_pubkeys: Vec<BlsPublicKey>,
+    /// Operator signer address the validators are currently registered to.
+    #[schema(value_type = String)]
+    pub(crate) operator: Address,
+    /// Sequence number of this message. Must be strictly greater than the nonce of the last
+    /// accepted deregistration of the operator, which prevents replays. Independent of the
+    /// nonces of the validators.
+    pub(crate) nonce: u64,
+    /// EIP-712 signature of the operator signer over
+    /// `DeregisterValidators(bytes32 validatorsHash,uint64 nonce)`, where `validatorsHash` is the
+    /// keccak256 hash of the concatenated compressed validator public keys.
+    #[schema(value_type = String)]
+    pub(crate) signature: Bytes,
+}
+
+impl OperatorDeregistrationBatch {
+    /// Returns the EIP-712 message signed by the operator.
+    pub(crate) fn message(&self) -> DeregisterValidators {
+        DeregisterValidators {
+            validatorsHash: validators_hash(&self.validator_pubkeys),
+            nonce: self.nonce,
+        }
+    }
+
+    /// Verifies that the batch was signed by the operator signer.
+    pub(crate) fn verify_signature(&self, ctx: &SigningContext) -> bool {
+        recover_signer(&self.message(), &ctx.eip712_domain(), &self.signature) ==
+            Some(self.operator)
+    }
+}
+
 /// A single deregistration of a validator.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub(crate) struct Deregistration {
@@ -118,9 +300,12 @@ pub(crate) struct Deregistration {
     /// Operator that can sign commitments on behalf of the validator.
     #[schema(value_type = String)]
     pub(crate) operator: Address,
-    /// The BLS signature of the validator on the de-registration.
-    #[schema(value_type = String)]
-    pub(crate) signature: BlsSignature,
+    /// The nonce of the de-registration message.
+    pub(crate) nonce: u64,
+    /// The BLS signature of the validator on the de-registration. Not present for
+    /// de-registrations initiated by the operator.
+    #[schema(value_type = Option<String>)]
+    pub(crate) signature: Option<BlsSignature>,
 }
 
 /// An entry in the validator registry.
@@ -145,7 +330,1031 @@ pub(crate) struct Operator {
     pub(crate) collateral_tokens: Vec<Address>,
     #[schema(value_type = Vec<u64>)]
     pub(crate) collateral_amounts: Vec<U256>,
+    /// The restaking protocol through which the operator provides collateral, derived from the
+    /// middleware it registered with. Not set for operators imported from external sources.
+    #[serde(default)]
+    pub(crate) protocol: Option<RestakingProtocol>,
+    /// Where the operator comes from.
+    #[serde(default)]
+    pub(crate) source: DataSource,
+    /// The metadata published by the operator in the `extraData` of its on-chain registration.
+    #[serde(default)]
+    pub(crate) metadata: Option<OperatorMetadata>,
+    /// Whether registrations to this operator must carry its acceptance signature.
+    #[serde(default)]
+    pub(crate) requires_acceptance: bool,
+    /// The on-chain status of the operator. Only active operators accept new registrations.
+    #[serde(default)]
+    pub(crate) status: OperatorStatus,
+    /// The EigenLayer operator sets of the bolt AVS that the operator is a member of, which
+    /// define its slashing scope. Empty for operators that don't restake through EigenLayer.
+    #[serde(default)]
+    pub(crate) operator_sets: Vec<OperatorSet>,
+}
+
+/// Descriptive metadata of an operator, published as a JSON object in the `extraData` of its
+/// `OperatorsRegistryV1` registration:
+///
+/// ```json
+/// {
+///   "display_name": "Example Operator",
+///   "website": "https://operator.example.com",
+///   "logo_uri": "https://operator.example.com/logo.png",
+///   "contact": "ops@operator.example.com"
+/// }
+/// ```
+///
+/// All fields are optional. Unknown fields are ignored, and so are fields that are not strings,
+/// are empty or too long, contain control characters, or are not valid URLs where one is
+/// expected.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct OperatorMetadata {
+    /// The human-readable name of the operator.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) display_name: Option<String>,
+    /// The website of the operator, as an HTTP(S) URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub(crate) website: Option<Url>,
+    /// The URI of the operator logo, as an HTTP(S) or IPFS URI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub(crate) logo_uri: Option<Url>,
+    /// A contact of the operator, e.g. an email address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) contact: Option<String>,
+}
+
+impl OperatorMetadata {
+    /// The maximum length in bytes of a metadata field. Longer fields are ignored.
+    const MAX_FIELD_LENGTH: usize = 256;
+
+    /// Parses operator metadata from the `extraData` of an operator registration, ignoring
+    /// invalid fields. Returns `None` if the data is not a JSON object, or has no valid field.
+    pub(crate) fn parse(extra_data: &str) -> Option<Self> {
+        let serde_json::Value::Object(fields) = serde_json::from_str(extra_data).ok()? else {
+            return None;
+        };
+
+        let text = |key: &str| {
+            let value = fields.get(key)?.as_str()?.trim();
+            let valid = !value.is_empty() &&
+                value.len() <= Self::MAX_FIELD_LENGTH &&
+                !value.chars().any(char::is_control);
+
+            valid.then(|| value.to_owned())
+        };
+        let uri = |key: &str, schemes: &[&str]| {
+            text(key)?.parse::<Url>().ok().filter(|url| schemes.contains(&url.scheme()))
+        };
+
+        let metadata = Self {
+            display_name: text("display_name"),
+            website: uri("website", &["http", "https"]),
+            logo_uri: uri("logo_uri", &["http", "https", "ipfs"]),
+            contact: text("contact"),
+        };
+
+        (metadata != Self::default()).then_some(metadata)
+    }
+
+    /// Returns the JSON representation of the metadata, as stored in the database.
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("operator metadata serializes to JSON")
+    }
+}
+
+/// An EigenLayer operator set of the bolt AVS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct OperatorSet {
+    /// The ID of the operator set.
+    pub(crate) id: u32,
+    /// The strategies backing the operator set, i.e. the stake that can be slashed.
+    #[schema(value_type = Vec<String>)]
+    pub(crate) strategies: Vec<Address>,
+}
+
+/// The status of an operator in the operators registry contract.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OperatorStatus {
+    /// The operator is registered and accepts validator registrations.
+    #[default]
+    Active,
+    /// The operator is paused, and doesn't accept validator registrations until unpaused.
+    Paused,
+    /// The operator is deregistered.
+    Deregistered,
+}
+
+impl OperatorStatus {
+    /// Returns the string representation of the status, as stored in the database.
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Paused => "paused",
+            Self::Deregistered => "deregistered",
+        }
+    }
+
+    /// Parses a status from its string representation.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(Self::Active),
+            "paused" => Some(Self::Paused),
+            "deregistered" => Some(Self::Deregistered),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OperatorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An operator event of the operators registry contract, as applied to the registry. Applied
+/// events are kept so that they can be rolled back on execution layer reorgs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OperatorEvent {
+    /// The block number in which the event was emitted.
+    pub(crate) block_number: u64,
+    /// The index of the event log in the block.
+    pub(crate) log_index: u64,
+    /// The operator signer address.
+    pub(crate) signer: Address,
+    /// The status of the operator after the event.
+    pub(crate) status: OperatorStatus,
+    /// The RPC endpoint of the operator. Only set on registrations.
+    pub(crate) rpc_endpoint: Option<Url>,
+    /// The restaking protocol of the middleware the operator registered with. Only set on
+    /// registrations, and only if the middleware is known.
+    pub(crate) protocol: Option<RestakingProtocol>,
+    /// The metadata of the operator. Only set on registrations with valid metadata.
+    pub(crate) metadata: Option<OperatorMetadata>,
+}
+
+/// A restaking protocol supported by the bolt middlewares.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RestakingProtocol {
+    /// Symbiotic, whose collateral is held in vaults.
+    Symbiotic,
+    /// EigenLayer, whose collateral is held in strategies.
+    EigenLayer,
+}
+
+impl RestakingProtocol {
+    /// Returns the string representation of the protocol, as stored in the database.
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Symbiotic => "symbiotic",
+            Self::EigenLayer => "eigenlayer",
+        }
+    }
+
+    /// Parses a protocol from its string representation.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "symbiotic" => Some(Self::Symbiotic),
+            "eigenlayer" => Some(Self::EigenLayer),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RestakingProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The origin of a validator registration or an operator in the registry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DataSource {
+    /// Submitted to the registry API, signed by the validators.
+    Api,
+    /// Synced from the bolt contracts.
+    Onchain,
+    /// Imported from the Lido Keys API.
+    Lido,
+    /// Imported from an operator feed.
+    Feed,
+    /// Stored before the origin of registry data was tracked.
+    #[default]
+    #[serde(rename = "none")]
+    Unknown,
+}
+
+impl DataSource {
+    /// Returns the string representation of the source, as stored in the database.
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Api => "api",
+            Self::Onchain => "onchain",
+            Self::Lido => "lido",
+            Self::Feed => "feed",
+            Self::Unknown => "none",
+        }
+    }
+
+    /// Parses a source from its string representation.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "api" => Some(Self::Api),
+            "onchain" => Some(Self::Onchain),
+            "lido" => Some(Self::Lido),
+            "feed" => Some(Self::Feed),
+            "none" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Filters the operators and validators returned by discovery. Validators are filtered by the
+/// protocol of their operator, and by the source of their registration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub(crate) struct DiscoveryFilter {
+    /// Only return entries of operators restaking through this protocol.
+    pub(crate) protocol: Option<RestakingProtocol>,
+    /// Only return entries from this source.
+    pub(crate) source: Option<DataSource>,
+}
+
+impl DiscoveryFilter {
+    /// Returns `true` if an entry with the given operator protocol and source passes the filter.
+    pub(crate) fn matches(&self, protocol: Option<RestakingProtocol>, source: DataSource) -> bool {
+        self.protocol.is_none_or(|p| protocol == Some(p)) && self.source.is_none_or(|s| source == s)
+    }
+}
+
+/// A collateral whitelisted in a restaking middleware: a vault for Symbiotic, or a strategy for
+/// EigenLayer. Only stake in whitelisted, unpaused collateral counts towards operator collateral.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct WhitelistedCollateral {
+    /// The restaking protocol of the collateral.
+    pub(crate) protocol: RestakingProtocol,
+    /// The address of the vault or strategy.
+    #[schema(value_type = String)]
+    pub(crate) address: Address,
+    /// Whether the collateral is paused in the middleware.
+    pub(crate) paused: bool,
+}
+
+/// A change to the collateral whitelist of a restaking middleware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WhitelistAction {
+    /// The collateral was whitelisted.
+    Whitelisted,
+    /// The collateral was paused.
+    Paused,
+    /// The collateral was unpaused.
+    Unpaused,
+    /// The collateral was removed from the whitelist.
+    Removed,
+}
+
+impl WhitelistAction {
+    /// Returns the string representation of the action, as stored in the database.
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Whitelisted => "whitelisted",
+            Self::Paused => "paused",
+            Self::Unpaused => "unpaused",
+            Self::Removed => "removed",
+        }
+    }
+
+    /// Parses an action from its string representation.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "whitelisted" => Some(Self::Whitelisted),
+            "paused" => Some(Self::Paused),
+            "unpaused" => Some(Self::Unpaused),
+            "removed" => Some(Self::Removed),
+            _ => None,
+        }
+    }
+}
+
+/// A whitelist event of a restaking middleware contract, as applied to the registry. The current
+/// whitelist is derived from the last event of every collateral, so that events can be rolled
+/// back on execution layer reorgs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WhitelistEvent {
+    /// The block number in which the event was emitted.
+    pub(crate) block_number: u64,
+    /// The index of the event log in the block.
+    pub(crate) log_index: u64,
+    /// The restaking protocol of the middleware that emitted the event.
+    pub(crate) protocol: RestakingProtocol,
+    /// The address of the vault or strategy.
+    pub(crate) address: Address,
+    /// The whitelist change.
+    pub(crate) action: WhitelistAction,
+}
+
+/// Derives the current collateral whitelist from whitelist events, in the order they were
+/// emitted. Collateral is ordered by protocol, then address.
+pub(crate) fn whitelist_from_events<'a>(
+    events: impl IntoIterator<Item = &'a WhitelistEvent>,
+) -> Vec<WhitelistedCollateral> {
+    let mut whitelist = BTreeMap::new();
+    for event in events {
+        let key = (event.protocol, event.address);
+        match event.action {
+            WhitelistAction::Whitelisted | WhitelistAction::Unpaused => {
+                whitelist.insert(key, false);
+            }
+            WhitelistAction::Paused => {
+                whitelist.insert(key, true);
+            }
+            WhitelistAction::Removed => {
+                whitelist.remove(&key);
+            }
+        }
+    }
+
+    whitelist
+        .into_iter()
+        .map(|((protocol, address), paused)| WhitelistedCollateral { protocol, address, paused })
+        .collect()
+}
+
+/// A change to the EigenLayer operator sets of the bolt AVS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OperatorSetChange {
+    /// The operator set was created.
+    Created,
+    /// The operator was added to the operator set.
+    OperatorAdded(Address),
+    /// The operator was removed from the operator set.
+    OperatorRemoved(Address),
+    /// The strategy was added to the operator set.
+    StrategyAdded(Address),
+    /// The strategy was removed from the operator set.
+    StrategyRemoved(Address),
+}
+
+impl OperatorSetChange {
+    /// Returns the string representation of the kind of change, as stored in the database.
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::OperatorAdded(_) => "operator_added",
+            Self::OperatorRemoved(_) => "operator_removed",
+            Self::StrategyAdded(_) => "strategy_added",
+            Self::StrategyRemoved(_) => "strategy_removed",
+        }
+    }
+
+    /// Returns the operator or strategy address of the change, if any.
+    pub(crate) const fn address(&self) -> Option<Address> {
+        match self {
+            Self::Created => None,
+            Self::OperatorAdded(address) |
+            Self::OperatorRemoved(address) |
+            Self::StrategyAdded(address) |
+            Self::StrategyRemoved(address) => Some(*address),
+        }
+    }
+}
+
+/// An operator set event of the EigenLayer allocation manager concerning the bolt AVS, as
+/// applied to the registry. Operator set membership is derived from these events, so that they
+/// can be rolled back on execution layer reorgs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OperatorSetEvent {
+    /// The block number in which the event was emitted.
+    pub(crate) block_number: u64,
+    /// The index of the event log in the block.
+    pub(crate) log_index: u64,
+    /// The ID of the operator set.
+    pub(crate) operator_set_id: u32,
+    /// The operator set change.
+    pub(crate) change: OperatorSetChange,
+}
+
+/// A stream of contract events ingested by the syncer. Streams introduced after a database
+/// started ingesting contract events are backfilled on their own, up to the last ingested block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ContractEventStream {
+    /// The operator events of the operators registry.
+    Operators,
+    /// The operator metadata of the operators registry registrations.
+    OperatorMetadata,
+    /// The whitelist events of the restaking middlewares.
+    Whitelist,
+    /// The operator set events of the EigenLayer allocation manager.
+    OperatorSets,
+}
+
+impl ContractEventStream {
+    /// Returns the string representation of the stream, as stored in the database.
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Operators => "operators",
+            Self::OperatorMetadata => "operator_metadata",
+            Self::Whitelist => "whitelist",
+            Self::OperatorSets => "operator_sets",
+        }
+    }
+
+    /// Parses a stream from its string representation.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "operators" => Some(Self::Operators),
+            "operator_metadata" => Some(Self::OperatorMetadata),
+            "whitelist" => Some(Self::Whitelist),
+            "operator_sets" => Some(Self::OperatorSets),
+            _ => None,
+        }
+    }
+}
+
+/// The current operators and strategies of every operator set, materialized from operator set
+/// events. Operator sets and their strategies are ordered by ID and address respectively.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OperatorSets {
+    sets: BTreeMap<u32, OperatorSetMembers>,
+}
+
+/// The current operators and strategies of an operator set.
+#[derive(Debug, Clone, Default)]
+struct OperatorSetMembers {
+    operators: BTreeSet<Address>,
+    strategies: BTreeSet<Address>,
+}
+
+impl OperatorSets {
+    /// Applies an operator set event. Events must be applied in the order they were emitted.
+    pub(crate) fn apply(&mut self, event: &OperatorSetEvent) {
+        let set = self.sets.entry(event.operator_set_id).or_default();
+        match event.change {
+            OperatorSetChange::Created => {}
+            OperatorSetChange::OperatorAdded(operator) => {
+                set.operators.insert(operator);
+            }
+            OperatorSetChange::OperatorRemoved(operator) => {
+                set.operators.remove(&operator);
+            }
+            OperatorSetChange::StrategyAdded(strategy) => {
+                set.strategies.insert(strategy);
+            }
+            OperatorSetChange::StrategyRemoved(strategy) => {
+                set.strategies.remove(&strategy);
+            }
+        }
+    }
+
+    /// Rebuilds the given operator sets from their remaining events, e.g. after rolling back
+    /// events on reorgs.
+    pub(crate) fn rebuild<'a>(
+        &mut self,
+        ids: &HashSet<u32>,
+        events: impl IntoIterator<Item = &'a OperatorSetEvent>,
+    ) {
+        self.sets.retain(|id, _| !ids.contains(id));
+        for event in events.into_iter().filter(|e| ids.contains(&e.operator_set_id)) {
+            self.apply(event);
+        }
+    }
+
+    /// Returns the operator sets the given operator is a member of.
+    pub(crate) fn memberships(&self, operator: Address) -> Vec<OperatorSet> {
+        self.sets
+            .iter()
+            .filter(|(_, set)| set.operators.contains(&operator))
+            .map(|(id, set)| OperatorSet {
+                id: *id,
+                strategies: set.strategies.iter().copied().collect(),
+            })
+            .collect()
+    }
+}
+
+/// An RPC endpoint of an operator, and from when the registry served it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct RpcEndpointUpdate {
+    /// The signer address of the operator.
+    #[schema(value_type = String)]
+    pub(crate) operator: Address,
+    /// The RPC endpoint of the operator.
+    #[schema(value_type = String)]
+    pub(crate) rpc_endpoint: Url,
+    /// The execution block number at which the endpoint was observed.
+    pub(crate) block_number: u64,
+    /// The beacon chain epoch from which the endpoint is effective.
+    pub(crate) epoch: u64,
+}
+
+/// Conflicting claims of external sources on the operator of a validator. The claim of the
+/// source with the highest priority is registered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct SourceConflict {
+    /// The validator claimed by the sources.
+    pub(crate) validator_pubkey: BlsPublicKey,
+    /// The operator of the registered claim.
+    #[schema(value_type = String)]
+    pub(crate) operator: Address,
+    /// The claims of all sources that returned the validator, by descending priority.
+    pub(crate) claims: Vec<SourceClaim>,
+}
+
+/// The operator an external source claims for a validator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct SourceClaim {
+    /// The name of the source.
+    pub(crate) source: String,
+    /// The priority of the source.
+    pub(crate) priority: u8,
+    /// The claimed operator.
+    #[schema(value_type = String)]
+    pub(crate) operator: Address,
+}
+
+/// A snapshot of the stake of an operator at the start of an operators registry epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct StakeSnapshot {
+    /// The signer address of the operator.
+    #[schema(value_type = String)]
+    pub(crate) operator: Address,
+    /// The operators registry epoch of the snapshot.
+    pub(crate) epoch: u64,
+    /// The UNIX timestamp in seconds of the epoch start, at which the Symbiotic stake was
+    /// measured. The EigenLayer middleware has no historical stake view, so the EigenLayer stake
+    /// is the one at the time the snapshot was taken, shortly after the epoch start.
+    pub(crate) timestamp: u64,
+    /// The collateral tokens staked to the operator, across both restaking protocols.
+    #[schema(value_type = Vec<String>)]
+    pub(crate) collateral_tokens: Vec<Address>,
+    /// The staked amounts of every collateral token.
+    #[schema(value_type = Vec<String>)]
+    pub(crate) collateral_amounts: Vec<U256>,
+}
+
+/// The settings of an operator, managed by the operator through signed
+/// [updates](OperatorSettingsUpdate).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct OperatorSettings {
+    /// Whether registrations to this operator must carry its acceptance signature.
+    pub(crate) requires_acceptance: bool,
+    /// The nonce of the last accepted settings update.
+    pub(crate) nonce: u64,
+}
+
+/// A settings update signed by an operator.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct OperatorSettingsUpdate {
+    /// The operator signer address.
+    #[schema(value_type = String)]
+    pub(crate) operator: Address,
+    /// Whether registrations to this operator must carry its acceptance signature.
+    pub(crate) requires_acceptance: bool,
+    /// Sequence number of this message. Must be strictly greater than the nonce of the last
+    /// accepted settings update of the operator, which prevents replays.
+    pub(crate) nonce: u64,
+    /// EIP-712 signature of the operator signer over
+    /// `UpdateOperatorSettings(bool requiresAcceptance,uint64 nonce)`.
+    #[schema(value_type = String)]
+    pub(crate) signature: Bytes,
+}
+
+impl OperatorSettingsUpdate {
+    /// Verifies that the update was signed by the operator signer.
+    pub(crate) fn verify_signature(&self, ctx: &SigningContext) -> bool {
+        let message = UpdateOperatorSettings {
+            requiresAcceptance: self.requires_acceptance,
+            nonce: self.nonce,
+        };
+
+        recover_signer(&message, &ctx.eip712_domain(), &self.signature) == Some(self.operator)
+    }
+
+    /// Returns the operator settings after applying this update.
+    pub(crate) const fn settings(&self) -> OperatorSettings {
+        OperatorSettings { requires_acceptance: self.requires_acceptance, nonce: self.nonce }
+    }
 }
 
 /// A lookahead representation.
 pub(crate) type Lookahead = HashMap<u64, RegistryEntry>;
+
+/// Returns `true` if the given expiry timestamp is reached at `now`. An expiry of 0 never expires.
+const fn is_expired(expiry: u64, now: u64) -> bool {
+    expiry != 0 && expiry <= now
+}
+
+/// Returns the public keys whose signature over the given signing root is invalid or missing,
+/// in the same order as `pubkeys`. Signatures are matched to public keys by position.
+///
+/// All signatures are checked at once with randomized batch verification, which blst spreads
+/// over its own thread pool. Only if the batch fails as a whole, signatures are re-checked one by
+/// one to report the exact invalid signers.
+fn invalid_signers(
+    pubkeys: &[BlsPublicKey],
+    signatures: &[BlsSignature],
+    signing_root: Digest,
+) -> Vec<BlsPublicKey> {
+    let signed = pubkeys.len().min(signatures.len());
+    let (pubkeys, unsigned) = pubkeys.split_at(signed);
+    let signatures = &signatures[..signed];
+
+    let signature_sets = pubkeys
+        .iter()
+        .zip(signatures)
+        .map(|(pubkey, signature)| {
+            SignatureSet::single_pubkey(signature, Cow::Borrowed(&**pubkey), signing_root)
+        })
+        .collect::<Vec<_>>();
+
+    let mut invalid = if bls::verify_signature_sets(signature_sets.iter()) {
+        Vec::new()
+    } else {
+        pubkeys
+            .iter()
+            .zip(signatures)
+            .filter(|(pubkey, signature)| !signature.verify(pubkey, signing_root))
+            .map(|(pubkey, _)| pubkey.clone())
+            .collect()
+    };
+
+    // Validators without a signature are always invalid
+    invalid.extend_from_slice(unsigned);
+    invalid
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::local::PrivateKeySigner;
+
+    use crate::primitives::{
+        eip712::tests::sign,
+        signing::tests::{HOLESKY, MAINNET},
+    };
+
+    use super::*;
+
+    /// Creates a registration batch signed by the given keypairs on mainnet.
+    fn signed_batch(keypairs: &[bls::Keypair]) -> RegistrationBatch {
+        let mut batch = RegistrationBatch {
+            validator_pubkeys: keypairs
+                .iter()
+                .map(|kp| BlsPublicKey::from(kp.pk.clone()))
+                .collect(),
+            operator: Address::random(),
+            gas_limit: 10_000,
+            expiry: 0,
+            nonce: 1,
+            signatures: vec![],
+            aggregate_signature: None,
+            operator_signature: None,
+        };
+
+        let signing_root = batch.signing_root(&MAINNET);
+        batch.signatures = keypairs.iter().map(|kp| kp.sk.sign(signing_root)).collect();
+        batch
+    }
+
+    /// Replaces the individual signatures of the batch with their aggregate.
+    fn aggregate(batch: &mut RegistrationBatch) {
+        let mut aggregate_signature = BlsAggregateSignature::infinity();
+        for signature in batch.signatures.drain(..) {
+            aggregate_signature.add_assign(&signature);
+        }
+
+        batch.aggregate_signature = Some(aggregate_signature);
+    }
+
+    #[test]
+    fn test_registration_signatures_valid() {
+        let keypairs = (0..4).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let batch = signed_batch(&keypairs);
+
+        assert!(batch.invalid_signers(&MAINNET).is_empty());
+    }
+
+    #[test]
+    fn test_registration_signatures_reported_per_validator() {
+        let keypairs = (0..4).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+
+        // Sign the wrong message with the second validator
+        batch.signatures[1] = keypairs[1].sk.sign(Digest::ZERO);
+        // Sign the right message with the wrong key for the third validator
+        batch.signatures[2] = bls::Keypair::random().sk.sign(batch.signing_root(&MAINNET));
+
+        let invalid = batch.invalid_signers(&MAINNET);
+        assert_eq!(
+            invalid,
+            vec![batch.validator_pubkeys[1].clone(), batch.validator_pubkeys[2].clone()]
+        );
+    }
+
+    #[test]
+    fn test_registration_signatures_large_batch() {
+        let keypairs = (0..200).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+        assert!(batch.invalid_signers(&MAINNET).is_empty());
+
+        // Invalid signatures spread over the batch are all reported, in order
+        let bad = [0, 57, 58, 199];
+        for i in bad {
+            batch.signatures[i] = keypairs[i].sk.sign(Digest::ZERO);
+        }
+
+        let expected = bad.iter().map(|i| batch.validator_pubkeys[*i].clone()).collect::<Vec<_>>();
+        assert_eq!(batch.invalid_signers(&MAINNET), expected);
+    }
+
+    #[test]
+    fn test_registration_signatures_tampered_payload() {
+        let keypairs = (0..2).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+
+        // Changing the operator after signing invalidates all signatures
+        batch.operator = Address::random();
+
+        assert_eq!(batch.invalid_signers(&MAINNET), batch.validator_pubkeys);
+    }
+
+    #[test]
+    fn test_registration_nonce_is_signed() {
+        let keypairs = (0..2).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+
+        // Bumping the nonce of a signed message invalidates all signatures
+        batch.nonce += 1;
+
+        assert_eq!(batch.invalid_signers(&MAINNET), batch.validator_pubkeys);
+    }
+
+    #[test]
+    fn test_registration_signatures_are_network_bound() {
+        let keypairs = (0..2).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+
+        // Mainnet signatures are not valid on Holesky
+        assert_eq!(batch.invalid_signers(&HOLESKY), batch.validator_pubkeys);
+
+        // Signatures over the bare digest are not valid on any network
+        let digest = batch.digest();
+        batch.signatures = keypairs.iter().map(|kp| kp.sk.sign(digest)).collect();
+        assert_eq!(batch.invalid_signers(&MAINNET), batch.validator_pubkeys);
+    }
+
+    #[test]
+    fn test_registration_aggregate_signature() {
+        let keypairs = (0..16).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+        assert!(!batch.verify_aggregate_signature(&MAINNET));
+
+        aggregate(&mut batch);
+        assert!(batch.signatures.is_empty());
+        assert!(batch.verify_aggregate_signature(&MAINNET));
+        assert!(!batch.verify_aggregate_signature(&HOLESKY));
+
+        // The aggregate must cover every validator in the batch
+        batch.validator_pubkeys.push(BlsPublicKey::random());
+        assert!(!batch.verify_aggregate_signature(&MAINNET));
+        batch.validator_pubkeys.pop();
+
+        // Registrations signed with an aggregate are stored without individual signatures
+        let index_map =
+            batch.validator_pubkeys.iter().enumerate().map(|(i, pk)| (pk.clone(), i as u64));
+        let registrations = batch.into_items(index_map.collect());
+        assert_eq!(registrations.len(), keypairs.len());
+        assert!(registrations.iter().all(|r| r.signature.is_none()));
+    }
+
+    #[test]
+    fn test_registration_aggregate_signature_wrong_signer() {
+        let keypairs = (0..4).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+
+        // Replace one of the signatures with a signature from a key outside of the batch
+        batch.signatures[3] = bls::Keypair::random().sk.sign(batch.signing_root(&MAINNET));
+        aggregate(&mut batch);
+
+        assert!(!batch.verify_aggregate_signature(&MAINNET));
+    }
+
+    #[test]
+    fn test_registration_expiry() {
+        let mut batch = signed_batch(&[]);
+        assert!(!batch.is_expired(u64::MAX));
+
+        batch.expiry = 1_700_000_000;
+        assert!(!batch.is_expired(batch.expiry - 1));
+        assert!(batch.is_expired(batch.expiry));
+        assert!(batch.is_expired(batch.expiry + 1));
+    }
+
+    #[test]
+    fn test_registration_operator_signature() {
+        let operator = PrivateKeySigner::random();
+        let keypairs = (0..2).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+        batch.operator = operator.address();
+        assert!(!batch.verify_operator_signature(&MAINNET));
+
+        batch.operator_signature =
+            Some(sign(&operator, &batch.acceptance(), &MAINNET.eip712_domain()));
+        assert!(batch.verify_operator_signature(&MAINNET));
+        assert!(!batch.verify_operator_signature(&HOLESKY));
+
+        // The acceptance covers the validators of the batch
+        batch.validator_pubkeys.swap(0, 1);
+        assert!(!batch.verify_operator_signature(&MAINNET));
+        batch.validator_pubkeys.swap(0, 1);
+
+        // Only the operator itself can accept validators
+        let other = PrivateKeySigner::random();
+        batch.operator_signature =
+            Some(sign(&other, &batch.acceptance(), &MAINNET.eip712_domain()));
+        assert!(!batch.verify_operator_signature(&MAINNET));
+    }
+
+    #[test]
+    fn test_operator_settings_update_signature() {
+        let operator = PrivateKeySigner::random();
+        let mut update = OperatorSettingsUpdate {
+            operator: operator.address(),
+            requires_acceptance: true,
+            nonce: 1,
+            signature: Bytes::new(),
+        };
+        assert!(!update.verify_signature(&MAINNET));
+
+        let message = UpdateOperatorSettings { requiresAcceptance: true, nonce: 1 };
+        update.signature = sign(&operator, &message, &MAINNET.eip712_domain());
+        assert!(update.verify_signature(&MAINNET));
+
+        // Flipping the setting invalidates the signature
+        update.requires_acceptance = false;
+        assert!(!update.verify_signature(&MAINNET));
+    }
+
+    #[test]
+    fn test_operator_status_roundtrip() {
+        for status in [OperatorStatus::Active, OperatorStatus::Paused, OperatorStatus::Deregistered]
+        {
+            assert_eq!(OperatorStatus::parse(status.as_str()), Some(status));
+            assert_eq!(serde_json::to_string(&status).unwrap(), format!("\"{status}\""));
+        }
+
+        assert_eq!(OperatorStatus::parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_operator_deregistration_signature() {
+        let operator = PrivateKeySigner::random();
+        let mut batch = OperatorDeregistrationBatch {
+            validator_pubkeys: vec![BlsPublicKey::random(), BlsPublicKey::random()],
+            operator: operator.address(),
+            nonce: 2,
+            signature: Bytes::new(),
+        };
+        assert!(!batch.verify_signature(&MAINNET));
+
+        batch.signature = sign(&operator, &batch.message(), &MAINNET.eip712_domain());
+        assert!(batch.verify_signature(&MAINNET));
+
+        // The signature covers the validators and the nonce
+        batch.validator_pubkeys.pop();
+        assert!(!batch.verify_signature(&MAINNET));
+    }
+
+    #[test]
+    fn test_deregistration_signatures() {
+        let keypairs = (0..3).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = DeregistrationBatch {
+            validator_pubkeys: keypairs
+                .iter()
+                .map(|kp| BlsPublicKey::from(kp.pk.clone()))
+                .collect(),
+            operator: Address::random(),
+            nonce: 1,
+            signatures: vec![],
+        };
+
+        let signing_root = batch.signing_root(&MAINNET);
+        batch.signatures = keypairs.iter().map(|kp| kp.sk.sign(signing_root)).collect();
+        assert!(batch.invalid_signers(&MAINNET).is_empty());
+
+        // A registration signature must not be valid for a deregistration
+        let registration = RegistrationBatch {
+            validator_pubkeys: vec![],
+            operator: batch.operator,
+            gas_limit: 0,
+            expiry: 0,
+            nonce: batch.nonce,
+            signatures: vec![],
+            aggregate_signature: None,
+            operator_signature: None,
+        };
+        batch.signatures[0] = keypairs[0].sk.sign(registration.signing_root(&MAINNET));
+
+        assert_eq!(batch.invalid_signers(&MAINNET), vec![batch.validator_pubkeys[0].clone()]);
+    }
+
+    #[test]
+    fn test_registration_signatures_missing() {
+        let keypairs = (0..3).map(|_| bls::Keypair::random()).collect::<Vec<_>>();
+        let mut batch = signed_batch(&keypairs);
+        batch.signatures.pop();
+
+        assert_eq!(batch.invalid_signers(&MAINNET), vec![batch.validator_pubkeys[2].clone()]);
+    }
+
+    #[test]
+    fn test_operator_sets() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let (steth, reth) = (Address::with_last_byte(3), Address::with_last_byte(4));
+
+        let events = [
+            (0, OperatorSetChange::Created),
+            (0, OperatorSetChange::StrategyAdded(steth)),
+            (0, OperatorSetChange::StrategyAdded(reth)),
+            (1, OperatorSetChange::Created),
+            (1, OperatorSetChange::StrategyAdded(reth)),
+            (0, OperatorSetChange::OperatorAdded(a)),
+            (1, OperatorSetChange::OperatorAdded(a)),
+            (1, OperatorSetChange::OperatorAdded(b)),
+            (0, OperatorSetChange::StrategyRemoved(reth)),
+            (1, OperatorSetChange::OperatorRemoved(a)),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (operator_set_id, change))| OperatorSetEvent {
+            block_number: 1,
+            log_index: i as u64,
+            operator_set_id,
+            change,
+        })
+        .collect::<Vec<_>>();
+
+        let mut sets = OperatorSets::default();
+        events.iter().for_each(|event| sets.apply(event));
+        assert_eq!(sets.memberships(a), vec![OperatorSet { id: 0, strategies: vec![steth] }]);
+        assert_eq!(sets.memberships(b), vec![OperatorSet { id: 1, strategies: vec![reth] }]);
+
+        // Rebuilding a set from its remaining events restores its operators and strategies
+        sets.rebuild(&HashSet::from([0, 1]), &events[..8]);
+        assert_eq!(
+            sets.memberships(a),
+            vec![
+                OperatorSet { id: 0, strategies: vec![steth, reth] },
+                OperatorSet { id: 1, strategies: vec![reth] },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_operator_metadata_parse() {
+        let extra_data = r#"{
+            "display_name": " Example Operator ",
+            "website": "https://operator.example.com",
+            "logo_uri": "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+            "contact": "ops@operator.example.com",
+            "unknown": 1
+        }"#;
+
+        let metadata = OperatorMetadata::parse(extra_data).unwrap();
+        assert_eq!(metadata.display_name.as_deref(), Some("Example Operator"));
+        assert_eq!(metadata.website.unwrap().as_str(), "https://operator.example.com/");
+        assert_eq!(metadata.logo_uri.unwrap().scheme(), "ipfs");
+        assert_eq!(metadata.contact.as_deref(), Some("ops@operator.example.com"));
+
+        // Invalid fields are dropped, valid ones are kept
+        let extra_data = format!(
+            r#"{{"display_name": "{}", "website": "javascript:alert(1)", "logo_uri": 42, "contact": "a\u0000b", "unknown": "x"}}"#,
+            "x".repeat(257)
+        );
+        assert_eq!(OperatorMetadata::parse(&extra_data), None);
+
+        let extra_data = r#"{"display_name": "Operator", "website": "not a url"}"#;
+        let metadata = OperatorMetadata::parse(extra_data).unwrap();
+        assert_eq!(metadata.display_name.as_deref(), Some("Operator"));
+        assert_eq!(metadata.website, None);
+
+        // Malformed data is ignored
+        for extra_data in ["", "operator", "[]", "\"\"", "{\"display_name\": "] {
+            assert_eq!(OperatorMetadata::parse(extra_data), None);
+        }
+
+        // The stored representation round-trips
+        let metadata = OperatorMetadata::parse(r#"{"contact": "@operator"}"#).unwrap();
+        assert_eq!(OperatorMetadata::parse(&metadata.to_json()), Some(metadata));
+    }
+}
@@ -0,0 +1,156 @@
+//! Consensus-spec style signing roots for registry messages.
+//!
+//! Registry messages are not signed directly. Instead, validators sign the signing root of the
+//! message digest, which mixes in a bolt-specific domain. The domain is derived from the network
+//! fork version and genesis validators root, which makes signatures network-bound.
+//!
+//! Operators sign with ECDSA instead, using EIP-712 typed data in a domain bound to the chain ID.
+//!
+//! Reference: <https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/beacon-chain.md#compute_signing_root>
+
+use alloy::{
+    primitives::{FixedBytes, B256},
+    sol_types::{eip712_domain, Eip712Domain},
+};
+use sha2::{Digest as _, Sha256};
+
+use super::Digest;
+
+/// A 4-byte domain type.
+pub(crate) type DomainType = FixedBytes<4>;
+
+/// A 4-byte fork version.
+pub(crate) type Version = FixedBytes<4>;
+
+/// The domain type of bolt registry messages (ASCII "bolt").
+pub(crate) const DOMAIN_BOLT_REGISTRY: DomainType = FixedBytes([0x62, 0x6f, 0x6c, 0x74]);
+
+/// The name of the EIP-712 domain of operator signatures.
+pub(crate) const EIP712_DOMAIN_NAME: &str = "BoltRegistry";
+
+/// The version of the EIP-712 domain of operator signatures.
+pub(crate) const EIP712_DOMAIN_VERSION: &str = "1";
+
+/// The network parameters that registry signatures are bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SigningContext {
+    /// The genesis fork version of the network.
+    pub(crate) fork_version: Version,
+    /// The genesis validators root of the network.
+    pub(crate) genesis_validators_root: B256,
+    /// The execution layer chain ID of the network, used in the EIP-712 domain of operator
+    /// signatures.
+    pub(crate) chain_id: u64,
+}
+
+impl SigningContext {
+    /// Creates a new signing context from the network parameters.
+    pub(crate) const fn new(
+        fork_version: Version,
+        genesis_validators_root: B256,
+        chain_id: u64,
+    ) -> Self {
+        Self { fork_version, genesis_validators_root, chain_id }
+    }
+
+    /// Returns the bolt registry domain for this network.
+    pub(crate) fn domain(&self) -> B256 {
+        compute_domain(DOMAIN_BOLT_REGISTRY, self.fork_version, self.genesis_validators_root)
+    }
+
+    /// Returns the signing root of the given message digest for this network.
+    pub(crate) fn signing_root(&self, digest: Digest) -> Digest {
+        compute_signing_root(digest, self.domain())
+    }
+
+    /// Returns the EIP-712 domain of operator signatures for this network.
+    pub(crate) fn eip712_domain(&self) -> Eip712Domain {
+        eip712_domain! {
+            name: EIP712_DOMAIN_NAME,
+            version: EIP712_DOMAIN_VERSION,
+            chain_id: self.chain_id,
+        }
+    }
+}
+
+/// Computes the domain for the given domain type, fork version and genesis validators root.
+pub(crate) fn compute_domain(
+    domain_type: DomainType,
+    fork_version: Version,
+    genesis_validators_root: B256,
+) -> B256 {
+    let fork_data_root = compute_fork_data_root(fork_version, genesis_validators_root);
+
+    let mut domain = B256::ZERO;
+    domain[..4].copy_from_slice(domain_type.as_slice());
+    domain[4..].copy_from_slice(&fork_data_root[..28]);
+    domain
+}
+
+/// Computes the SSZ hash tree root of the `ForkData` container.
+fn compute_fork_data_root(fork_version: Version, genesis_validators_root: B256) -> B256 {
+    // `Version` is a 4-byte vector, right-padded to a 32-byte chunk
+    let mut version_chunk = [0u8; 32];
+    version_chunk[..4].copy_from_slice(fork_version.as_slice());
+
+    sha256_pair(&version_chunk, genesis_validators_root.as_slice())
+}
+
+/// Computes the SSZ hash tree root of the `SigningData` container.
+fn compute_signing_root(object_root: Digest, domain: B256) -> Digest {
+    sha256_pair(object_root.as_slice(), domain.as_slice())
+}
+
+/// Hashes the concatenation of two 32-byte chunks.
+fn sha256_pair(left: &[u8], right: &[u8]) -> B256 {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+
+    let arr: [u8; 32] = hasher.finalize().into();
+    arr.into()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloy::primitives::{b256, fixed_bytes};
+
+    use super::*;
+
+    /// Mainnet network parameters.
+    pub(crate) const MAINNET: SigningContext = SigningContext::new(
+        fixed_bytes!("00000000"),
+        b256!("4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"),
+        1,
+    );
+
+    /// Holesky network parameters.
+    pub(crate) const HOLESKY: SigningContext = SigningContext::new(
+        fixed_bytes!("01017000"),
+        b256!("9143aa7c615a7f7115e2b6aac319c03529df8242ae705fba9df39b79c59fa8b1"),
+        17000,
+    );
+
+    #[test]
+    fn test_compute_domain_builder_vector() {
+        // The well-known mainnet builder domain (`DOMAIN_APPLICATION_BUILDER` with the genesis
+        // fork version and an empty genesis validators root).
+        let domain = compute_domain(fixed_bytes!("00000001"), fixed_bytes!("00000000"), B256::ZERO);
+
+        assert_eq!(
+            domain,
+            b256!("00000001f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a9")
+        );
+    }
+
+    #[test]
+    fn test_signing_roots_are_network_bound() {
+        let digest = B256::repeat_byte(0x42);
+
+        assert_eq!(&MAINNET.domain()[..4], DOMAIN_BOLT_REGISTRY.as_slice());
+        assert_ne!(MAINNET.domain(), HOLESKY.domain());
+        assert_ne!(MAINNET.signing_root(digest), HOLESKY.signing_root(digest));
+        assert_ne!(MAINNET.signing_root(digest), digest);
+        assert_ne!(MAINNET.eip712_domain().separator(), HOLESKY.eip712_domain().separator());
+    }
+}
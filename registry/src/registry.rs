@@ -1,32 +1,43 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use alloy::primitives::Address;
+use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
-use tracing::info;
+use tracing::{error, info};
 
 use crate::{
     api::spec::RegistryError,
-    cli::Config,
+    chainio::{OperatorsRegistryClient, RestakingMiddlewaresClient},
+    cli::{Config, SourceKind},
     client::BeaconClient,
     db::RegistryDb,
     primitives::{
         registry::{
-            DeregistrationBatch, Lookahead, Operator, Registration, RegistrationBatch,
-            RegistryEntry,
+            DeregistrationBatch, DiscoveryFilter, Lookahead, Operator, OperatorDeregistrationBatch,
+            OperatorSettingsUpdate, OperatorStatus, Registration, RegistrationBatch, RegistryEntry,
+            RestakingProtocol, RpcEndpointUpdate, SourceConflict, StakeSnapshot,
+            WhitelistedCollateral,
         },
-        BlsPublicKey,
+        signing::SigningContext,
+        unix_seconds, BlsPublicKey,
     },
-    sources::kapi::KeysApi,
+    sources::{feed::OperatorFeed, kapi::KeysApi},
     sync::{SyncHandle, Syncer},
     Action, ActionStream,
 };
 
+/// The interval at which expired registrations are pruned from the database. Expired
+/// registrations are hidden from all reads in the meantime.
+const EXPIRY_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 /// The main registry object.
 pub(crate) struct Registry<Db> {
     /// The database handle.
     db: Db,
     /// The beacon API client.
     beacon: BeaconClient,
+    /// The signing context of the network, used to verify operator signatures.
+    signing: SigningContext,
     /// Handle to the syncer. The implementation MUST block any DB reads & writes
     /// until the syncer is done syncing the registry.
     sync: SyncHandle,
@@ -36,19 +47,49 @@ impl<Db> Registry<Db>
 where
     Db: RegistryDb,
 {
-    /// Create a new registry instance.
-    pub(crate) fn new(config: Config, db: Db, beacon: BeaconClient) -> Self {
-        let kapi = KeysApi::new(&config.keys_api_url);
-        // TODO: add health check for the keys API before proceeding
+    /// Create a new registry instance. Operator signatures are verified against the given
+    /// network [`SigningContext`].
+    pub(crate) fn new(
+        config: Config,
+        db: Db,
+        beacon: BeaconClient,
+        signing: SigningContext,
+    ) -> Self {
+        let contracts = config.contracts();
+        let sources = config.sources();
 
         let (mut syncer, handle) = Syncer::new(config.beacon_url, db.clone());
 
-        // Set source
-        syncer.set_source(kapi);
+        // Add external sources
+        // TODO: add health checks for the sources before proceeding
+        for source in sources {
+            match source.kind {
+                SourceKind::Lido => syncer.add_source(KeysApi::new(source.url), source.priority),
+                SourceKind::Feed => {
+                    syncer.add_source(OperatorFeed::new(source.url), source.priority)
+                }
+            }
+        }
+
+        syncer.set_operators_registry(
+            OperatorsRegistryClient::new(
+                config.execution_url.clone(),
+                contracts.operators_registry,
+            ),
+            config.confirmation_depth,
+        );
+        syncer.set_bootstrap(config.bootstrap);
+        syncer.set_retry_policy(config.retry);
+        syncer.set_restaking_middlewares(RestakingMiddlewaresClient::new(
+            config.execution_url,
+            contracts.symbiotic_middleware,
+            contracts.eigenlayer_middleware,
+        ));
 
         let _sync_task = syncer.spawn();
+        let _sweeper_task = spawn_expiry_sweeper(db.clone());
 
-        Self { db, beacon, sync: handle }
+        Self { db, beacon, signing, sync: handle }
     }
 
     /// Handle incoming actions from the API server and update the registry.
@@ -65,12 +106,20 @@ where
                     let res = self.deregister_validators(deregistration).await;
                     response.send(res).ok();
                 }
+                Action::OperatorDeregister { deregistration, response } => {
+                    let res = self.operator_deregister_validators(deregistration).await;
+                    response.send(res).ok();
+                }
+                Action::UpdateOperatorSettings { update, response } => {
+                    let res = self.update_operator_settings(update).await;
+                    response.send(res).ok();
+                }
                 Action::GetRegistrations { response } => {
                     let res = self.list_registrations().await;
                     response.send(res).ok();
                 }
-                Action::GetValidators { response } => {
-                    let res = self.list_validators().await;
+                Action::GetValidators { filter, response } => {
+                    let res = self.list_validators(filter).await;
                     response.send(res).ok();
                 }
                 Action::GetValidatorsByPubkeys { pubkeys, response } => {
@@ -91,14 +140,34 @@ where
                     let first_operator_res = res.map(|mut o| o.pop()).transpose();
                     response.send(first_operator_res.unwrap_or(Err(RegistryError::NotFound))).ok();
                 }
-                Action::GetOperators { response } => {
-                    let res = self.list_operators().await;
+                Action::GetOperators { filter, response } => {
+                    let res = self.list_operators(filter).await;
                     response.send(res).ok();
                 }
                 Action::GetLookahead { epoch, response } => {
                     let res = self.get_lookahead(epoch).await;
                     response.send(res).ok();
                 }
+                Action::GetOperatorRpcHistory { signer, response } => {
+                    let res = self.list_rpc_endpoint_history(signer).await;
+                    response.send(res).ok();
+                }
+                Action::GetOperatorStakeHistory { signer, response } => {
+                    let res = self.list_stake_snapshots(signer).await;
+                    response.send(res).ok();
+                }
+                Action::GetOperatorStakeAt { signer, epoch, response } => {
+                    let res = self.get_stake_snapshot(signer, epoch).await;
+                    response.send(res.and_then(|s| s.ok_or(RegistryError::NotFound))).ok();
+                }
+                Action::GetWhitelist { protocol, response } => {
+                    let res = self.list_whitelisted_collateral(protocol).await;
+                    response.send(res).ok();
+                }
+                Action::GetSourceConflicts { response } => {
+                    let res = self.list_source_conflicts().await;
+                    response.send(res).ok();
+                }
             }
         }
     }
@@ -111,11 +180,30 @@ where
         let count = registration.validator_pubkeys.len();
         let operator = registration.operator;
 
-        // 1. validate the existence and activity of the validators in the beacon chain
+        // 0. reject registrations that have already expired
+        if registration.is_expired(unix_seconds()) {
+            return Err(RegistryError::BadRequest("Registration has already expired"));
+        }
+
+        // NOTE: the signatures of the validators were verified by the API before the registration
+        // reached the registry, so that requests are verified concurrently.
+
+        // 1. check that the operator is known and active on-chain
+        self.check_operator(operator).await?;
+
+        // 2. check that the operator accepts the validators, if it signed or requires acceptance
+        self.check_operator_acceptance(&registration).await?;
+
+        // 3. reject stale or replayed registrations
         let pubkeys = registration.validator_pubkeys.as_slice();
+        self.check_nonces(pubkeys, registration.nonce).await?;
+
+        // 4. validate the existence and activity of the validators in the beacon chain.
+        // NOTE: this also guarantees that aggregate signatures are safe from rogue key attacks,
+        // since active validators have proven possession of their keys with their deposits.
         let validators = self.beacon.get_active_validators_by_pubkey(pubkeys).await?;
 
-        // 2. collect a map of validator public keys to their indices
+        // 5. collect a map of validator public keys to their indices
         let index_map = validators
             .into_iter()
             .map(|v| {
@@ -126,14 +214,14 @@ where
             })
             .collect::<HashMap<_, _>>();
 
-        // 3. check that all validators are present
+        // 6. check that all validators are present
         if index_map.len() != count {
             return Err(RegistryError::BadRequest(
                 "Not all validators are active in the beacon chain, skipping registration",
             ));
         }
 
-        // 4. insert the registrations into the database
+        // 7. insert the registrations into the database
         let registrations = registration.into_items(index_map);
 
         self.sync.wait_for_sync().await;
@@ -151,13 +239,175 @@ where
         let count = deregistration.validator_pubkeys.len();
         let operator = deregistration.operator;
 
-        self.sync.wait_for_sync().await;
+        // NOTE: the signatures of the validators were verified by the API before the
+        // deregistration reached the registry, so that requests are verified concurrently.
+
+        // 1. reject stale or replayed deregistrations
+        self.check_nonces(&deregistration.validator_pubkeys, deregistration.nonce).await?;
+
+        // 2. check that all validators are registered to the given operator
+        self.check_registered_to(&deregistration.validator_pubkeys, operator).await?;
+
+        // 3. remove the registrations from the database
         self.db.deregister_validators(&deregistration.into_items()).await?;
 
         info!(%count, %operator, "Validators deregistered successfully");
         Ok(())
     }
 
+    /// Deregister validators from the registry on behalf of their operator.
+    pub(crate) async fn operator_deregister_validators(
+        &mut self,
+        deregistration: OperatorDeregistrationBatch,
+    ) -> Result<(), RegistryError> {
+        let count = deregistration.validator_pubkeys.len();
+        let operator = deregistration.operator;
+
+        if count == 0 {
+            return Err(RegistryError::BadRequest("No validators to deregister"));
+        }
+
+        // 1. verify the signature of the operator over the deregistration
+        if !deregistration.verify_signature(&self.signing) {
+            return Err(RegistryError::BadRequest("Invalid operator signature"));
+        }
+
+        // 2. reject stale or replayed deregistrations. Operator nonces are tracked per operator,
+        // apart from the validator nonces, so operators can't block the validators they remove.
+        self.sync.wait_for_sync().await;
+        let last_nonce = self.db.get_operator_deregistration_nonce(operator).await?;
+        if last_nonce.is_some_and(|last| last >= deregistration.nonce) {
+            return Err(RegistryError::BadRequest(
+                "Stale or replayed operator deregistration nonce",
+            ));
+        }
+
+        // 3. check that the operator only removes validators registered to it
+        self.check_registered_to(&deregistration.validator_pubkeys, operator).await?;
+
+        // 4. remove the registrations from the database
+        let pubkeys = &deregistration.validator_pubkeys;
+        self.db.operator_deregister_validators(operator, pubkeys, deregistration.nonce).await?;
+
+        info!(%count, %operator, "Validators deregistered by operator successfully");
+        Ok(())
+    }
+
+    /// Checks that all given validators are registered to the given operator.
+    async fn check_registered_to(
+        &mut self,
+        pubkeys: &[BlsPublicKey],
+        operator: Address,
+    ) -> Result<(), RegistryError> {
+        self.sync.wait_for_sync().await;
+        let registrations = self.db.get_registrations_by_pubkey(pubkeys).await?;
+
+        if registrations.len() != pubkeys.len() {
+            return Err(RegistryError::NotFound);
+        }
+
+        let mismatched = registrations
+            .into_iter()
+            .filter(|r| r.operator != operator)
+            .map(|r| r.validator_pubkey)
+            .collect::<Vec<_>>();
+
+        if !mismatched.is_empty() {
+            return Err(RegistryError::OperatorMismatch(mismatched));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the operator is in the registry and active in the operators registry
+    /// contract.
+    async fn check_operator(&mut self, operator: Address) -> Result<(), RegistryError> {
+        self.sync.wait_for_sync().await;
+
+        let Some(status) =
+            self.db.get_operators_by_signer(&[operator]).await?.pop().map(|o| o.status)
+        else {
+            return Err(RegistryError::UnknownOperator(operator));
+        };
+
+        if status != OperatorStatus::Active {
+            return Err(RegistryError::InactiveOperator(operator, status));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the acceptance signature of the operator of a registration batch. The signature
+    /// must be valid if present, and present if the operator requires it.
+    async fn check_operator_acceptance(
+        &mut self,
+        registration: &RegistrationBatch,
+    ) -> Result<(), RegistryError> {
+        if registration.operator_signature.is_some() {
+            if !registration.verify_operator_signature(&self.signing) {
+                return Err(RegistryError::BadRequest("Invalid operator acceptance signature"));
+            }
+
+            return Ok(());
+        }
+
+        self.sync.wait_for_sync().await;
+        if self.db.get_operator_settings(registration.operator).await?.requires_acceptance {
+            return Err(RegistryError::BadRequest("Operator requires an acceptance signature"));
+        }
+
+        Ok(())
+    }
+
+    /// Update the settings of an operator.
+    pub(crate) async fn update_operator_settings(
+        &mut self,
+        update: OperatorSettingsUpdate,
+    ) -> Result<(), RegistryError> {
+        let operator = update.operator;
+
+        // 1. verify the signature of the operator over the update
+        if !update.verify_signature(&self.signing) {
+            return Err(RegistryError::BadRequest("Invalid operator signature"));
+        }
+
+        // 2. reject stale or replayed updates
+        self.sync.wait_for_sync().await;
+        let current = self.db.get_operator_settings(operator).await?;
+        if current.nonce >= update.nonce {
+            return Err(RegistryError::BadRequest("Stale or replayed operator settings nonce"));
+        }
+
+        // 3. store the new settings
+        self.db.update_operator_settings(operator, update.settings()).await?;
+
+        info!(%operator, requires_acceptance = update.requires_acceptance, "Operator settings updated");
+        Ok(())
+    }
+
+    /// Checks that `nonce` is strictly greater than the nonce of the last accepted
+    /// (de)registration of every given validator. Returns the stale validators otherwise.
+    async fn check_nonces(
+        &mut self,
+        pubkeys: &[BlsPublicKey],
+        nonce: u64,
+    ) -> Result<(), RegistryError> {
+        self.sync.wait_for_sync().await;
+        let last_nonces = self.db.get_nonces(pubkeys).await?;
+
+        let stale = pubkeys
+            .iter()
+            .filter(|pubkey| last_nonces.get(*pubkey).is_some_and(|last| *last >= nonce))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !stale.is_empty() {
+            return Err(RegistryError::StaleNonce(stale));
+        }
+
+        Ok(())
+    }
+
     /// List all registrations in the registry.
     pub(crate) async fn list_registrations(&mut self) -> Result<Vec<Registration>, RegistryError> {
         self.sync.wait_for_sync().await;
@@ -173,10 +423,13 @@ where
         Ok(self.db.get_registrations_by_pubkey(pubkeys).await?)
     }
 
-    /// List all validators in the registry.
-    pub(crate) async fn list_validators(&mut self) -> Result<Vec<RegistryEntry>, RegistryError> {
+    /// List the validators in the registry that pass the given filter.
+    pub(crate) async fn list_validators(
+        &mut self,
+        filter: DiscoveryFilter,
+    ) -> Result<Vec<RegistryEntry>, RegistryError> {
         self.sync.wait_for_sync().await;
-        Ok(self.db.list_validators().await?)
+        Ok(self.db.list_validators(filter).await?)
     }
 
     /// Get validators by validator public key.
@@ -197,10 +450,13 @@ where
         Ok(self.db.get_validators_by_index(indices).await?)
     }
 
-    /// List all operators in the registry.
-    pub(crate) async fn list_operators(&mut self) -> Result<Vec<Operator>, RegistryError> {
+    /// List the operators in the registry that pass the given filter.
+    pub(crate) async fn list_operators(
+        &mut self,
+        filter: DiscoveryFilter,
+    ) -> Result<Vec<Operator>, RegistryError> {
         self.sync.wait_for_sync().await;
-        Ok(self.db.list_operators().await?)
+        Ok(self.db.list_operators(filter).await?)
     }
 
     /// Get operators by signer.
@@ -212,6 +468,52 @@ where
         Ok(self.db.get_operators_by_signer(signers).await?)
     }
 
+    /// List the RPC endpoint history of an operator, from oldest to newest.
+    pub(crate) async fn list_rpc_endpoint_history(
+        &mut self,
+        signer: Address,
+    ) -> Result<Vec<RpcEndpointUpdate>, RegistryError> {
+        self.sync.wait_for_sync().await;
+        Ok(self.db.list_rpc_endpoint_history(signer).await?)
+    }
+
+    /// List the stake snapshots of an operator, ordered by operators registry epoch.
+    pub(crate) async fn list_stake_snapshots(
+        &mut self,
+        signer: Address,
+    ) -> Result<Vec<StakeSnapshot>, RegistryError> {
+        self.sync.wait_for_sync().await;
+        Ok(self.db.list_stake_snapshots(signer).await?)
+    }
+
+    /// Get the stake snapshot of an operator at the given operators registry epoch.
+    pub(crate) async fn get_stake_snapshot(
+        &mut self,
+        signer: Address,
+        epoch: u64,
+    ) -> Result<Option<StakeSnapshot>, RegistryError> {
+        self.sync.wait_for_sync().await;
+        Ok(self.db.get_stake_snapshot(signer, epoch).await?)
+    }
+
+    /// List the collateral whitelisted in the restaking middlewares, optionally filtered by
+    /// protocol.
+    pub(crate) async fn list_whitelisted_collateral(
+        &mut self,
+        protocol: Option<RestakingProtocol>,
+    ) -> Result<Vec<WhitelistedCollateral>, RegistryError> {
+        self.sync.wait_for_sync().await;
+        Ok(self.db.list_whitelisted_collateral(protocol).await?)
+    }
+
+    /// List the validators that external sources claim for different operators.
+    pub(crate) async fn list_source_conflicts(
+        &mut self,
+    ) -> Result<Vec<SourceConflict>, RegistryError> {
+        self.sync.wait_for_sync().await;
+        Ok(self.db.list_source_conflicts().await?)
+    }
+
     /// Get the active validators that will propose in the given epoch
     /// that are also registered in the registry.
     pub(crate) async fn get_lookahead(&mut self, epoch: u64) -> Result<Lookahead, RegistryError> {
@@ -224,9 +526,19 @@ where
 
         // 2. fetch the registry entries from the database
         self.sync.wait_for_sync().await;
-        let registry_entries = self.db.get_validators_by_pubkey(&proposer_pubkeys).await?;
+        let mut registry_entries = self.db.get_validators_by_pubkey(&proposer_pubkeys).await?;
 
-        // 3. map registry entries to their proposal slot. example result:
+        // 3. serve the RPC endpoints that were effective at the epoch, which differ from the
+        // current ones for past epochs. Operators without endpoint history keep their current one.
+        let operators = registry_entries.iter().map(|e| e.operator).collect::<Vec<_>>();
+        let rpc_endpoints = self.db.get_rpc_endpoints_at(&operators, epoch).await?;
+        for entry in &mut registry_entries {
+            if let Some(rpc_endpoint) = rpc_endpoints.get(&entry.operator) {
+                entry.rpc_endpoint = rpc_endpoint.clone();
+            }
+        }
+
+        // 4. map registry entries to their proposal slot. example result:
         //
         // 10936976: { validator_pubkey: 0x1234, operator: 0x5678, gas_limit: 1000000, rpc_endpoint: https://rpc.example.com }
         // 10936977: { validator_pubkey: 0x9214, operator: 0x5678, gas_limit: 1000000, rpc_endpoint: https://rpc.example.com }
@@ -243,3 +555,20 @@ where
         Ok(lookahead)
     }
 }
+
+/// Spawns a background task that periodically prunes expired registrations from the database.
+fn spawn_expiry_sweeper<Db: RegistryDb>(db: Db) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_SWEEP_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match db.prune_expired_registrations(unix_seconds()).await {
+                Ok(0) => {}
+                Ok(count) => info!(%count, "Pruned expired registrations"),
+                Err(e) => error!(error = ?e, "Failed to prune expired registrations"),
+            }
+        }
+    })
+}
@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use reqwest::IntoUrl;
+use url::Url;
+
+use crate::primitives::{
+    registry::{DataSource, RegistryEntry},
+    BlsPublicKey,
+};
+
+use super::{ExternalSource, SourceError};
+
+/// Operator feed external source. The feed serves the registry entries of the validators of an
+/// operator, as a JSON array of [`RegistryEntry`]s.
+pub(crate) struct OperatorFeed {
+    client: reqwest::Client,
+    /// The URL of the feed.
+    url: Url,
+}
+
+impl OperatorFeed {
+    pub(crate) fn new(url: impl IntoUrl) -> Self {
+        Self { client: reqwest::Client::new(), url: url.into_url().expect("failed to parse URL") }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExternalSource for OperatorFeed {
+    fn name(&self) -> &str {
+        self.url.as_str()
+    }
+
+    fn source(&self) -> DataSource {
+        DataSource::Feed
+    }
+
+    /// Fetches the feed, and returns the entries of the given `pubkeys`.
+    async fn get_validators(
+        &self,
+        pubkeys: &[BlsPublicKey],
+    ) -> Result<Vec<RegistryEntry>, SourceError> {
+        let res = self.client.get(self.url.clone()).send().await?.error_for_status()?;
+        let entries = res.json::<Vec<RegistryEntry>>().await?;
+
+        let pubkeys = pubkeys.iter().collect::<HashSet<_>>();
+        Ok(entries.into_iter().filter(|entry| pubkeys.contains(&entry.validator_pubkey)).collect())
+    }
+}
@@ -3,7 +3,10 @@ use reqwest::{IntoUrl, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::primitives::{registry::RegistryEntry, BlsPublicKey};
+use crate::primitives::{
+    registry::{DataSource, RegistryEntry},
+    BlsPublicKey,
+};
 
 use super::{ExternalSource, SourceError};
 
@@ -67,10 +70,14 @@ impl KeysApi {
 
 #[async_trait::async_trait]
 impl ExternalSource for KeysApi {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "lido-keys-api"
     }
 
+    fn source(&self) -> DataSource {
+        DataSource::Lido
+    }
+
     /// Fetches validators by `pubkeys` from the API.
     async fn get_validators(
         &self,
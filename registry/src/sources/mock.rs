@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use crate::primitives::{registry::RegistryEntry, BlsPublicKey};
+use crate::primitives::{
+    registry::{DataSource, RegistryEntry},
+    BlsPublicKey,
+};
 
 use super::{ExternalSource, SourceError};
 
@@ -20,10 +23,15 @@ impl MockSource {
 
 #[async_trait::async_trait]
 impl ExternalSource for MockSource {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "mock"
     }
 
+    /// The mock stands in for the Lido Keys API, the only external source.
+    fn source(&self) -> DataSource {
+        DataSource::Lido
+    }
+
     async fn get_validators(
         &self,
         pubkeys: &[BlsPublicKey],
@@ -1,12 +1,23 @@
 //! Sources contain external registry data sources.
+use std::{cmp::Reverse, collections::HashMap};
+
 use thiserror::Error;
 
-use crate::primitives::{registry::RegistryEntry, BlsPublicKey};
+use crate::{
+    client::retry::CircuitOpen,
+    primitives::{
+        registry::{DataSource, RegistryEntry, SourceClaim, SourceConflict},
+        BlsPublicKey,
+    },
+};
 
 /// Lido Keys API source.
 /// <https://github.com/lidofinance/lido-keys-api/tree/develop>
 pub(crate) mod kapi;
 
+/// Operator feed source, serving the registry entries of an operator.
+pub(crate) mod feed;
+
 /// Mock external source for testing.
 #[cfg(test)]
 pub(crate) mod mock;
@@ -17,15 +28,148 @@ pub(crate) enum SourceError {
     Reqwest(#[from] reqwest::Error),
     #[error("{0}")]
     Other(String),
+    #[error(transparent)]
+    CircuitOpen(#[from] CircuitOpen),
 }
 
 /// External source trait.
 #[async_trait::async_trait]
 pub(crate) trait ExternalSource {
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &str;
+
+    /// The source recorded on the registrations and operators imported from this source.
+    fn source(&self) -> DataSource;
 
     async fn get_validators(
         &self,
         pubkeys: &[BlsPublicKey],
     ) -> Result<Vec<RegistryEntry>, SourceError>;
 }
+
+/// The entries returned by an external source.
+#[derive(Debug)]
+pub(crate) struct SourceEntries {
+    /// The name of the source.
+    pub(crate) name: String,
+    /// The source recorded on the imported registrations and operators.
+    pub(crate) source: DataSource,
+    /// The configured priority of the source.
+    pub(crate) priority: u8,
+    /// The returned entries.
+    pub(crate) entries: Vec<RegistryEntry>,
+}
+
+/// An entry selected among the entries of multiple external sources.
+#[derive(Debug)]
+pub(crate) struct MergedEntry {
+    /// The selected entry.
+    pub(crate) entry: RegistryEntry,
+    /// The source of the selected entry.
+    pub(crate) source: DataSource,
+    /// The priority of the source of the selected entry.
+    pub(crate) priority: u8,
+}
+
+/// Merges the entries of multiple external sources, given in their configured order.
+///
+/// For every validator, the entry of the source with the highest priority is selected, with ties
+/// going to the source configured first. Validators claimed for different operators are also
+/// returned as [`SourceConflict`]s.
+pub(crate) fn merge_entries(
+    mut results: Vec<SourceEntries>,
+) -> (Vec<MergedEntry>, Vec<SourceConflict>) {
+    // Claims of every validator, as (source index, entry), in order of first appearance
+    let mut order = Vec::new();
+    let mut claims = HashMap::<BlsPublicKey, Vec<(usize, RegistryEntry)>>::new();
+    for (i, result) in results.iter_mut().enumerate() {
+        for entry in std::mem::take(&mut result.entries) {
+            claims
+                .entry(entry.validator_pubkey.clone())
+                .or_insert_with(|| {
+                    order.push(entry.validator_pubkey.clone());
+                    Vec::new()
+                })
+                .push((i, entry));
+        }
+    }
+
+    let mut merged = Vec::with_capacity(order.len());
+    let mut conflicts = Vec::new();
+    for pubkey in order {
+        let mut claims = claims.remove(&pubkey).expect("claims are present");
+
+        // NOTE: the sort is stable, so ties keep the configured order
+        claims.sort_by_key(|(i, _)| Reverse(results[*i].priority));
+
+        let operator = claims[0].1.operator;
+        if claims.iter().any(|(_, entry)| entry.operator != operator) {
+            conflicts.push(SourceConflict {
+                validator_pubkey: pubkey,
+                operator,
+                claims: claims
+                    .iter()
+                    .map(|(i, entry)| SourceClaim {
+                        source: results[*i].name.clone(),
+                        priority: results[*i].priority,
+                        operator: entry.operator,
+                    })
+                    .collect(),
+            });
+        }
+
+        let (i, entry) = claims.swap_remove(0);
+        merged.push(MergedEntry {
+            entry,
+            source: results[i].source,
+            priority: results[i].priority,
+        });
+    }
+
+    (merged, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Address;
+
+    use super::*;
+
+    fn entries(name: &str, priority: u8, claims: &[(&BlsPublicKey, u8)]) -> SourceEntries {
+        let entries = claims
+            .iter()
+            .map(|(pubkey, operator)| RegistryEntry {
+                validator_pubkey: (*pubkey).clone(),
+                operator: Address::with_last_byte(*operator),
+                gas_limit: 0,
+                rpc_endpoint: "https://rick.com".parse().unwrap(),
+            })
+            .collect();
+
+        SourceEntries { name: name.to_owned(), source: DataSource::Feed, priority, entries }
+    }
+
+    #[test]
+    fn test_merge_entries() {
+        let pubkeys = [BlsPublicKey::random(), BlsPublicKey::random(), BlsPublicKey::random()];
+
+        let (merged, conflicts) = merge_entries(vec![
+            entries("a", 1, &[(&pubkeys[0], 1), (&pubkeys[1], 1)]),
+            entries("b", 5, &[(&pubkeys[0], 2)]),
+            entries("c", 5, &[(&pubkeys[0], 3), (&pubkeys[2], 3)]),
+        ]);
+
+        let operators = merged.iter().map(|m| m.entry.operator).collect::<Vec<_>>();
+        assert_eq!(
+            operators,
+            [Address::with_last_byte(2), Address::with_last_byte(1), Address::with_last_byte(3)]
+        );
+        assert_eq!(merged[0].priority, 5);
+
+        // The tie between "b" and "c" goes to the source configured first
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].validator_pubkey, pubkeys[0]);
+        assert_eq!(conflicts[0].operator, Address::with_last_byte(2));
+        let sources = conflicts[0].claims.iter().map(|c| c.source.as_str()).collect::<Vec<_>>();
+        assert_eq!(sources, ["b", "c", "a"]);
+    }
+}
@@ -1,33 +1,124 @@
 //! Module `sync` contains functionality for syncing the registry with the chain, and other external
 //! data providers.
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use beacon_api_client::ProposerDuty;
 use chain::{EpochTransition, EpochTransitionStream};
 use reqwest::IntoUrl;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::{sync::watch, task::JoinHandle};
+use tokio::{
+    sync::watch,
+    task::{JoinHandle, JoinSet},
+};
 use tokio_stream::StreamExt;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+use url::Url;
 
 use crate::{
-    client::{beacon::BeaconClientError, BeaconClient},
+    chainio::{
+        abi::OperatorsRegistry::OperatorsRegistryEvents, ChainIoError, OperatorsRegistryClient,
+        RegistryLog, RestakingMiddlewaresClient,
+    },
+    client::{beacon::BeaconClientError, BeaconClient, CircuitBreaker, RetryPolicy},
     db::{RegistryDb, SyncTransaction},
     primitives::{
-        registry::{Operator, Registration},
+        registry::{
+            ContractEventStream, DataSource, Operator, OperatorEvent, OperatorMetadata,
+            OperatorSetEvent, OperatorStatus, Registration, RpcEndpointUpdate, StakeSnapshot,
+            WhitelistEvent,
+        },
         BlsPublicKey, SyncStateUpdate,
     },
-    sources::ExternalSource,
+    sources::{merge_entries, ExternalSource, SourceEntries, SourceError},
 };
 
 mod chain;
 
+/// The maximum block range of a single contract log query.
+const MAX_LOG_RANGE: u64 = 10_000;
+
+/// The number of blocks backfilled per sync transaction.
+const BACKFILL_BATCH_SIZE: u64 = 100_000;
+
+/// The number of contract checkpoints kept beyond the confirmation depth. Reorgs deeper than the
+/// kept checkpoints roll back all contract events.
+const EXTRA_CONTRACT_CHECKPOINTS: usize = 64;
+
+/// The maximum number of operator collateral or stake queries in flight at once.
+const MAX_CONCURRENT_OPERATOR_QUERIES: usize = 16;
+
+/// The maximum number of epochs whose lookaheads are synced in a single sync transaction. The
+/// remaining epochs are synced by the next transitions.
+const MAX_EPOCHS_PER_TRANSITION: u64 = 32;
+
+/// The interval between bootstrap, backfill and initial sync attempts after a failure.
+const SYNC_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The duration of a beacon chain slot, in seconds.
+const SECONDS_PER_SLOT: u64 = 12;
+
+/// How the syncer initializes its state on a fresh database, i.e. from which epoch lookaheads
+/// are synced. Contract events are always synced from the deployment of the contracts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub(crate) enum SyncBootstrap {
+    /// Start from the current epoch of the beacon chain.
+    #[default]
+    Head,
+    /// Start from the given epoch.
+    Checkpoint {
+        /// The first epoch to sync.
+        epoch: u64,
+    },
+    /// Start from the epoch in which the operators registry and restaking middlewares were
+    /// deployed.
+    Deployment,
+}
+
+/// An external data source, and the priority of its entries over the ones of other sources.
+struct PrioritizedSource {
+    source: Arc<dyn ExternalSource + Send + Sync>,
+    priority: u8,
+    /// The circuit breaker of the source, shared by all its queries.
+    breaker: Arc<CircuitBreaker>,
+}
+
+/// The contract events emitted in a block range.
+#[derive(Debug, Default)]
+struct ContractEvents {
+    /// Operator events of the operators registry.
+    operators: Vec<RegistryLog>,
+    /// Whitelist events of the restaking middlewares.
+    whitelist: Vec<WhitelistEvent>,
+    /// Operator set events of the EigenLayer allocation manager for the bolt AVS.
+    operator_sets: Vec<OperatorSetEvent>,
+}
+
+impl ContractEvents {
+    /// Returns the total number of events.
+    fn len(&self) -> usize {
+        self.operators.len() + self.whitelist.len() + self.operator_sets.len()
+    }
+}
+
 #[derive(Debug, Error)]
 pub(crate) enum SyncError {
     #[error(transparent)]
     Beacon(#[from] BeaconClientError),
     #[error(transparent)]
     Db(#[from] crate::db::DbError),
+    #[error(transparent)]
+    Chain(#[from] ChainIoError),
+    #[error(transparent)]
+    Source(#[from] SourceError),
 }
 
 enum SyncState {
@@ -65,14 +156,27 @@ pub(crate) struct Syncer<Db> {
     state: watch::Sender<SyncState>,
     beacon_client: BeaconClient,
 
-    /// External data source.
-    /// NOTE: We use dynamic dispatch because when we have multiple data sources, we don't want to
-    /// have to change the `Syncer` struct every time we add a new source. This will all sit in a
-    /// vector of sources.
-    source: Option<Box<dyn ExternalSource + Send + Sync>>,
-
-    /// The last known block number. Whenever a new epoch transition occurs, sync contract events
-    /// from this block number to the new block number.
+    /// External data sources, in their configured order. They are queried concurrently, and
+    /// their entries merged by priority.
+    sources: Vec<PrioritizedSource>,
+    /// The retry policy of beacon and external source calls.
+    retry: RetryPolicy,
+
+    /// Client for the operators registry contract, from which operators are synced.
+    operators_registry: Option<OperatorsRegistryClient>,
+    /// The number of blocks behind the head after which contract events are considered final
+    /// enough to be applied.
+    confirmation_depth: u64,
+    /// The current block range of contract log queries. Adapted to the limits of the provider.
+    log_range: AtomicU64,
+    /// Client for the restaking middleware contracts, from which operator collateral is synced.
+    middlewares: Option<RestakingMiddlewaresClient>,
+
+    /// How to initialize the sync state on a fresh database.
+    bootstrap: SyncBootstrap,
+
+    /// The last known block number. Contract events are synced from the last ingested block
+    /// tracked in the database instead, as it lags behind by the confirmation depth.
     last_block_number: u64,
     /// The last known epoch number. Whenever a new epoch transition occurs, sync all lookaheads
     /// from this epoch to the new epoch.
@@ -85,17 +189,23 @@ where
 {
     /// Creates a new syncer with the given beacon and keys API URLs, and the database handle.
     pub(crate) fn new(beacon_url: impl IntoUrl, db: Db) -> (Self, SyncHandle) {
-        let (state_tx, state_rx) = watch::channel(SyncState::Synced);
+        // Reads are blocked until the first sync transaction is committed
+        let (state_tx, state_rx) = watch::channel(SyncState::Syncing);
         let handle = SyncHandle { state: state_rx };
 
         let beacon_client = BeaconClient::new(beacon_url.into_url().unwrap());
 
-        // TODO: read the last block number from the database and use as checkpoint for backfill
         let syncer = Self {
             db,
             state: state_tx,
             beacon_client,
-            source: None,
+            sources: Vec::new(),
+            retry: RetryPolicy::default(),
+            operators_registry: None,
+            confirmation_depth: 0,
+            log_range: AtomicU64::new(MAX_LOG_RANGE),
+            middlewares: None,
+            bootstrap: SyncBootstrap::default(),
             last_block_number: 0,
             last_epoch: 0,
         };
@@ -103,40 +213,187 @@ where
         (syncer, handle)
     }
 
-    /// Sets an external data source.
-    pub(crate) fn set_source<S: ExternalSource + Send + Sync + 'static>(&mut self, source: S) {
-        self.source = Some(Box::new(source));
+    /// Adds an external data source. On conflicting entries, the source with the highest
+    /// priority wins, and ties go to the source added first.
+    pub(crate) fn add_source<S: ExternalSource + Send + Sync + 'static>(
+        &mut self,
+        source: S,
+        priority: u8,
+    ) {
+        self.sources.push(PrioritizedSource {
+            source: Arc::new(source),
+            priority,
+            breaker: Arc::default(),
+        });
+    }
+
+    /// Sets the retry policy of beacon and external source calls.
+    pub(crate) fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+        self.beacon_client.set_retry_policy(retry);
+    }
+
+    /// Sets the operators registry contract client. Contract events are applied once they are
+    /// `confirmation_depth` blocks deep.
+    pub(crate) fn set_operators_registry(
+        &mut self,
+        client: OperatorsRegistryClient,
+        confirmation_depth: u64,
+    ) {
+        self.operators_registry = Some(client);
+        self.confirmation_depth = confirmation_depth;
+    }
+
+    /// Sets the restaking middlewares client.
+    pub(crate) fn set_restaking_middlewares(&mut self, client: RestakingMiddlewaresClient) {
+        self.middlewares = Some(client);
+    }
+
+    /// Sets how the sync state is initialized on a fresh database.
+    pub(crate) fn set_bootstrap(&mut self, bootstrap: SyncBootstrap) {
+        self.bootstrap = bootstrap;
     }
 
     /// Spawns the [`Syncer`] actor task.
+    ///
+    /// The sync state is loaded from the database, or bootstrapped on a fresh one. If an
+    /// operators registry is configured, contract events are backfilled first. The syncer only
+    /// reports being synced once an initial full sync up to the current head is done.
     pub(crate) fn spawn(mut self) -> JoinHandle<Result<(), SyncError>> {
         tokio::spawn(async move {
-            // Set initial state
-            // NOTE: this will fail if no sync state is present in the DB.
-            let sync_state = self.db.get_sync_state().await?;
-            self.last_epoch = sync_state.epoch;
-            self.last_block_number = sync_state.block_number;
+            let res = self.run().await;
+            if let Err(ref e) = res {
+                error!(error = ?e, "Syncer terminated");
+            }
 
-            info!(?sync_state, "Loaded sync state from DB");
+            res
+        })
+    }
 
-            let pa_stream = self.beacon_client.subscribe_payload_attributes().await?;
+    /// Runs the syncer until the payload attributes stream ends.
+    async fn run(&mut self) -> Result<(), SyncError> {
+        let sync_state = loop {
+            match self.load_sync_state().await {
+                Ok(state) => break state,
+                Err(e) => {
+                    error!(error = ?e, "Failed to load sync state, retrying...");
+                    tokio::time::sleep(SYNC_RETRY_INTERVAL).await;
+                }
+            }
+        };
 
-            let mut epoch_stream = EpochTransitionStream::new(pa_stream);
+        self.last_epoch = sync_state.epoch;
+        self.last_block_number = sync_state.block_number;
 
-            while let Some(transition) = epoch_stream.next().await {
-                self.on_transition(transition).await;
+        if self.operators_registry.is_some() {
+            // NOTE: progress is committed in batches, so retries resume where they failed.
+            while let Err(e) = self.backfill_contract_events(&sync_state).await {
+                error!(error = ?e, "Failed to backfill contract events, retrying...");
+                tokio::time::sleep(SYNC_RETRY_INTERVAL).await;
             }
+        }
 
-            Ok(())
-        })
+        // Catch up to the current head before following epoch transitions
+        while let Err(e) = self.initial_sync().await {
+            error!(error = ?e, "Failed to run initial sync, retrying...");
+            tokio::time::sleep(SYNC_RETRY_INTERVAL).await;
+        }
+
+        let pa_stream = self.beacon_client.subscribe_payload_attributes().await?;
+
+        let mut epoch_stream = EpochTransitionStream::new(pa_stream);
+
+        while let Some(transition) = epoch_stream.next().await {
+            self.on_transition(transition).await;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the sync state from the database. On a fresh database, the sync state is
+    /// bootstrapped according to the configured [`SyncBootstrap`] mode and persisted.
+    async fn load_sync_state(&self) -> Result<SyncStateUpdate, SyncError> {
+        if let Some(sync_state) = self.db.get_sync_state().await? {
+            info!(?sync_state, "Loaded sync state from DB");
+            return Ok(sync_state);
+        }
+
+        let epoch = match (self.bootstrap, self.operators_registry.as_ref()) {
+            (SyncBootstrap::Checkpoint { epoch }, _) => epoch,
+            (SyncBootstrap::Deployment, Some(registry)) => self.deployment_epoch(registry).await?,
+            (bootstrap, _) => {
+                if bootstrap == SyncBootstrap::Deployment {
+                    warn!("No operators registry configured, bootstrapping from the beacon head");
+                }
+
+                self.beacon_client.get_epoch().await?
+            }
+        };
+
+        // NOTE: no block is synced yet. Contract events are tracked by their own checkpoints.
+        let sync_state = SyncStateUpdate { block_number: 0, epoch, slot: epoch * 32 };
+        self.db.update_sync_state(sync_state.clone()).await?;
+
+        info!(?sync_state, bootstrap = ?self.bootstrap, "Bootstrapped sync state");
+
+        Ok(sync_state)
+    }
+
+    /// Returns the beacon chain epoch in which the contracts were deployed.
+    async fn deployment_epoch(&self, registry: &OperatorsRegistryClient) -> Result<u64, SyncError> {
+        let block_number = self.deployment_block(registry);
+        let timestamp = registry
+            .get_block_timestamp(block_number)
+            .await?
+            .ok_or(ChainIoError::BlockNotFound(block_number))?;
+        let genesis_time = self.beacon_client.get_genesis_time().await?;
+
+        Ok(timestamp.saturating_sub(genesis_time) / (SECONDS_PER_SLOT * 32))
+    }
+
+    /// Runs a full sync up to the current head of the beacon chain and the execution layer. The
+    /// epochs are synced in batches of at most [`MAX_EPOCHS_PER_TRANSITION`], each committed in
+    /// its own sync transaction, so that reads are served in between and retries resume from the
+    /// last committed batch.
+    async fn initial_sync(&mut self) -> Result<(), SyncError> {
+        loop {
+            let slot = self.beacon_client.get_head_slot().await?;
+            let block_number = match self.operators_registry.as_ref() {
+                Some(registry) => registry.get_block_number().await?,
+                None => self.last_block_number,
+            };
+            let epoch = slot / 32;
+
+            info!(epoch, from = self.last_epoch, "Running initial sync");
+
+            self.sync_transition(EpochTransition { block_number, epoch, slot }).await?;
+
+            if self.last_epoch >= epoch {
+                return Ok(());
+            }
+        }
     }
 
     /// Handles an epoch transition event.
     async fn on_transition(&mut self, transition: EpochTransition) {
+        let epoch = transition.epoch;
+        if let Err(e) = self.sync_transition(transition).await {
+            error!(error = ?e, epoch, "Failed to sync epoch transition");
+
+            // NOTE: the sync transaction was rolled back, so reads are served from the last
+            // committed state. The missed epochs are synced on the next transition.
+            let _ = self.state.send(SyncState::Synced);
+        }
+    }
+
+    /// Syncs the registry up to the given epoch transition, and commits the new sync state. The
+    /// lookaheads of at most [`MAX_EPOCHS_PER_TRANSITION`] epochs are synced, in which case the
+    /// committed sync state is the last synced epoch.
+    async fn sync_transition(&mut self, transition: EpochTransition) -> Result<(), SyncError> {
         let start = std::time::Instant::now();
 
-        let epoch_distance = transition.epoch - self.last_epoch;
-        let block_distance = transition.block_number - self.last_block_number;
+        let epoch_distance = transition.epoch.saturating_sub(self.last_epoch);
+        let block_distance = transition.block_number.saturating_sub(self.last_block_number);
 
         info!(
             epoch = transition.epoch,
@@ -155,71 +412,667 @@ where
         // - Register new validators from external sources
         // - Register their associated operators from external sources
         // - Register new operators from contract events
+        // - Record operator RPC endpoint updates
+        // - Update the collateral of all operators
+        // - Snapshot the stake of all operators on new registry epochs
         // - Update the state table
-        let Ok(mut sync_transaction) = self.db.begin_sync().await else {
-            error!("Failed to begin sync transaction");
-            return;
-        };
+        let mut sync_transaction = self.db.begin_sync().await?;
 
-        self.sync_contract_events(&mut sync_transaction, transition.block_number).await;
+        self.sync_contract_events(&mut sync_transaction, transition.block_number).await?;
+        self.sync_rpc_endpoints(&mut sync_transaction, &transition).await?;
 
-        // Sync from the last known epoch to the new epoch
-        for epoch in self.last_epoch..=transition.epoch {
+        // Sync from the last known epoch to the new epoch, capped to bound the transaction
+        let to_epoch =
+            transition.epoch.min(self.last_epoch.saturating_add(MAX_EPOCHS_PER_TRANSITION));
+        for epoch in self.last_epoch..=to_epoch {
             debug!("Syncing epoch {}", epoch);
-            // TODO: handle failure here (currently infinitely retried inside beacon_client)
-            let Ok(lookahead) = self.beacon_client.get_lookahead(epoch, true).await else {
-                error!("Failed to get lookahead for epoch {}", transition.epoch);
-                return;
-            };
+            let lookahead = self.beacon_client.get_lookahead(epoch, true).await?;
 
-            self.sync_lookahead(&mut sync_transaction, lookahead).await;
+            self.sync_lookahead(&mut sync_transaction, lookahead).await?;
         }
 
-        // Update the sync state in the database
-        if let Err(e) =
-            self.finalize_sync(sync_transaction, SyncStateUpdate::from(transition)).await
-        {
-            error!(error = ?e, "Failed to finalize sync");
+        let mut state = SyncStateUpdate::from(transition.clone());
+        if to_epoch < transition.epoch {
+            warn!(
+                synced = to_epoch,
+                remaining = transition.epoch - to_epoch,
+                "Epoch catch-up capped, syncing the remaining epochs on the next transitions"
+            );
+            state = SyncStateUpdate { epoch: to_epoch, slot: to_epoch * 32, ..state };
         }
 
+        // Sync collateral last, as operator registrations above don't carry it
+        self.sync_collateral(&mut sync_transaction).await?;
+        self.sync_stake_snapshots(&mut sync_transaction, transition.block_number).await?;
+
+        // Update the sync state in the database
+        self.finalize_sync(sync_transaction, state).await?;
+
         info!(elapsed = ?start.elapsed(), "Transition handled");
+
+        Ok(())
     }
 
-    /// Finalizes a sync operation. Sets internal state to the newly synced state, updates the DB,
-    /// and notifies the state channel.
+    /// Finalizes a sync operation. Updates the DB, sets internal state to the newly synced state
+    /// once committed, and notifies the state channel.
     async fn finalize_sync(
         &mut self,
         sync_transaction: Db::SyncTransaction,
         state: SyncStateUpdate,
     ) -> Result<(), SyncError> {
-        // Update last epoch
+        // NOTE: the internal state only advances once committed, so that failed syncs are retried
+        sync_transaction.commit(state.clone()).await?;
+
         self.last_epoch = state.epoch;
         self.last_block_number = state.block_number;
 
-        // TODO: retries on transient faults (e.g. network errors)
-        sync_transaction.commit(state).await?;
         let _ = self.state.send(SyncState::Synced);
         Ok(())
     }
 
-    /// Syncs contract events from the last known block number to the given block number.
+    /// Syncs operator events of the operators registry contract, and whitelist events of the
+    /// restaking middlewares, up to the given block number, minus the confirmation depth. If the
+    /// execution layer was reorged below the confirmation depth since the last sync, the events
+    /// of the reorged blocks are rolled back and re-applied.
     async fn sync_contract_events(
         &self,
         sync_transaction: &mut Db::SyncTransaction,
         block_number: u64,
-    ) {
-        // TODO:
-        // 1. Get contract logs from self.last_block_number to block_number
-        // 2. Sync to database
-        // 3. Update last_block_number = block_number
+    ) -> Result<(), SyncError> {
+        let Some(registry) = self.operators_registry.as_ref() else {
+            info!("No operators registry configured, skipping...");
+            return Ok(());
+        };
+
+        let confirmed = block_number.saturating_sub(self.confirmation_depth);
+        let last_synced = self.rollback_to_canonical(registry, sync_transaction).await?;
+
+        // No events can be emitted before the deployment of the contracts
+        let from = last_synced.map_or(0, |n| n + 1).max(self.deployment_block(registry));
+        if from > confirmed {
+            return Ok(());
+        }
+
+        self.ingest_contract_events(registry, sync_transaction, from, confirmed).await
+    }
+
+    /// Backfills contract events from the last ingested block, or from the deployment block on a
+    /// fresh database, up to the confirmed head of the execution layer. Progress is committed in
+    /// batches of [`BACKFILL_BATCH_SIZE`] blocks with the given sync state, so that an
+    /// interrupted backfill resumes from the last committed batch.
+    ///
+    /// Event streams that were not ingested up to the last ingested block, e.g. because they were
+    /// introduced or configured later, are first backfilled on their own.
+    async fn backfill_contract_events(&self, state: &SyncStateUpdate) -> Result<(), SyncError> {
+        let Some(registry) = self.operators_registry.as_ref() else {
+            return Ok(());
+        };
+        let confirmed = registry.get_block_number().await?.saturating_sub(self.confirmation_depth);
+
+        let mut sync_transaction = self.db.begin_sync().await?;
+        let last_synced = self.rollback_to_canonical(registry, &mut sync_transaction).await?;
+        let ingested = sync_transaction.get_contract_event_streams().await?;
+        let streams =
+            self.event_streams().into_iter().filter(|s| !ingested.contains(s)).collect::<Vec<_>>();
+        if last_synced.is_none() {
+            // Nothing was ingested yet, so all streams are ingested from the deployment block
+            sync_transaction.insert_contract_event_streams(&streams).await?;
+        }
+        sync_transaction.commit(state.clone()).await?;
+
+        if let Some(last_synced) = last_synced {
+            self.backfill_event_streams(registry, &streams, last_synced, state).await?;
+        }
+
+        let mut from = last_synced.map_or(0, |n| n + 1).max(self.deployment_block(registry));
+
+        info!(from, to = confirmed, "Backfilling contract events");
+
+        while from <= confirmed {
+            let to = confirmed.min(from.saturating_add(BACKFILL_BATCH_SIZE - 1));
+
+            let mut sync_transaction = self.db.begin_sync().await?;
+            self.ingest_contract_events(registry, &mut sync_transaction, from, to).await?;
+            sync_transaction.commit(state.clone()).await?;
+
+            info!(from, to, remaining = confirmed - to, "Backfilled contract events batch");
+            from = to + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Backfills the given event streams on their own, from the deployment block up to the last
+    /// ingested block, in batches of [`BACKFILL_BATCH_SIZE`] blocks. The streams are recorded as
+    /// ingested with the last batch, so an interrupted backfill starts over. Operator events are
+    /// never backfilled this way, as the contract checkpoints track their ingestion.
+    async fn backfill_event_streams(
+        &self,
+        registry: &OperatorsRegistryClient,
+        streams: &[ContractEventStream],
+        last_synced: u64,
+        state: &SyncStateUpdate,
+    ) -> Result<(), SyncError> {
+        let streams = streams
+            .iter()
+            .copied()
+            .filter(|s| *s != ContractEventStream::Operators)
+            .collect::<Vec<_>>();
+        if streams.is_empty() {
+            return Ok(());
+        }
+
+        let mut from = self.deployment_block(registry);
+
+        info!(?streams, from, to = last_synced, "Backfilling new contract event streams");
+
+        loop {
+            let to = last_synced.min(from.saturating_add(BACKFILL_BATCH_SIZE - 1));
+
+            let mut sync_transaction = self.db.begin_sync().await?;
+            if from <= to {
+                self.apply_contract_events(registry, &mut sync_transaction, &streams, from, to)
+                    .await?;
+            }
+
+            let done = to == last_synced;
+            if done {
+                sync_transaction.insert_contract_event_streams(&streams).await?;
+            }
+            sync_transaction.commit(state.clone()).await?;
+
+            if done {
+                return Ok(());
+            }
+
+            info!(
+                from,
+                to,
+                remaining = last_synced - to,
+                "Backfilled contract event streams batch"
+            );
+            from = to + 1;
+        }
+    }
+
+    /// Fetches and applies the contract events of all streams in the inclusive block range
+    /// `from..=to`, and records the range as ingested.
+    async fn ingest_contract_events(
+        &self,
+        registry: &OperatorsRegistryClient,
+        sync_transaction: &mut Db::SyncTransaction,
+        from: u64,
+        to: u64,
+    ) -> Result<(), SyncError> {
+        let streams = self.event_streams();
+        self.apply_contract_events(registry, sync_transaction, &streams, from, to).await?;
+
+        // Track the hash of the last ingested block, to detect reorgs on the next sync
+        let hash = registry.get_block_hash(to).await?.ok_or(ChainIoError::BlockNotFound(to))?;
+        sync_transaction.insert_contract_checkpoint(to, hash, self.kept_checkpoints()).await?;
+
+        Ok(())
+    }
+
+    /// Fetches and applies the contract events of the given streams in the inclusive block range
+    /// `from..=to`, in chunks of adaptive size.
+    ///
+    /// Chunks are halved whenever the provider rejects a query for spanning too many blocks or
+    /// results, and grow back slowly after successful queries.
+    async fn apply_contract_events(
+        &self,
+        registry: &OperatorsRegistryClient,
+        sync_transaction: &mut Db::SyncTransaction,
+        streams: &[ContractEventStream],
+        from: u64,
+        to: u64,
+    ) -> Result<(), SyncError> {
+        let start = std::time::Instant::now();
+        let mut count = 0;
+
+        let mut chunk_start = from;
+        while chunk_start <= to {
+            let range = self.log_range.load(Ordering::Relaxed);
+            let chunk_end = to.min(chunk_start.saturating_add(range - 1));
+
+            let events = match self
+                .fetch_contract_events(registry, streams, chunk_start, chunk_end)
+                .await
+            {
+                Ok(events) => events,
+                Err(e) if e.is_range_too_large() && range > 1 => {
+                    warn!(error = %e, range = range / 2, "Log query rejected, shrinking block range");
+                    self.log_range.store(range / 2, Ordering::Relaxed);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            count += events.len();
+            for log in events.operators {
+                if streams.contains(&ContractEventStream::Operators) {
+                    debug!(block_number = log.block_number, event = ?log.event, "Applying operator event");
+                    self.apply_operator_event(sync_transaction, log).await?;
+                } else {
+                    self.apply_operator_metadata(sync_transaction, log).await?;
+                }
+            }
+
+            // The whitelist and operator sets are derived from the recorded events, so there is
+            // nothing else to apply
+            for event in events.whitelist {
+                debug!(block_number = event.block_number, ?event, "Applying whitelist event");
+                sync_transaction.record_whitelist_event(&event).await?;
+            }
+
+            for event in events.operator_sets {
+                debug!(block_number = event.block_number, ?event, "Applying operator set event");
+                sync_transaction.record_operator_set_event(&event).await?;
+            }
+
+            let grown = (range + range / 4).max(range + 1).min(MAX_LOG_RANGE);
+            self.log_range.store(grown, Ordering::Relaxed);
+            chunk_start = chunk_end + 1;
+        }
+
+        info!(count, from, to, elapsed = ?start.elapsed(), "Applied contract events");
+
+        Ok(())
+    }
+
+    /// Returns the number of contract checkpoints kept to detect reorgs, i.e. the confirmation
+    /// depth plus [`EXTRA_CONTRACT_CHECKPOINTS`].
+    fn kept_checkpoints(&self) -> usize {
+        (self.confirmation_depth as usize).saturating_add(EXTRA_CONTRACT_CHECKPOINTS)
+    }
+
+    /// Returns the contract event streams to ingest. The streams of the restaking middlewares
+    /// are only ingested if they are configured.
+    fn event_streams(&self) -> Vec<ContractEventStream> {
+        let mut streams =
+            vec![ContractEventStream::Operators, ContractEventStream::OperatorMetadata];
+        if self.middlewares.is_some() {
+            streams.extend([ContractEventStream::Whitelist, ContractEventStream::OperatorSets]);
+        }
+
+        streams
+    }
+
+    /// Returns the block from which contract events are synced, i.e. the first deployment block
+    /// of the operators registry and the restaking middlewares.
+    fn deployment_block(&self, registry: &OperatorsRegistryClient) -> u64 {
+        let middlewares = self.middlewares.as_ref().map_or(u64::MAX, |m| m.deployment_block());
+        registry.deployment_block().min(middlewares)
+    }
+
+    /// Fetches the events of the given streams in the inclusive block range `from..=to`, i.e. the
+    /// operator events of the operators registry, and the whitelist and EigenLayer operator set
+    /// events of the restaking middlewares if configured.
+    async fn fetch_contract_events(
+        &self,
+        registry: &OperatorsRegistryClient,
+        streams: &[ContractEventStream],
+        from: u64,
+        to: u64,
+    ) -> Result<ContractEvents, ChainIoError> {
+        let fetch_operators = streams.iter().any(|s| {
+            matches!(s, ContractEventStream::Operators | ContractEventStream::OperatorMetadata)
+        });
+        let middlewares = |stream: ContractEventStream| {
+            self.middlewares.as_ref().filter(|_| streams.contains(&stream))
+        };
+
+        let (operators, whitelist, operator_sets) = tokio::try_join!(
+            async {
+                if fetch_operators {
+                    registry.get_operator_events(from, to).await
+                } else {
+                    Ok(vec![])
+                }
+            },
+            async {
+                match middlewares(ContractEventStream::Whitelist) {
+                    Some(middlewares) => middlewares.get_whitelist_events(from, to).await,
+                    None => Ok(vec![]),
+                }
+            },
+            async {
+                match middlewares(ContractEventStream::OperatorSets) {
+                    Some(middlewares) => middlewares.get_operator_set_events(from, to).await,
+                    None => Ok(vec![]),
+                }
+            }
+        )?;
+
+        Ok(ContractEvents { operators, whitelist, operator_sets })
+    }
+
+    /// Returns the last ingested block that is still canonical, if any. Contract events after it
+    /// are rolled back, so that they can be re-applied from the canonical chain.
+    async fn rollback_to_canonical(
+        &self,
+        registry: &OperatorsRegistryClient,
+        sync_transaction: &mut Db::SyncTransaction,
+    ) -> Result<Option<u64>, SyncError> {
+        let checkpoints =
+            sync_transaction.get_contract_checkpoints(self.kept_checkpoints()).await?;
+        let Some(&(last, _)) = checkpoints.first() else {
+            return Ok(None);
+        };
+
+        for (number, hash) in checkpoints {
+            if registry.get_block_hash(number).await? == Some(hash) {
+                if number != last {
+                    warn!(
+                        canonical = number,
+                        last, "Execution layer reorg detected, rolling back contract events"
+                    );
+                    sync_transaction.rollback_contract_events(number).await?;
+                }
+
+                return Ok(Some(number));
+            }
+        }
+
+        warn!(last, "No ingested block is canonical anymore, rolling back all contract events");
+        sync_transaction.rollback_contract_events(0).await?;
+
+        Ok(None)
+    }
+
+    /// Applies the metadata of an operator registration event, whose registration was applied
+    /// before its metadata was ingested.
+    async fn apply_operator_metadata(
+        &self,
+        sync_transaction: &mut Db::SyncTransaction,
+        log: RegistryLog,
+    ) -> Result<(), SyncError> {
+        let OperatorsRegistryEvents::OperatorRegistered(event) = log.event else {
+            return Ok(());
+        };
+
+        debug!(block_number = log.block_number, signer = %event.signer, "Applying operator metadata");
+        let metadata = OperatorMetadata::parse(&event.extraData);
+        sync_transaction
+            .update_registration_metadata(log.block_number, log.log_index, metadata.as_ref())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Applies an operator event of the operators registry contract to the database, and records
+    /// it for rollbacks.
+    async fn apply_operator_event(
+        &self,
+        sync_transaction: &mut Db::SyncTransaction,
+        log: RegistryLog,
+    ) -> Result<(), SyncError> {
+        let (block_number, log_index) = (log.block_number, log.log_index);
+        let status_event = |signer, status| OperatorEvent {
+            block_number,
+            log_index,
+            signer,
+            status,
+            rpc_endpoint: None,
+            protocol: None,
+            metadata: None,
+        };
+
+        let event = match log.event {
+            OperatorsRegistryEvents::OperatorRegistered(event) => {
+                let Ok(rpc_endpoint) = event.rpcEndpoint.parse::<Url>() else {
+                    warn!(signer = %event.signer, rpc = %event.rpcEndpoint, "Skipping operator with invalid RPC endpoint");
+                    return Ok(());
+                };
+
+                let protocol = self
+                    .middlewares
+                    .as_ref()
+                    .and_then(|m| m.protocol_of(event.restakingMiddleware));
+                if protocol.is_none() {
+                    debug!(signer = %event.signer, middleware = %event.restakingMiddleware, "Operator registered with an unknown middleware");
+                }
+
+                let metadata = OperatorMetadata::parse(&event.extraData);
+                if metadata.is_none() && !event.extraData.is_empty() {
+                    debug!(signer = %event.signer, extra_data = %event.extraData, "Ignoring malformed operator metadata");
+                }
+
+                let operator = Operator {
+                    signer: event.signer,
+                    rpc_endpoint: rpc_endpoint.clone(),
+                    // Collateral is synced from the restaking middlewares
+                    collateral_tokens: vec![],
+                    collateral_amounts: vec![],
+                    protocol,
+                    source: DataSource::Onchain,
+                    metadata: metadata.clone(),
+                    requires_acceptance: false,
+                    status: OperatorStatus::Active,
+                    // Operator sets are derived from the allocation manager events
+                    operator_sets: vec![],
+                };
+
+                sync_transaction.register_operator(operator).await?;
+
+                OperatorEvent {
+                    rpc_endpoint: Some(rpc_endpoint),
+                    protocol,
+                    metadata,
+                    ..status_event(event.signer, OperatorStatus::Active)
+                }
+            }
+            OperatorsRegistryEvents::OperatorPaused(event) => {
+                status_event(event.signer, OperatorStatus::Paused)
+            }
+            OperatorsRegistryEvents::OperatorUnpaused(event) => {
+                status_event(event.signer, OperatorStatus::Active)
+            }
+            OperatorsRegistryEvents::OperatorDeregistered(event) => {
+                // The signed registrations are kept, but the validators must delegate to another
+                // operator to be served again
+                let count = sync_transaction.mark_for_redelegation(event.signer).await?;
+                if count > 0 {
+                    info!(signer = %event.signer, count, "Operator deregistered, marked validators for re-delegation");
+                }
+
+                status_event(event.signer, OperatorStatus::Deregistered)
+            }
+            // Not requested by the filter
+            _ => return Ok(()),
+        };
+
+        // Registrations are applied above, together with their RPC endpoint
+        if event.rpc_endpoint.is_none() {
+            sync_transaction.update_operator_status(event.signer, event.status).await?;
+        }
+
+        sync_transaction.record_operator_event(&event).await?;
+
+        Ok(())
+    }
+
+    /// Records the RPC endpoints of all operators at the confirmed block of the given epoch
+    /// transition, i.e. its block minus the confirmation depth, so that endpoint changes become
+    /// effective from its epoch.
+    ///
+    /// NOTE: `updateOperatorRpcEndpoint` emits no event, so endpoints are polled once per epoch
+    /// instead of being synced from logs. Changes within an epoch are only observed at its end.
+    async fn sync_rpc_endpoints(
+        &self,
+        sync_transaction: &mut Db::SyncTransaction,
+        transition: &EpochTransition,
+    ) -> Result<(), SyncError> {
+        let Some(registry) = self.operators_registry.as_ref() else {
+            info!("No operators registry configured, skipping...");
+            return Ok(());
+        };
+
+        // Endpoints are read at the confirmed block, like contract events, to avoid recording
+        // endpoints of reorged blocks
+        let block_number = transition.block_number.saturating_sub(self.confirmation_depth);
+
+        let mut count = 0;
+        for (signer, rpc) in registry.get_operator_rpc_endpoints(block_number).await? {
+            let Ok(rpc_endpoint) = rpc.parse::<Url>() else {
+                warn!(%signer, %rpc, "Skipping operator with invalid RPC endpoint");
+                continue;
+            };
+
+            let update = RpcEndpointUpdate {
+                operator: signer,
+                rpc_endpoint,
+                block_number,
+                epoch: transition.epoch,
+            };
+
+            if sync_transaction.update_operator_rpc_endpoint(&update).await? {
+                debug!(%signer, %rpc, epoch = transition.epoch, "Operator RPC endpoint updated");
+                count += 1;
+            }
+        }
+
+        info!(count, "Synced operator RPC endpoints");
+
+        Ok(())
+    }
+
+    /// Syncs the current collateral of all operators that are not deregistered from the restaking
+    /// middlewares.
+    ///
+    /// Operators are queried concurrently, up to [`MAX_CONCURRENT_OPERATOR_QUERIES`] at once.
+    /// Operators whose query fails keep their previous collateral until the next sync.
+    async fn sync_collateral(
+        &self,
+        sync_transaction: &mut Db::SyncTransaction,
+    ) -> Result<(), SyncError> {
+        let Some(middlewares) = self.middlewares.as_ref() else {
+            info!("No restaking middlewares configured, skipping...");
+            return Ok(());
+        };
+
+        let start = std::time::Instant::now();
+
+        let signers = sync_transaction.get_operator_signers().await?;
+        let mut failed = 0;
+
+        // Query the operators in bounded chunks, so that large operator sets don't flood the RPC
+        for chunk in signers.chunks(MAX_CONCURRENT_OPERATOR_QUERIES) {
+            let mut queries = JoinSet::new();
+            for signer in chunk.iter().copied() {
+                let middlewares = middlewares.clone();
+                queries.spawn(async move {
+                    (signer, middlewares.get_operator_collaterals(signer).await)
+                });
+            }
+
+            // A failed query keeps the previous collateral of the operator, instead of aborting
+            // the sync of all other operators
+            for (signer, result) in queries.join_all().await {
+                match result {
+                    Ok((tokens, amounts)) => {
+                        sync_transaction.update_operator_collateral(signer, tokens, amounts).await?
+                    }
+                    Err(e) => {
+                        warn!(%signer, error = ?e, "Failed to query operator collateral");
+                        failed += 1;
+                    }
+                }
+            }
+        }
+
+        info!(count = signers.len(), failed, elapsed = ?start.elapsed(), "Synced operator collateral");
+
+        Ok(())
+    }
+
+    /// Takes a stake snapshot of all operators that are not deregistered, if the operators registry
+    /// epoch of the given block has no snapshots yet. Stake is measured at the epoch start.
+    ///
+    /// Operators are queried concurrently, up to [`MAX_CONCURRENT_OPERATOR_QUERIES`] at once.
+    /// Operators whose query fails have no snapshot for the epoch.
+    ///
+    /// NOTE: epochs that start and end while the syncer is down are not backfilled, as the
+    /// EigenLayer middleware has no historical stake view.
+    async fn sync_stake_snapshots(
+        &self,
+        sync_transaction: &mut Db::SyncTransaction,
+        block_number: u64,
+    ) -> Result<(), SyncError> {
+        let (Some(registry), Some(middlewares)) =
+            (self.operators_registry.as_ref(), self.middlewares.as_ref())
+        else {
+            info!("No operators registry or restaking middlewares configured, skipping...");
+            return Ok(());
+        };
+
+        let epochs = registry.get_epochs().await?;
+        let timestamp = registry
+            .get_block_timestamp(block_number)
+            .await?
+            .ok_or(ChainIoError::BlockNotFound(block_number))?;
+
+        let Some(epoch) = epochs.epoch_at(timestamp) else {
+            return Ok(());
+        };
+
+        if sync_transaction.get_last_stake_snapshot_epoch().await? >= Some(epoch) {
+            return Ok(());
+        }
+
+        let start = std::time::Instant::now();
+        let epoch_start = epochs.epoch_start(epoch);
+
+        let signers = sync_transaction.get_operator_signers().await?;
+        let mut failed = 0;
+
+        for chunk in signers.chunks(MAX_CONCURRENT_OPERATOR_QUERIES) {
+            let mut queries = JoinSet::new();
+            for signer in chunk.iter().copied() {
+                let middlewares = middlewares.clone();
+                queries.spawn(async move {
+                    (signer, middlewares.get_operator_stake_at(signer, epoch_start).await)
+                });
+            }
+
+            // A failed query skips the snapshot of the operator, instead of aborting the
+            // snapshots of all other operators
+            for (signer, result) in queries.join_all().await {
+                let (collateral_tokens, collateral_amounts) = match result {
+                    Ok(stake) => stake,
+                    Err(e) => {
+                        warn!(%signer, epoch, error = ?e, "Failed to query operator stake");
+                        failed += 1;
+                        continue;
+                    }
+                };
+
+                let snapshot = StakeSnapshot {
+                    operator: signer,
+                    epoch,
+                    timestamp: epoch_start,
+                    collateral_tokens,
+                    collateral_amounts,
+                };
+                sync_transaction.insert_stake_snapshot(&snapshot).await?;
+            }
+        }
+
+        info!(epoch, count = signers.len(), failed, elapsed = ?start.elapsed(), "Took operator stake snapshots");
+
+        Ok(())
     }
 
     /// Syncs the lookahead with external data sources.
+    ///
+    /// Sources that fail are skipped for this lookahead, so that one failing source doesn't stall
+    /// the other sources and the on-chain sync. Registrations of the skipped sources are kept, as
+    /// are the recorded source conflicts, which can't be resolved without all sources.
     async fn sync_lookahead(
         &self,
         sync_transaction: &mut Db::SyncTransaction,
         lookahead: Vec<ProposerDuty>,
-    ) {
+    ) -> Result<(), SyncError> {
         let pubkeys = lookahead
             .into_iter()
             .map(|duty| {
@@ -229,40 +1082,42 @@ where
 
         let start = std::time::Instant::now();
 
-        let Some(source) = self.source.as_ref() else {
-            info!("No external source configured, skipping...");
-            return;
-        };
+        if self.sources.is_empty() {
+            info!("No external sources configured, skipping...");
+            return Ok(());
+        }
 
-        let mut entries = loop {
-            match source.get_validators(&pubkeys).await {
-                Ok(registrations) => break registrations,
-                Err(e) => {
-                    error!(error = ?e, "Failed to get validators from {}, retrying...", source.name());
-                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                }
-            }
-        };
+        let (results, failed) = self.query_sources(&pubkeys).await;
+        let (mut entries, conflicts) = merge_entries(results);
 
-        let pubkeys =
-            entries.iter().map(|entry| entry.validator_pubkey.clone()).collect::<Vec<_>>();
+        info!(count = entries.len(), sources = self.sources.len(), failed, elapsed = ?start.elapsed(), "Queried entries from external sources");
 
-        info!(count = entries.len(), elapsed = ?start.elapsed(), "Queried entries from {}", source.name());
+        for conflict in &conflicts {
+            warn!(
+                validator = ?conflict.validator_pubkey,
+                operator = %conflict.operator,
+                claims = ?conflict.claims,
+                "External sources claim different operators for a validator"
+            );
+        }
 
-        let Ok(summaries) = self.beacon_client.get_active_validator_summaries(&pubkeys).await
-        else {
-            error!("Failed to get active validator summaries from the beacon chain");
-            return
-        };
+        if failed == 0 {
+            sync_transaction.update_source_conflicts(&pubkeys, &conflicts).await?;
+        }
+
+        let pubkeys =
+            entries.iter().map(|merged| merged.entry.validator_pubkey.clone()).collect::<Vec<_>>();
+
+        let summaries = self.beacon_client.get_active_validator_summaries(&pubkeys).await?;
 
         // Remove entries that are not present in the beacon chain
-        entries.retain(|entry| {
+        entries.retain(|merged| {
             if !summaries.iter().any(|summary| {
-                summary.validator.public_key == entry.validator_pubkey.to_consensus()
+                summary.validator.public_key == merged.entry.validator_pubkey.to_consensus()
             }) {
                 error!(
                     "Validator not found / active in the beacon chain: {:?}",
-                    entry.validator_pubkey
+                    merged.entry.validator_pubkey
                 );
                 return false;
             }
@@ -270,11 +1125,13 @@ where
             true
         });
 
-        let mut operators = HashMap::new();
+        // Operators keep the entry data of the source with the highest priority
+        let mut operators = HashMap::<_, (u8, Operator)>::new();
 
         let registrations = entries
             .into_iter()
-            .map(|entry| {
+            .map(|merged| {
+                let entry = merged.entry;
                 let validator_index = summaries
                     .iter()
                     .find(|s| s.validator.public_key == entry.validator_pubkey.to_consensus())
@@ -284,46 +1141,125 @@ where
                 let operator = Operator {
                     signer: entry.operator,
                     rpc_endpoint: entry.rpc_endpoint,
-                    // TODO: once collateral is supported, update this
+                    // Collateral is synced from the restaking middlewares
                     collateral_tokens: vec![],
                     collateral_amounts: vec![],
+                    protocol: None,
+                    source: merged.source,
+                    metadata: None,
+                    requires_acceptance: false,
+                    status: OperatorStatus::Active,
+                    // Operator sets are derived from the allocation manager events
+                    operator_sets: vec![],
                 };
 
-                operators.insert(entry.operator, operator);
+                if operators.get(&entry.operator).is_none_or(|(p, _)| *p < merged.priority) {
+                    operators.insert(entry.operator, (merged.priority, operator));
+                }
 
                 Registration {
                     validator_pubkey: entry.validator_pubkey,
                     operator: entry.operator,
                     gas_limit: 10_000,
                     expiry: 0,
+                    nonce: 0,
                     validator_index,
                     signature: None,
+                    source: merged.source,
+                    priority: merged.priority.into(),
+                    needs_redelegation: false,
                 }
             })
             .collect::<Vec<_>>();
 
-        // TODO: retries on transient faults (e.g. network errors)
-        // TODO: use a DB transaction here
-        if let Err(e) = sync_transaction.register_validators(&registrations).await {
-            error!(error = ?e, "Failed to register validators in the database");
+        sync_transaction.register_validators(&registrations).await?;
+
+        for (_, operator) in operators.into_values() {
+            sync_transaction.register_operator(operator).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Queries all external sources concurrently for the given validators, and returns the
+    /// entries of the sources that answered in their configured order, along with the number of
+    /// sources that failed.
+    ///
+    /// # Retries
+    /// Failed queries are retried according to the [`RetryPolicy`] of the syncer. Sources that
+    /// still fail, or whose circuit breaker is open, are logged and skipped.
+    async fn query_sources(&self, pubkeys: &[BlsPublicKey]) -> (Vec<SourceEntries>, usize) {
+        let mut queries = JoinSet::new();
+        for (i, PrioritizedSource { source, priority, breaker }) in self.sources.iter().enumerate()
+        {
+            let (source, priority, breaker) = (Arc::clone(source), *priority, Arc::clone(breaker));
+            let (retry, pubkeys) = (self.retry, pubkeys.to_vec());
+
+            queries.spawn(async move {
+                let result =
+                    retry.retry(&breaker, source.name(), || source.get_validators(&pubkeys)).await;
+
+                let name = source.name().to_owned();
+                (
+                    i,
+                    result.map(|entries| SourceEntries {
+                        name,
+                        source: source.source(),
+                        priority,
+                        entries,
+                    }),
+                )
+            });
         }
 
-        for operator in operators.into_values() {
-            if let Err(e) = sync_transaction.register_operator(operator).await {
-                error!(error = ?e, "Failed to register operator in the database");
+        let mut results = Vec::with_capacity(self.sources.len());
+        let mut failed = 0;
+        for (i, result) in queries.join_all().await {
+            match result {
+                Ok(entries) => {
+                    debug!(count = entries.entries.len(), "Queried entries from {}", entries.name);
+                    results.push((i, entries));
+                }
+                Err(e) => {
+                    let name = self.sources[i].source.name();
+                    warn!(source = name, error = ?e, "Failed to query external source, skipping");
+                    failed += 1;
+                }
             }
         }
+
+        // Restore the configured order, which breaks priority ties
+        results.sort_by_key(|(i, _)| *i);
+
+        (results.into_iter().map(|(_, entries)| entries).collect(), failed)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use alloy::primitives::Address;
-
-    use crate::{db::InMemoryDb, primitives::registry::RegistryEntry, sources::mock::MockSource};
+    use alloy::{
+        network::{EthereumWallet, TransactionBuilder},
+        node_bindings::{Anvil, AnvilInstance},
+        primitives::{Address, Bytes, U256},
+        providers::{Provider, ProviderBuilder},
+        rpc::types::TransactionRequest,
+        signers::local::PrivateKeySigner,
+    };
+
+    use crate::{
+        chainio::abi::OperatorsRegistry,
+        cli::Deployment,
+        db::InMemoryDb,
+        primitives::registry::{DiscoveryFilter, RegistryEntry},
+        sources::mock::MockSource,
+    };
 
     use super::*;
 
+    /// The `OperatorsRegistryV1` artifact, built with `forge build` in the contracts directory.
+    const OPERATORS_REGISTRY_ARTIFACT: &str =
+        "../contracts/out/OperatorsRegistryV1.sol/OperatorsRegistryV1.json";
+
     #[tokio::test]
     async fn test_external_source_sync() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt().with_max_level(tracing::Level::DEBUG).try_init();
@@ -362,7 +1298,7 @@ mod tests {
         db.update_sync_state(SyncStateUpdate { block_number: 0, epoch: epoch - 1, slot: 0 })
             .await?;
 
-        syncer.set_source(source);
+        syncer.add_source(source, 0);
         syncer.spawn();
 
         // Wait for state to change to `Syncing`
@@ -372,7 +1308,358 @@ mod tests {
 
         // Check validator registration
         assert!(!db.get_validators_by_pubkey(&[pubkey.clone()]).await?.is_empty());
-        assert!(!db.get_operators_by_signer(&[operator]).await?.is_empty());
+        let operators = db.get_operators_by_signer(&[operator]).await?;
+        assert_eq!(operators.first().map(|o| o.source), Some(DataSource::Lido));
+
+        Ok(())
+    }
+
+    /// A source whose queries always fail.
+    struct FailingSource;
+
+    #[async_trait::async_trait]
+    impl ExternalSource for FailingSource {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn source(&self) -> DataSource {
+            DataSource::Lido
+        }
+
+        async fn get_validators(
+            &self,
+            _pubkeys: &[BlsPublicKey],
+        ) -> Result<Vec<RegistryEntry>, SourceError> {
+            Err(SourceError::Other("unavailable".to_owned()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_sources_skips_failed_sources() {
+        let (mut syncer, _handle) = Syncer::new("http://localhost:0", InMemoryDb::default());
+        syncer.set_retry_policy(RetryPolicy { max_attempts: 1, ..Default::default() });
+
+        let entry = RegistryEntry {
+            validator_pubkey: BlsPublicKey::random(),
+            operator: Address::random(),
+            gas_limit: 0,
+            rpc_endpoint: "https://rick.com".parse().unwrap(),
+        };
+
+        let mut source = MockSource::new();
+        source.add_entry(entry.clone());
+        syncer.add_source(FailingSource, 1);
+        syncer.add_source(source, 0);
+
+        // The failing source is skipped, and the entries of the other source are kept
+        let (results, failed) = syncer.query_sources(&[entry.validator_pubkey.clone()]).await;
+        assert_eq!(failed, 1);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "mock");
+        assert_eq!(results[0].entries.len(), 1);
+        assert_eq!(results[0].entries[0].validator_pubkey, entry.validator_pubkey);
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_sync_state() -> eyre::Result<()> {
+        let db = InMemoryDb::default();
+        let (mut syncer, _handle) = Syncer::new("http://localhost:5052", db.clone());
+        syncer.set_bootstrap(SyncBootstrap::Checkpoint { epoch: 100 });
+
+        assert!(db.get_sync_state().await?.is_none());
+
+        let state = syncer.load_sync_state().await?;
+        assert_eq!((state.epoch, state.slot), (100, 3200));
+
+        // The bootstrapped state is persisted, so the bootstrap mode no longer applies
+        syncer.set_bootstrap(SyncBootstrap::Checkpoint { epoch: 200 });
+        assert_eq!(syncer.load_sync_state().await?.epoch, 100);
+        assert_eq!(db.get_sync_state().await?.map(|s| s.epoch), Some(100));
+
+        Ok(())
+    }
+
+    /// Spawns a local Anvil instance, and returns it with the bytecode of the operators registry.
+    /// Returns `None` if the contract artifacts are missing or Anvil is not installed, in which
+    /// case the test should be skipped.
+    fn spawn_anvil() -> eyre::Result<Option<(AnvilInstance, Bytes)>> {
+        let _ = tracing_subscriber::fmt().with_max_level(tracing::Level::DEBUG).try_init();
+
+        let Some(bytecode) = operators_registry_bytecode()? else {
+            tracing::warn!(
+                "Skipping test because of missing contract artifacts, run `forge build`"
+            );
+            return Ok(None)
+        };
+
+        let Ok(anvil) = Anvil::new().try_spawn() else {
+            tracing::warn!("Skipping test because anvil is not installed");
+            return Ok(None)
+        };
+
+        Ok(Some((anvil, bytecode)))
+    }
+
+    /// Reads the bytecode of the `OperatorsRegistryV1` contract from its artifact, if built.
+    fn operators_registry_bytecode() -> eyre::Result<Option<Bytes>> {
+        let Ok(artifact) = std::fs::read_to_string(OPERATORS_REGISTRY_ARTIFACT) else {
+            return Ok(None)
+        };
+
+        let artifact: serde_json::Value = serde_json::from_str(&artifact)?;
+        Ok(Some(serde_json::from_value(artifact["bytecode"]["object"].clone())?))
+    }
+
+    /// Returns a wallet with the first two anvil accounts. The first one owns the registry, the
+    /// second one acts as its restaking middleware.
+    fn test_wallet(anvil: &AnvilInstance) -> EthereumWallet {
+        let owner: PrivateKeySigner = anvil.keys()[0].clone().into();
+        let middleware: PrivateKeySigner = anvil.keys()[1].clone().into();
+
+        let mut wallet = EthereumWallet::new(owner);
+        wallet.register_signer(middleware);
+        wallet
+    }
+
+    /// Deploys and initializes the operators registry on the anvil chain, with the second anvil
+    /// account as its restaking middleware.
+    async fn deploy_operators_registry(
+        anvil: &AnvilInstance,
+        bytecode: Bytes,
+        epoch_duration: u64,
+    ) -> eyre::Result<Deployment> {
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(test_wallet(anvil))
+            .on_http(anvil.endpoint_url());
+
+        let tx = TransactionRequest::default().with_deploy_code(bytecode);
+        let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+        let address = receipt.contract_address.expect("contract is deployed");
+
+        let registry = OperatorsRegistry::new(address, &provider);
+        registry
+            .initialize(anvil.addresses()[0], epoch_duration.try_into()?)
+            .send()
+            .await?
+            .watch()
+            .await?;
+        registry
+            .updateRestakingMiddleware("SYMBIOTIC".to_string(), anvil.addresses()[1])
+            .send()
+            .await?
+            .watch()
+            .await?;
+
+        Ok(Deployment::new(address, receipt.block_number.unwrap_or_default()))
+    }
+
+    /// Syncs contract events up to the given block number, and commits the sync.
+    async fn sync_contract_events_to(
+        syncer: &mut Syncer<InMemoryDb>,
+        block_number: u64,
+    ) -> eyre::Result<()> {
+        let mut tx = syncer.db.begin_sync().await?;
+        syncer.sync_contract_events(&mut tx, block_number).await?;
+        syncer.finalize_sync(tx, SyncStateUpdate { block_number, epoch: 0, slot: 0 }).await?;
+
+        Ok(())
+    }
+
+    /// Returns the status of the operator with the given signer, if present in the database.
+    async fn operator_status(
+        db: &InMemoryDb,
+        signer: Address,
+    ) -> eyre::Result<Option<OperatorStatus>> {
+        Ok(db.get_operators_by_signer(&[signer]).await?.first().map(|o| o.status))
+    }
+
+    #[tokio::test]
+    async fn test_sync_contract_events() -> eyre::Result<()> {
+        let Some((anvil, bytecode)) = spawn_anvil()? else { return Ok(()) };
+
+        let epoch_duration = 60;
+        let deployment = deploy_operators_registry(&anvil, bytecode, epoch_duration).await?;
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(test_wallet(&anvil))
+            .on_http(anvil.endpoint_url());
+        let registry = OperatorsRegistry::new(deployment.address, &provider);
+        let middleware = anvil.addresses()[1];
+
+        let (active, paused, deregistered) =
+            (Address::random(), Address::random(), Address::random());
+
+        for signer in [active, paused, deregistered] {
+            registry
+                .registerOperator(signer, "https://rpc.bolt.test".to_string(), String::new())
+                .from(middleware)
+                .send()
+                .await?
+                .watch()
+                .await?;
+        }
+
+        for signer in [paused, deregistered] {
+            registry.pauseOperator(signer).from(middleware).send().await?.watch().await?;
+        }
+
+        // Operators can only be deregistered or unpaused after the immutable period
+        provider
+            .raw_request::<_, serde_json::Value>("evm_increaseTime".into(), (3 * epoch_duration,))
+            .await?;
+
+        registry.deregisterOperator(deregistered).from(middleware).send().await?.watch().await?;
+
+        let db = InMemoryDb::default();
+        let (mut syncer, _handle) = Syncer::new("http://localhost:5052", db.clone());
+        syncer.set_operators_registry(
+            OperatorsRegistryClient::new(anvil.endpoint_url(), deployment),
+            0,
+        );
+
+        sync_contract_events_to(&mut syncer, provider.get_block_number().await?).await?;
+
+        let onchain = DiscoveryFilter { source: Some(DataSource::Onchain), ..Default::default() };
+        assert_eq!(db.list_operators(onchain).await?.len(), 3);
+        assert_eq!(operator_status(&db, active).await?, Some(OperatorStatus::Active));
+        assert_eq!(operator_status(&db, paused).await?, Some(OperatorStatus::Paused));
+        assert_eq!(operator_status(&db, deregistered).await?, Some(OperatorStatus::Deregistered));
+
+        // Only events after the last synced block are applied
+        registry.unpauseOperator(paused).from(middleware).send().await?.watch().await?;
+
+        sync_contract_events_to(&mut syncer, provider.get_block_number().await?).await?;
+
+        assert_eq!(operator_status(&db, paused).await?, Some(OperatorStatus::Active));
+        assert_eq!(operator_status(&db, deregistered).await?, Some(OperatorStatus::Deregistered));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_contract_events_reorg() -> eyre::Result<()> {
+        let Some((anvil, bytecode)) = spawn_anvil()? else { return Ok(()) };
+
+        let deployment = deploy_operators_registry(&anvil, bytecode, 60).await?;
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(test_wallet(&anvil))
+            .on_http(anvil.endpoint_url());
+        let registry = OperatorsRegistry::new(deployment.address, &provider);
+        let middleware = anvil.addresses()[1];
+
+        let db = InMemoryDb::default();
+        let (mut syncer, _handle) = Syncer::new("http://localhost:5052", db.clone());
+        syncer.set_operators_registry(
+            OperatorsRegistryClient::new(anvil.endpoint_url(), deployment),
+            0,
+        );
+
+        let (kept, reorged, replacement) =
+            (Address::random(), Address::random(), Address::random());
+        let rpc = "https://rpc.bolt.test".to_string();
+
+        registry
+            .registerOperator(kept, rpc.clone(), String::new())
+            .from(middleware)
+            .send()
+            .await?
+            .watch()
+            .await?;
+        sync_contract_events_to(&mut syncer, provider.get_block_number().await?).await?;
+
+        let snapshot: U256 = provider.raw_request("evm_snapshot".into(), ()).await?;
+
+        registry
+            .registerOperator(reorged, rpc.clone(), String::new())
+            .from(middleware)
+            .send()
+            .await?
+            .watch()
+            .await?;
+        registry.pauseOperator(kept).from(middleware).send().await?.watch().await?;
+
+        let reorged_head = provider.get_block_number().await?;
+        sync_contract_events_to(&mut syncer, reorged_head).await?;
+
+        assert_eq!(operator_status(&db, kept).await?, Some(OperatorStatus::Paused));
+        assert_eq!(operator_status(&db, reorged).await?, Some(OperatorStatus::Active));
+
+        // Reorg the synced blocks: revert to the snapshot and build a different chain at least as
+        // high as the synced one.
+        let reverted: bool = provider.raw_request("evm_revert".into(), (snapshot,)).await?;
+        assert!(reverted);
+
+        registry
+            .registerOperator(replacement, rpc, String::new())
+            .from(middleware)
+            .send()
+            .await?
+            .watch()
+            .await?;
+        while provider.get_block_number().await? < reorged_head {
+            provider.raw_request::<_, serde_json::Value>("evm_mine".into(), ()).await?;
+        }
+
+        sync_contract_events_to(&mut syncer, provider.get_block_number().await?).await?;
+
+        // Events of the reorged blocks are rolled back, and the canonical ones applied
+        assert_eq!(operator_status(&db, kept).await?, Some(OperatorStatus::Active));
+        assert_eq!(operator_status(&db, reorged).await?, None);
+        assert_eq!(operator_status(&db, replacement).await?, Some(OperatorStatus::Active));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_backfill_contract_events() -> eyre::Result<()> {
+        let Some((anvil, bytecode)) = spawn_anvil()? else { return Ok(()) };
+
+        let deployment = deploy_operators_registry(&anvil, bytecode, 60).await?;
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(test_wallet(&anvil))
+            .on_http(anvil.endpoint_url());
+        let registry = OperatorsRegistry::new(deployment.address, &provider);
+
+        let signers = (0..3).map(|_| Address::random()).collect::<Vec<_>>();
+        for signer in &signers {
+            registry
+                .registerOperator(*signer, "https://rpc.bolt.test".to_string(), String::new())
+                .from(anvil.addresses()[1])
+                .send()
+                .await?
+                .watch()
+                .await?;
+        }
+
+        let db = InMemoryDb::default();
+        let (mut syncer, _handle) = Syncer::new("http://localhost:5052", db.clone());
+        syncer.set_operators_registry(
+            OperatorsRegistryClient::new(anvil.endpoint_url(), deployment),
+            1,
+        );
+
+        syncer.backfill_contract_events(&SyncStateUpdate::default()).await?;
+
+        // The last block is not confirmed yet
+        let head = provider.get_block_number().await?;
+        let checkpoints = db.begin_sync().await?.get_contract_checkpoints(1).await?;
+        assert_eq!(checkpoints.first().map(|(n, _)| *n), Some(head - 1));
+
+        assert_eq!(db.get_operators_by_signer(&signers[..2]).await?.len(), 2);
+        assert!(db.get_operators_by_signer(&signers[2..]).await?.is_empty());
+
+        // Backfilling again resumes from the last ingested block
+        provider.raw_request::<_, serde_json::Value>("evm_mine".into(), ()).await?;
+        syncer.backfill_contract_events(&SyncStateUpdate::default()).await?;
+
+        assert_eq!(db.get_operators_by_signer(&signers).await?.len(), 3);
 
         Ok(())
     }